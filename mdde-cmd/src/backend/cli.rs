@@ -0,0 +1,685 @@
+use super::prune::{self, BuildCacheUsage, ContainerUsage, ImageUsage, VolumeUsage};
+use super::service::{COMPOSE_PROJECT_LABEL, COMPOSE_WORKING_DIR_LABEL};
+use super::stats::parse_bytes;
+use super::{
+    BuildOutput, ContainerBackend, ContainerDetails, ContainerStats, DiskUsage, ExecOutput,
+    LogEntry, OutputStream, PruneReport, PruneScope, PruneTarget, RunOptions,
+};
+use crate::compose::ComposeProject;
+use crate::docker::{DockerCommand, DockerError};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+/// 基于容器引擎命令行（`docker` 或 `podman`）的后端
+///
+/// Engine API 不可用时（如 Windows 命名管道、远程上下文）作为回退方案。
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CliBackend;
+
+impl CliBackend {
+    fn output(args: &[&str]) -> Result<Output, DockerError> {
+        DockerCommand::command()
+            .args(args)
+            .output()
+            .map_err(|e| DockerError::CommandFailed(e.to_string()))
+    }
+
+    /// 用 `list` 列出 ID，再用 `inspect` 一次获取所有对象的详细信息
+    fn inspect_all(list: &[&str], inspect: &[&str]) -> Result<Vec<Value>, DockerError> {
+        let output = Self::output(list)?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "{}: {}",
+                list.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut ids: Vec<&str> = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .collect();
+        // `image ls -a` 会为每个标签列出一次同一镜像
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut args = inspect.to_vec();
+        args.extend(ids);
+        let output = Self::output(&args)?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "{}: {}",
+                inspect.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| DockerError::OutputParseFailed(e.to_string()))
+    }
+}
+
+/// 根据命令行的错误输出推断错误类型，兼容 docker 与 podman 的措辞
+fn classify_error(container: &str, stderr: &[u8], action: &str) -> DockerError {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lower = stderr.to_ascii_lowercase();
+    if lower.contains("no such container") || lower.contains("no such object") {
+        DockerError::ContainerNotFound(container.to_string())
+    } else if lower.contains("is not running") || lower.contains("container state improper") {
+        DockerError::ContainerNotRunning(container.to_string())
+    } else {
+        DockerError::CommandFailed(format!("{}: {}", action, stderr.trim()))
+    }
+}
+
+/// `exec` 的结果，引擎自身的错误转换为 [`DockerError`]，命令的非零退出码原样返回
+///
+/// 两者都会反映在退出码上，需要按错误信息区分：Docker 的错误以 `Error response from daemon` 开头，
+/// Podman 以退出码 125 和 `Error:` 开头的错误信息表示引擎出错。
+fn exec_output(container: &str, output: Output) -> Result<ExecOutput, DockerError> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let code = output.status.code();
+    let engine_error = stderr.starts_with("Error response from daemon")
+        || (code == Some(125) && stderr.starts_with("Error:"));
+    if !output.status.success() && engine_error {
+        return Err(classify_error(container, &output.stderr, "执行命令失败"));
+    }
+
+    Ok(ExecOutput {
+        exit_code: code.unwrap_or(-1) as i64,
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: stderr.to_string(),
+    })
+}
+
+fn lines_to_entries(bytes: &[u8], stream: OutputStream) -> impl Iterator<Item = LogEntry> + '_ {
+    std::str::from_utf8(bytes)
+        .unwrap_or_default()
+        .lines()
+        .map(move |line| LogEntry {
+            stream,
+            message: line.to_string(),
+        })
+}
+
+fn labels(value: &Value) -> HashMap<String, String> {
+    value
+        .as_object()
+        .map(|labels| {
+            labels
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// `container inspect --size` 的一项
+fn container_usage(value: &Value) -> ContainerUsage {
+    ContainerUsage {
+        id: string(&value["Id"]),
+        name: value["Name"]
+            .as_str()
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string(),
+        image_id: string(&value["Image"]),
+        state: string(&value["State"]["Status"]),
+        labels: labels(&value["Config"]["Labels"]),
+        size: value["SizeRw"].as_u64(),
+        volumes: value["Mounts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|mount| mount["Type"] == "volume")
+            .filter_map(|mount| mount["Name"].as_str().map(str::to_string))
+            .collect(),
+        networks: value["NetworkSettings"]["Networks"]
+            .as_object()
+            .map(|networks| networks.keys().cloned().collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// `image inspect` 的一项
+fn image_usage(value: &Value) -> ImageUsage {
+    ImageUsage {
+        id: string(&value["Id"]),
+        tags: value["RepoTags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str().map(str::to_string))
+            .collect(),
+        labels: labels(&value["Config"]["Labels"]),
+        size: value["Size"].as_u64(),
+    }
+}
+
+/// `system df --format '{{json .}}'` 中构建缓存可回收的空间
+fn build_cache_usage(output: &str) -> Option<BuildCacheUsage> {
+    let row = output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|row| row["Type"] == "Build Cache")?;
+    // 形如 `1.2GB (100%)`
+    let reclaimable = row["Reclaimable"].as_str()?.split_whitespace().next()?;
+    let size = parse_bytes(reclaimable).filter(|size| *size > 0)?;
+    Some(BuildCacheUsage {
+        id: "build-cache".to_string(),
+        size: Some(size),
+        in_use: false,
+    })
+}
+
+/// 解析 `docker * prune` 的输出
+fn parse_prune_output(output: &str) -> PruneReport {
+    let mut report = PruneReport::default();
+    for line in output.lines().map(str::trim) {
+        if let Some(size) = line.strip_prefix("Total reclaimed space:") {
+            report.space_reclaimed = parse_bytes(size).unwrap_or_default();
+        } else if !line.is_empty() && !line.ends_with(':') && !line.starts_with("WARNING") {
+            report.deleted.push(line.to_string());
+        }
+    }
+    report
+}
+
+impl ContainerBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "docker-cli"
+    }
+
+    fn container_exists(&self, name: &str) -> Result<bool, DockerError> {
+        DockerCommand::container_exists(name)
+    }
+
+    fn container_running(&self, name: &str) -> Result<bool, DockerError> {
+        DockerCommand::container_running(name)
+    }
+
+    fn start_container(&self, name: &str) -> Result<(), DockerError> {
+        DockerCommand::start_container(name).map(|_| ())
+    }
+
+    fn stop_container(&self, name: &str) -> Result<(), DockerError> {
+        DockerCommand::stop_container(name).map(|_| ())
+    }
+
+    fn restart_container(&self, name: &str) -> Result<(), DockerError> {
+        DockerCommand::restart_container(name).map(|_| ())
+    }
+
+    fn exec(&self, container: &str, command: &str) -> Result<ExecOutput, DockerError> {
+        let output = Self::output(&["exec", container, "sh", "-c", command])?;
+        exec_output(container, output)
+    }
+
+    fn exec_stream(&self, container: &str, command: &str) -> Result<(), DockerError> {
+        DockerCommand::exec_command_stream(container, command)
+    }
+
+    fn exec_interactive(&self, container: &str, shell: &str) -> Result<(), DockerError> {
+        DockerCommand::exec_interactive(container, shell)
+    }
+
+    fn logs(&self, container: &str, tail: Option<usize>) -> Result<Vec<LogEntry>, DockerError> {
+        let tail = tail.map(|n| n.to_string());
+        let mut args = vec!["logs"];
+        if let Some(lines) = &tail {
+            args.push("--tail");
+            args.push(lines);
+        }
+        args.push(container);
+
+        let output = Self::output(&args)?;
+        if !output.status.success() {
+            return Err(classify_error(
+                container,
+                &output.stderr,
+                "获取容器日志失败",
+            ));
+        }
+
+        // 命令行无法保留 stdout 与 stderr 的交错顺序
+        Ok(lines_to_entries(&output.stdout, OutputStream::Stdout)
+            .chain(lines_to_entries(&output.stderr, OutputStream::Stderr))
+            .collect())
+    }
+
+    fn follow_logs(&self, container: &str, tail: Option<usize>) -> Result<(), DockerError> {
+        let mut cmd = DockerCommand::command();
+        cmd.arg("logs").arg("-f");
+        if let Some(lines) = tail {
+            cmd.arg("--tail").arg(lines.to_string());
+        }
+        cmd.arg(container)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+
+        let status = cmd
+            .status()
+            .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(DockerError::CommandFailed(format!(
+                "跟踪日志失败，退出代码: {}",
+                status.code().unwrap_or(-1)
+            )))
+        }
+    }
+
+    fn list_containers(&self) -> Result<Vec<String>, DockerError> {
+        let output = Self::output(&["ps", "--all", "--quiet", "--no-trunc"])?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "获取容器列表失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn stats(&self, container: &str) -> Result<ContainerStats, DockerError> {
+        let output = Self::output(&["stats", "--no-stream", "--format", "{{json .}}", container])?;
+        if !output.status.success() {
+            return Err(classify_error(
+                container,
+                &output.stderr,
+                "获取容器资源使用情况失败",
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .ok_or_else(|| DockerError::OutputParseFailed("docker stats 没有输出".to_string()))?;
+        ContainerStats::from_cli_json(line)
+    }
+
+    fn inspect(&self, container: &str) -> Result<ContainerDetails, DockerError> {
+        let output = Self::output(&["inspect", "--type", "container", container])?;
+        if !output.status.success() {
+            return Err(classify_error(
+                container,
+                &output.stderr,
+                "获取容器信息失败",
+            ));
+        }
+
+        let values: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| DockerError::OutputParseFailed(e.to_string()))?;
+        let value = values
+            .into_iter()
+            .next()
+            .ok_or_else(|| DockerError::ContainerNotFound(container.to_string()))?;
+
+        ContainerDetails::from_inspect_json(value)
+    }
+
+    fn remove_container(&self, name: &str, force: bool) -> Result<(), DockerError> {
+        DockerCommand::rm_container(name, force).map(|_| ())
+    }
+
+    fn build_image(&self, context: &Path, tag: &str) -> Result<BuildOutput, DockerError> {
+        let context = context.to_string_lossy();
+        let output = Self::output(&["build", "-q", "-t", tag, &context])?;
+
+        if output.status.success() {
+            let image_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            Ok(BuildOutput {
+                tag: tag.to_string(),
+                image_id: Some(image_id).filter(|id| !id.is_empty()),
+            })
+        } else {
+            Err(DockerError::CommandFailed(format!(
+                "构建镜像失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+
+    fn pull_image(&self, image: &str) -> Result<(), DockerError> {
+        DockerCommand::pull_image(image).map(|_| ())
+    }
+
+    fn remove_image(&self, image: &str, force: bool) -> Result<(), DockerError> {
+        let mut args = vec!["image", "rm"];
+        if force {
+            args.push("--force");
+        }
+        args.push(image);
+
+        let output = Self::output(&args)?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "删除镜像失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    fn remove_volume(&self, name: &str) -> Result<(), DockerError> {
+        let output = Self::output(&["volume", "rm", name])?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "删除卷失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    fn remove_network(&self, name: &str) -> Result<(), DockerError> {
+        let output = Self::output(&["network", "rm", name])?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "删除网络失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+
+    fn run_container(&self, options: &RunOptions) -> Result<String, DockerError> {
+        let mut cmd = DockerCommand::command();
+        cmd.arg("run");
+        if options.detach {
+            cmd.arg("-d");
+        }
+        for port in &options.ports {
+            cmd.arg("-p").arg(port);
+        }
+        for volume in &options.volumes {
+            cmd.arg("-v").arg(volume);
+        }
+        for env in &options.env {
+            cmd.arg("-e").arg(env);
+        }
+        cmd.arg("--name").arg(&options.name).arg(&options.image);
+
+        let output = cmd
+            .output()
+            .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+
+        // run 的退出码 125 表示引擎自身出错，前台运行时其他退出码来自容器中的命令
+        let code = output.status.code();
+        if !output.status.success() && (options.detach || code == Some(125)) {
+            return Err(DockerError::CommandFailed(format!(
+                "启动容器失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "容器退出，退出代码: {}",
+                code.unwrap_or(-1)
+            )));
+        }
+
+        if options.detach {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            // 前台运行时标准输出是容器自身的输出，需要单独查询容器 ID
+            Ok(self.inspect(&options.name)?.id)
+        }
+    }
+
+    fn compose_up(&self, project: &ComposeProject, detach: bool) -> Result<(), DockerError> {
+        if detach {
+            DockerCommand::compose_stream(project, &["up", "-d"])
+        } else {
+            DockerCommand::compose_stream(project, &["up"])
+        }
+    }
+
+    fn compose_down(
+        &self,
+        project: &ComposeProject,
+        remove_volumes: bool,
+    ) -> Result<(), DockerError> {
+        if remove_volumes {
+            DockerCommand::compose_output(project, &["down", "--volumes"]).map(|_| ())
+        } else {
+            DockerCommand::compose_output(project, &["down"]).map(|_| ())
+        }
+    }
+
+    fn compose_config(&self, project: &ComposeProject) -> Result<String, DockerError> {
+        DockerCommand::compose_output(project, &["config"])
+    }
+
+    fn compose_container_ids(&self, project: &ComposeProject) -> Result<Vec<String>, DockerError> {
+        let output = DockerCommand::compose_output(project, &["ps", "--all", "--quiet"])?;
+        Ok(output
+            .lines()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn compose_project_exists(&self, name: &str, working_dir: &Path) -> Result<bool, DockerError> {
+        let project = format!("label={}={}", COMPOSE_PROJECT_LABEL, name);
+        let dir = format!(
+            "label={}={}",
+            COMPOSE_WORKING_DIR_LABEL,
+            working_dir.display()
+        );
+        let output = Self::output(&[
+            "ps", "--all", "--quiet", "--filter", &project, "--filter", &dir,
+        ])?;
+        if !output.status.success() {
+            return Err(DockerError::CommandFailed(format!(
+                "获取容器列表失败: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+    }
+
+    fn prune(&self, target: PruneTarget, scope: PruneScope) -> Result<PruneReport, DockerError> {
+        let commands: &[&[&str]] = match (target, scope) {
+            (PruneTarget::System, PruneScope::Global) => &[&["system", "prune", "-a", "-f"]],
+            // system prune 不支持按标签过滤构建缓存，只清理带标签的容器、网络和镜像
+            (PruneTarget::System, PruneScope::Managed) => &[
+                &["container", "prune", "-f"],
+                &["network", "prune", "-f"],
+                &["image", "prune", "-a", "-f"],
+            ],
+            (PruneTarget::Images, PruneScope::Global) => &[&["image", "prune", "-f"]],
+            (PruneTarget::Images, PruneScope::Managed) => &[&["image", "prune", "-a", "-f"]],
+            (PruneTarget::Containers, _) => &[&["container", "prune", "-f"]],
+            (PruneTarget::Volumes, _) => return prune::remove_unused_volumes(self, scope),
+        };
+        let filter = scope.label_filter().map(|label| format!("label={}", label));
+
+        let mut report = PruneReport::default();
+        for command in commands {
+            let mut args = command.to_vec();
+            if let Some(filter) = &filter {
+                args.extend(["--filter", filter]);
+            }
+
+            let output = Self::output(&args)?;
+            if !output.status.success() {
+                return Err(DockerError::CommandFailed(format!(
+                    "清理失败: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            let part = parse_prune_output(&String::from_utf8_lossy(&output.stdout));
+            report.deleted.extend(part.deleted);
+            report.space_reclaimed += part.space_reclaimed;
+        }
+        Ok(report)
+    }
+
+    fn disk_usage(&self) -> Result<DiskUsage, DockerError> {
+        let containers = Self::inspect_all(
+            &["container", "ls", "--all", "--quiet", "--no-trunc"],
+            &["container", "inspect", "--size"],
+        )?;
+        let images = Self::inspect_all(
+            &["image", "ls", "--all", "--quiet", "--no-trunc"],
+            &["image", "inspect"],
+        )?;
+        // 命令行不提供单个卷的大小
+        let volumes = Self::inspect_all(&["volume", "ls", "--quiet"], &["volume", "inspect"])?;
+        let networks = Self::inspect_all(
+            &["network", "ls", "--quiet", "--no-trunc"],
+            &["network", "inspect"],
+        )?;
+        // 命令行只提供构建缓存的汇总，podman 没有这一项
+        let build_cache = Self::output(&["system", "df", "--format", "{{json .}}"])
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| build_cache_usage(&String::from_utf8_lossy(&output.stdout)));
+
+        Ok(DiskUsage {
+            containers: containers.iter().map(container_usage).collect(),
+            images: images.iter().map(image_usage).collect(),
+            volumes: volumes
+                .iter()
+                .map(|volume| VolumeUsage {
+                    name: string(&volume["Name"]),
+                    labels: labels(&volume["Labels"]),
+                    size: None,
+                })
+                .collect(),
+            networks: DiskUsage::networks_from_json(&Value::Array(networks)),
+            build_cache: build_cache.into_iter().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prune_output() {
+        let output = "Deleted Images:\nuntagged: node:18\ndeleted: sha256:abc\n\nTotal reclaimed space: 1.5GB\n";
+        let report = parse_prune_output(output);
+        assert_eq!(
+            report.deleted,
+            vec!["untagged: node:18", "deleted: sha256:abc"]
+        );
+        assert_eq!(report.space_reclaimed, 1_500_000_000);
+
+        assert_eq!(parse_bytes("0B"), Some(0));
+        assert_eq!(parse_bytes("512kB"), Some(512_000));
+        assert_eq!(parse_bytes("abc"), None);
+    }
+
+    #[test]
+    fn test_parse_disk_usage() {
+        let container = container_usage(&serde_json::json!({
+            "Id": "abc",
+            "Name": "/demo",
+            "Image": "sha256:def",
+            "State": {"Status": "exited"},
+            "Config": {"Labels": {"mdde.managed": "true"}},
+            "SizeRw": 2048,
+            "Mounts": [
+                {"Type": "bind", "Source": "/work"},
+                {"Type": "volume", "Name": "demo_data"}
+            ]
+        }));
+        assert_eq!(container.name, "demo");
+        assert_eq!(container.labels["mdde.managed"], "true");
+        assert_eq!(container.size, Some(2048));
+        assert_eq!(container.volumes, vec!["demo_data"]);
+
+        let image = image_usage(&serde_json::json!({
+            "Id": "sha256:def", "RepoTags": [], "Size": 100, "Config": {"Labels": null}
+        }));
+        assert!(image.is_dangling());
+        assert!(image.labels.is_empty());
+
+        let output = "{\"Type\":\"Images\",\"Reclaimable\":\"0B (0%)\"}\n{\"Type\":\"Build Cache\",\"Reclaimable\":\"1.5GB (100%)\"}\n";
+        assert_eq!(build_cache_usage(output).unwrap().size, Some(1_500_000_000));
+        assert!(build_cache_usage("").is_none());
+    }
+
+    #[test]
+    fn test_classify_podman_errors() {
+        let stderr = b"Error: no container with name or ID \"demo\" found: no such container";
+        assert!(matches!(
+            classify_error("demo", stderr, "exec"),
+            DockerError::ContainerNotFound(_)
+        ));
+
+        let stderr =
+            b"Error: can only create exec sessions on running containers: container state improper";
+        assert!(matches!(
+            classify_error("demo", stderr, "exec"),
+            DockerError::ContainerNotRunning(_)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exec_output_separates_engine_errors() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+
+        let output = |code: i32, stderr: &str| Output {
+            status: ExitStatus::from_raw(code << 8),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        };
+
+        // Podman 在容器已停止或不存在时以 125 退出
+        let stopped = output(
+            125,
+            "Error: can only create exec sessions on running containers: container state improper\n",
+        );
+        assert!(matches!(
+            exec_output("demo", stopped),
+            Err(DockerError::ContainerNotRunning(_))
+        ));
+        let missing = output(
+            125,
+            "Error: no container with name or ID \"demo\" found: no such container\n",
+        );
+        assert!(matches!(
+            exec_output("demo", missing),
+            Err(DockerError::ContainerNotFound(_))
+        ));
+        let docker = output(
+            1,
+            "Error response from daemon: container demo is not running\n",
+        );
+        assert!(matches!(
+            exec_output("demo", docker),
+            Err(DockerError::ContainerNotRunning(_))
+        ));
+
+        // 命令自身的失败返回退出码
+        let failed = exec_output("demo", output(1, "Error: Cannot find module 'app'\n")).unwrap();
+        assert_eq!(failed.exit_code, 1);
+        let failed = exec_output("demo", output(125, "no such file\n")).unwrap();
+        assert_eq!(failed.exit_code, 125);
+    }
+}
@@ -0,0 +1,814 @@
+use super::prune;
+use super::service::{COMPOSE_PROJECT_LABEL, COMPOSE_WORKING_DIR_LABEL};
+use super::unix_http::{self, Response};
+use super::{
+    BuildOutput, CliBackend, ContainerBackend, ContainerDetails, ContainerStats, DiskUsage,
+    ExecOutput, LogEntry, OutputStream, PruneReport, PruneScope, PruneTarget, RunOptions,
+};
+use crate::compose::ComposeProject;
+use crate::container_engine::{self, ContainerEngine};
+use crate::docker::DockerError;
+use crate::error::MddeError;
+use crate::port_mapping::PortMapping;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// 通过本地 unix 套接字直接调用 Docker Engine HTTP API 的后端
+///
+/// 返回结构化数据，不依赖命令行输出格式和语言环境。
+/// Podman 的 Docker 兼容套接字提供相同的 API，也由该后端驱动。
+/// 交互式 exec 需要 TTY 劫持，这部分仍交给命令行处理。
+#[derive(Debug, Clone)]
+pub struct EngineApiBackend {
+    socket: PathBuf,
+}
+
+impl EngineApiBackend {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// 根据当前容器引擎定位套接字，非 unix 地址返回 None
+    pub fn from_env() -> Option<Self> {
+        Self::for_engine(container_engine::current())
+    }
+
+    /// 根据环境变量或引擎的默认路径定位套接字
+    ///
+    /// Docker 读取 `DOCKER_HOST`，Podman 优先读取 `CONTAINER_HOST`。
+    pub fn for_engine(engine: ContainerEngine) -> Option<Self> {
+        let vars: &[&str] = match engine {
+            ContainerEngine::Docker => &["DOCKER_HOST"],
+            ContainerEngine::Podman => &["CONTAINER_HOST", "DOCKER_HOST"],
+        };
+
+        for var in vars {
+            if let Ok(host) = std::env::var(var) {
+                if !host.is_empty() {
+                    let socket = PathBuf::from(host.strip_prefix("unix://")?);
+                    return socket.exists().then(|| Self::new(socket));
+                }
+            }
+        }
+
+        engine
+            .socket_candidates()
+            .into_iter()
+            .find(|socket| socket.exists())
+            .map(Self::new)
+    }
+
+    /// 套接字路径
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// 检查引擎是否可以访问
+    pub fn ping(&self) -> bool {
+        matches!(self.call("GET", "/_ping", None), Ok(response) if response.is_success())
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> Result<Response, DockerError> {
+        match body {
+            Some(body) => {
+                let data = serde_json::to_vec(&body)
+                    .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+                unix_http::request(
+                    &self.socket,
+                    method,
+                    path,
+                    Some(("application/json", &data)),
+                )
+            }
+            None => unix_http::request(&self.socket, method, path, None),
+        }
+    }
+
+    /// 处理只关心成功与否的容器操作（start / stop / restart / rm）
+    fn container_action(&self, method: &str, name: &str, path: &str) -> Result<(), DockerError> {
+        let response = self.call(method, path, None)?;
+        match response.status {
+            // 304: 容器已经处于目标状态
+            200..=299 | 304 => Ok(()),
+            404 => Err(DockerError::ContainerNotFound(name.to_string())),
+            _ => Err(response.into_error()),
+        }
+    }
+
+    fn create_exec(&self, container: &str, command: &str) -> Result<String, DockerError> {
+        let body = json!({
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Tty": false,
+            "Cmd": ["sh", "-c", command],
+        });
+        let response = self.call(
+            "POST",
+            &format!("/containers/{}/exec", segment(container)),
+            Some(body),
+        )?;
+
+        match response.status {
+            201 => {
+                let value: Value = response.json()?;
+                value["Id"]
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| DockerError::OutputParseFailed("exec 响应缺少 Id".to_string()))
+            }
+            404 => Err(DockerError::ContainerNotFound(container.to_string())),
+            409 => Err(DockerError::ContainerNotRunning(container.to_string())),
+            _ => Err(response.into_error()),
+        }
+    }
+
+    fn start_exec(&self, id: &str) -> Result<Box<dyn Read>, DockerError> {
+        let body = json!({ "Detach": false, "Tty": false });
+        let response = self.call("POST", &format!("/exec/{}/start", segment(id)), Some(body))?;
+        if response.is_success() {
+            Ok(response.into_reader())
+        } else {
+            Err(response.into_error())
+        }
+    }
+
+    fn exec_exit_code(&self, id: &str) -> Result<i64, DockerError> {
+        let response = self.call("GET", &format!("/exec/{}/json", segment(id)), None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        let value: Value = response.json()?;
+        Ok(value["ExitCode"].as_i64().unwrap_or(-1))
+    }
+
+    fn logs_reader(
+        &self,
+        container: &str,
+        tail: Option<usize>,
+        follow: bool,
+    ) -> Result<(Box<dyn Read>, bool), DockerError> {
+        // TTY 容器的日志是原始字节流，非 TTY 容器是多路复用流
+        let tty = self.inspect(container)?.tty;
+        let tail = tail
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "all".to_string());
+        let path = format!(
+            "/containers/{}/logs?stdout=1&stderr=1&tail={}&follow={}",
+            segment(container),
+            tail,
+            follow as u8
+        );
+
+        let response = self.call("GET", &path, None)?;
+        match response.status {
+            200 => Ok((response.into_reader(), tty)),
+            404 => Err(DockerError::ContainerNotFound(container.to_string())),
+            _ => Err(response.into_error()),
+        }
+    }
+
+    fn create_container(&self, options: &RunOptions) -> Result<Response, DockerError> {
+        let mut exposed_ports = serde_json::Map::new();
+        let mut port_bindings = serde_json::Map::new();
+        for mapping in &options.ports {
+            let mapping: PortMapping = mapping.parse().map_err(|e: MddeError| {
+                DockerError::CommandFailed(format!("无效的端口映射: {}", e))
+            })?;
+            // API 中的 IPv6 地址不带方括号
+            let host_ip = mapping
+                .host_ip
+                .as_deref()
+                .unwrap_or_default()
+                .trim_matches(['[', ']']);
+            let ports = mapping.host_range().ports().zip(mapping.container.ports());
+            for (host_port, container_port) in ports {
+                let key = format!("{}/{}", container_port, mapping.protocol);
+                exposed_ports.insert(key.clone(), json!({}));
+                port_bindings.insert(
+                    key,
+                    json!([{ "HostIp": host_ip, "HostPort": host_port.to_string() }]),
+                );
+            }
+        }
+
+        let body = json!({
+            "Image": options.image,
+            "Env": options.env,
+            "ExposedPorts": exposed_ports,
+            "HostConfig": {
+                "Binds": options.volumes,
+                "PortBindings": port_bindings,
+            },
+        });
+
+        self.call(
+            "POST",
+            &format!("/containers/create?name={}", encode(&options.name)),
+            Some(body),
+        )
+    }
+
+    /// 调用一个 prune 接口，并将结果累加到报告中
+    fn prune_endpoint(
+        &self,
+        path: &str,
+        deleted_key: &str,
+        report: &mut PruneReport,
+    ) -> Result<(), DockerError> {
+        let response = self.call("POST", path, None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+
+        let value: Value = response.json()?;
+        for item in value[deleted_key].as_array().into_iter().flatten() {
+            match item {
+                Value::String(id) => report.deleted.push(id.clone()),
+                // 镜像的删除记录形如 {"Untagged": "..."} 或 {"Deleted": "..."}
+                Value::Object(map) => {
+                    for (kind, id) in map {
+                        if let Some(id) = id.as_str() {
+                            report
+                                .deleted
+                                .push(format!("{}: {}", kind.to_lowercase(), id));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        report.space_reclaimed += value["SpaceReclaimed"].as_u64().unwrap_or_default();
+        Ok(())
+    }
+}
+
+/// 编码查询参数的值
+fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// 路径中需要编码的字符：除字母、数字和 `-._~` 以外的所有字符
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// 编码路径中的一段，如容器名称、镜像名称
+fn segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+fn io_error(e: io::Error) -> DockerError {
+    DockerError::CommandFailed(e.to_string())
+}
+
+/// 逐帧读取多路复用流，TTY 流整体视为 stdout
+fn for_each_frame(
+    reader: &mut dyn Read,
+    tty: bool,
+    mut f: impl FnMut(OutputStream, &[u8]) -> io::Result<()>,
+) -> Result<(), DockerError> {
+    if tty {
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = reader.read(&mut buf).map_err(io_error)?;
+            if n == 0 {
+                return Ok(());
+            }
+            f(OutputStream::Stdout, &buf[..n]).map_err(io_error)?;
+        }
+    }
+
+    while let Some((kind, payload)) = unix_http::read_frame(reader).map_err(io_error)? {
+        let stream = if kind == 2 {
+            OutputStream::Stderr
+        } else {
+            OutputStream::Stdout
+        };
+        f(stream, &payload).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// 按行读取日志，一行被拆分到多个帧时先缓存不完整的部分
+fn read_log_lines(reader: &mut dyn Read, tty: bool) -> Result<Vec<LogEntry>, DockerError> {
+    let mut entries = Vec::new();
+    let mut pending: [Vec<u8>; 2] = Default::default();
+    let index = |stream| match stream {
+        OutputStream::Stdout => 0,
+        OutputStream::Stderr => 1,
+    };
+    let mut push = |stream, line: &[u8]| {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        entries.push(LogEntry {
+            stream,
+            message: String::from_utf8_lossy(line).to_string(),
+        });
+    };
+
+    for_each_frame(reader, tty, |stream, data| {
+        let buffer = &mut pending[index(stream)];
+        buffer.extend_from_slice(data);
+        if let Some(end) = buffer.iter().rposition(|&b| b == b'\n') {
+            let rest = buffer.split_off(end + 1);
+            for line in buffer[..end].split(|&b| b == b'\n') {
+                push(stream, line);
+            }
+            *buffer = rest;
+        }
+        Ok(())
+    })?;
+
+    for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+        let buffer = &pending[index(stream)];
+        if !buffer.is_empty() {
+            push(stream, buffer);
+        }
+    }
+    Ok(entries)
+}
+
+/// 将输出直接写到终端
+fn write_to_terminal(stream: OutputStream, data: &[u8]) -> io::Result<()> {
+    match stream {
+        OutputStream::Stdout => {
+            let mut out = io::stdout();
+            out.write_all(data)?;
+            out.flush()
+        }
+        OutputStream::Stderr => {
+            let mut err = io::stderr();
+            err.write_all(data)?;
+            err.flush()
+        }
+    }
+}
+
+/// 解析构建、拉取镜像时返回的 JSON 消息流，遇到 `error` 字段即失败
+fn read_progress(reader: Box<dyn Read>) -> Result<Vec<Value>, DockerError> {
+    let mut messages = Vec::new();
+    for message in serde_json::Deserializer::from_reader(reader).into_iter::<Value>() {
+        let message = message.map_err(|e| DockerError::OutputParseFailed(e.to_string()))?;
+        if let Some(error) = message.get("error").and_then(|e| e.as_str()) {
+            return Err(DockerError::CommandFailed(error.to_string()));
+        }
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+/// 将镜像引用拆分为名称和标签
+fn split_image_tag(image: &str) -> (&str, &str) {
+    match image.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => (name, tag),
+        _ => (image, "latest"),
+    }
+}
+
+impl ContainerBackend for EngineApiBackend {
+    fn name(&self) -> &'static str {
+        "docker-engine-api"
+    }
+
+    fn container_exists(&self, name: &str) -> Result<bool, DockerError> {
+        match self.inspect(name) {
+            Ok(_) => Ok(true),
+            Err(DockerError::ContainerNotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn container_running(&self, name: &str) -> Result<bool, DockerError> {
+        match self.inspect(name) {
+            Ok(details) => Ok(details.state.running),
+            Err(DockerError::ContainerNotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn start_container(&self, name: &str) -> Result<(), DockerError> {
+        self.container_action(
+            "POST",
+            name,
+            &format!("/containers/{}/start", segment(name)),
+        )
+    }
+
+    fn stop_container(&self, name: &str) -> Result<(), DockerError> {
+        self.container_action("POST", name, &format!("/containers/{}/stop", segment(name)))
+    }
+
+    fn restart_container(&self, name: &str) -> Result<(), DockerError> {
+        self.container_action(
+            "POST",
+            name,
+            &format!("/containers/{}/restart", segment(name)),
+        )
+    }
+
+    fn exec(&self, container: &str, command: &str) -> Result<ExecOutput, DockerError> {
+        let id = self.create_exec(container, command)?;
+        let mut reader = self.start_exec(&id)?;
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        for_each_frame(&mut reader, false, |stream, data| {
+            match stream {
+                OutputStream::Stdout => stdout.extend_from_slice(data),
+                OutputStream::Stderr => stderr.extend_from_slice(data),
+            }
+            Ok(())
+        })?;
+
+        Ok(ExecOutput {
+            exit_code: self.exec_exit_code(&id)?,
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+        })
+    }
+
+    fn exec_stream(&self, container: &str, command: &str) -> Result<(), DockerError> {
+        let id = self.create_exec(container, command)?;
+        let mut reader = self.start_exec(&id)?;
+        for_each_frame(&mut reader, false, write_to_terminal)?;
+
+        match self.exec_exit_code(&id)? {
+            0 => Ok(()),
+            code => Err(DockerError::CommandFailed(format!(
+                "执行命令失败，退出代码: {}",
+                code
+            ))),
+        }
+    }
+
+    fn exec_interactive(&self, container: &str, shell: &str) -> Result<(), DockerError> {
+        CliBackend.exec_interactive(container, shell)
+    }
+
+    fn logs(&self, container: &str, tail: Option<usize>) -> Result<Vec<LogEntry>, DockerError> {
+        let (mut reader, tty) = self.logs_reader(container, tail, false)?;
+        read_log_lines(&mut reader, tty)
+    }
+
+    fn follow_logs(&self, container: &str, tail: Option<usize>) -> Result<(), DockerError> {
+        let (mut reader, tty) = self.logs_reader(container, tail, true)?;
+        for_each_frame(&mut reader, tty, write_to_terminal)
+    }
+
+    fn list_containers(&self) -> Result<Vec<String>, DockerError> {
+        let response = self.call("GET", "/containers/json?all=1", None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+
+        let value: Value = response.json()?;
+        Ok(value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|container| container["Id"].as_str())
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn stats(&self, container: &str) -> Result<ContainerStats, DockerError> {
+        let response = self.call(
+            "GET",
+            &format!("/containers/{}/stats?stream=false", segment(container)),
+            None,
+        )?;
+        match response.status {
+            200 => Ok(ContainerStats::from_api_json(&response.json()?)),
+            404 => Err(DockerError::ContainerNotFound(container.to_string())),
+            _ => Err(response.into_error()),
+        }
+    }
+
+    fn inspect(&self, container: &str) -> Result<ContainerDetails, DockerError> {
+        let response = self.call(
+            "GET",
+            &format!("/containers/{}/json", segment(container)),
+            None,
+        )?;
+        match response.status {
+            200 => ContainerDetails::from_inspect_json(response.json()?),
+            404 => Err(DockerError::ContainerNotFound(container.to_string())),
+            _ => Err(response.into_error()),
+        }
+    }
+
+    fn remove_container(&self, name: &str, force: bool) -> Result<(), DockerError> {
+        self.container_action(
+            "DELETE",
+            name,
+            &format!("/containers/{}?force={}", segment(name), force),
+        )
+    }
+
+    fn build_image(&self, context: &Path, tag: &str) -> Result<BuildOutput, DockerError> {
+        // 构建上下文需要以 tar 包的形式上传
+        let mut archive = tar::Builder::new(Vec::new());
+        archive.follow_symlinks(false);
+        archive.append_dir_all(".", context).map_err(io_error)?;
+        let archive = archive.into_inner().map_err(io_error)?;
+
+        let path = format!("/build?t={}&rm=1", encode(tag));
+        let response = unix_http::request(
+            &self.socket,
+            "POST",
+            &path,
+            Some(("application/x-tar", &archive)),
+        )?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+
+        let image_id = read_progress(response.into_reader())?
+            .iter()
+            .rev()
+            .find_map(|m| m["aux"]["ID"].as_str().map(String::from));
+
+        Ok(BuildOutput {
+            tag: tag.to_string(),
+            image_id,
+        })
+    }
+
+    fn pull_image(&self, image: &str) -> Result<(), DockerError> {
+        let (name, tag) = split_image_tag(image);
+        let path = format!(
+            "/images/create?fromImage={}&tag={}",
+            encode(name),
+            encode(tag)
+        );
+        let response = self.call("POST", &path, None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+
+        read_progress(response.into_reader()).map(|_| ())
+    }
+
+    fn remove_image(&self, image: &str, force: bool) -> Result<(), DockerError> {
+        let path = format!("/images/{}?force={}", segment(image), force);
+        let response = self.call("DELETE", &path, None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        Ok(())
+    }
+
+    fn remove_volume(&self, name: &str) -> Result<(), DockerError> {
+        let response = self.call("DELETE", &format!("/volumes/{}", segment(name)), None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        Ok(())
+    }
+
+    fn remove_network(&self, name: &str) -> Result<(), DockerError> {
+        let response = self.call("DELETE", &format!("/networks/{}", segment(name)), None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        Ok(())
+    }
+
+    fn run_container(&self, options: &RunOptions) -> Result<String, DockerError> {
+        let mut response = self.create_container(options)?;
+        if response.status == 404 {
+            // 与 docker run 一致：镜像不存在时先拉取
+            self.pull_image(&options.image)?;
+            response = self.create_container(options)?;
+        }
+        if response.status != 201 {
+            return Err(response.into_error());
+        }
+
+        let value: Value = response.json()?;
+        let id = value["Id"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| DockerError::OutputParseFailed("创建容器响应缺少 Id".to_string()))?;
+
+        self.container_action(
+            "POST",
+            &options.name,
+            &format!("/containers/{}/start", segment(&id)),
+        )?;
+
+        if !options.detach {
+            let response =
+                self.call("POST", &format!("/containers/{}/wait", segment(&id)), None)?;
+            if !response.is_success() {
+                return Err(response.into_error());
+            }
+            for entry in self.logs(&id, None)? {
+                match entry.stream {
+                    OutputStream::Stdout => println!("{}", entry.message),
+                    OutputStream::Stderr => eprintln!("{}", entry.message),
+                }
+            }
+        }
+
+        Ok(id)
+    }
+
+    fn compose_up(&self, project: &ComposeProject, detach: bool) -> Result<(), DockerError> {
+        // compose 没有对应的 Engine API，交给命令行处理
+        CliBackend.compose_up(project, detach)
+    }
+
+    fn compose_down(
+        &self,
+        project: &ComposeProject,
+        remove_volumes: bool,
+    ) -> Result<(), DockerError> {
+        CliBackend.compose_down(project, remove_volumes)
+    }
+
+    fn compose_config(&self, project: &ComposeProject) -> Result<String, DockerError> {
+        CliBackend.compose_config(project)
+    }
+
+    fn compose_container_ids(&self, project: &ComposeProject) -> Result<Vec<String>, DockerError> {
+        CliBackend.compose_container_ids(project)
+    }
+
+    fn compose_project_exists(&self, name: &str, working_dir: &Path) -> Result<bool, DockerError> {
+        let filters = json!({
+            "label": [
+                format!("{}={}", COMPOSE_PROJECT_LABEL, name),
+                format!("{}={}", COMPOSE_WORKING_DIR_LABEL, working_dir.display()),
+            ]
+        });
+        let path = format!(
+            "/containers/json?all=1&filters={}",
+            encode(&filters.to_string())
+        );
+        let response = self.call("GET", &path, None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        let value: Value = response.json()?;
+        Ok(value
+            .as_array()
+            .is_some_and(|containers| !containers.is_empty()))
+    }
+
+    fn prune(&self, target: PruneTarget, scope: PruneScope) -> Result<PruneReport, DockerError> {
+        // 按范围加上标签过滤条件，`extra` 为其他过滤条件
+        let filtered = |path: &str, extra: &[(&str, &str)]| {
+            let mut filters = serde_json::Map::new();
+            for (key, value) in extra {
+                filters.insert(key.to_string(), json!([value]));
+            }
+            if let Some(label) = scope.label_filter() {
+                filters.insert("label".to_string(), json!([label]));
+            }
+            if filters.is_empty() {
+                path.to_string()
+            } else {
+                format!(
+                    "{}?filters={}",
+                    path,
+                    encode(&Value::Object(filters).to_string())
+                )
+            }
+        };
+
+        let mut report = PruneReport::default();
+        match target {
+            PruneTarget::System => {
+                self.prune_endpoint(
+                    &filtered("/containers/prune", &[]),
+                    "ContainersDeleted",
+                    &mut report,
+                )?;
+                self.prune_endpoint(
+                    &filtered("/networks/prune", &[]),
+                    "NetworksDeleted",
+                    &mut report,
+                )?;
+                self.prune_endpoint(
+                    &filtered("/images/prune", &[("dangling", "false")]),
+                    "ImagesDeleted",
+                    &mut report,
+                )?;
+                // 构建缓存不能按标签过滤，只在全局清理时清理
+                if scope == PruneScope::Global {
+                    self.prune_endpoint("/build/prune?all=true", "CachesDeleted", &mut report)?;
+                }
+            }
+            PruneTarget::Images => {
+                // mdde 构建的镜像通常带有标签名，不只清理悬空镜像
+                let extra: &[(&str, &str)] = match scope {
+                    PruneScope::Managed => &[("dangling", "false")],
+                    PruneScope::Global => &[],
+                };
+                self.prune_endpoint(
+                    &filtered("/images/prune", extra),
+                    "ImagesDeleted",
+                    &mut report,
+                )?
+            }
+            PruneTarget::Containers => self.prune_endpoint(
+                &filtered("/containers/prune", &[]),
+                "ContainersDeleted",
+                &mut report,
+            )?,
+            PruneTarget::Volumes => return prune::remove_unused_volumes(self, scope),
+        }
+        Ok(report)
+    }
+
+    fn disk_usage(&self) -> Result<DiskUsage, DockerError> {
+        let response = self.call("GET", "/system/df", None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        let mut usage = DiskUsage::from_api_json(response.json()?)?;
+
+        let response = self.call("GET", "/networks", None)?;
+        if !response.is_success() {
+            return Err(response.into_error());
+        }
+        usage.networks = DiskUsage::networks_from_json(&response.json()?);
+        Ok(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_image_tag() {
+        assert_eq!(split_image_tag("node:22"), ("node", "22"));
+        assert_eq!(split_image_tag("node"), ("node", "latest"));
+        assert_eq!(
+            split_image_tag("localhost:5000/team/app"),
+            ("localhost:5000/team/app", "latest")
+        );
+        assert_eq!(
+            split_image_tag("localhost:5000/team/app:v1"),
+            ("localhost:5000/team/app", "v1")
+        );
+    }
+
+    #[test]
+    fn test_segment_encodes_path() {
+        assert_eq!(segment("mdde-app_1.dev"), "mdde-app_1.dev");
+        assert_eq!(
+            segment("localhost:5000/team/app?x#y"),
+            "localhost%3A5000%2Fteam%2Fapp%3Fx%23y"
+        );
+    }
+
+    #[test]
+    fn test_read_log_lines_across_frames() {
+        let frame = |kind: u8, payload: &[u8]| {
+            let mut frame = vec![kind, 0, 0, 0];
+            frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            frame.extend_from_slice(payload);
+            frame
+        };
+        let stream = [
+            frame(1, b"hel"),
+            frame(2, b"warn"),
+            frame(1, b"lo\r\nwor"),
+            frame(2, b"ing\n"),
+            frame(1, b"ld\nlast"),
+        ]
+        .concat();
+
+        let entries = read_log_lines(&mut io::Cursor::new(stream), false).unwrap();
+        let lines: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.stream, entry.message.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (OutputStream::Stdout, "hello"),
+                (OutputStream::Stderr, "warning"),
+                (OutputStream::Stdout, "world"),
+                (OutputStream::Stdout, "last"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_progress_reports_error() {
+        let stream = br#"{"status":"Pulling"}{"error":"manifest unknown"}"#;
+        let result = read_progress(Box::new(io::Cursor::new(stream.to_vec())));
+        assert!(
+            matches!(result, Err(DockerError::CommandFailed(msg)) if msg == "manifest unknown")
+        );
+    }
+}
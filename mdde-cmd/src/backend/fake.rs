@@ -0,0 +1,607 @@
+use super::prune::{self, ContainerUsage, ImageUsage, NetworkUsage, VolumeUsage};
+use super::service::{COMPOSE_PROJECT_LABEL, COMPOSE_WORKING_DIR_LABEL, SERVICE_LABEL};
+use super::{
+    BuildOutput, ContainerBackend, ContainerDetails, ContainerState, ContainerStats, DiskUsage,
+    ExecOutput, LogEntry, OutputStream, PruneReport, PruneScope, PruneTarget, PublishedPort,
+    RunOptions,
+};
+use crate::compose::{ComposeProject, MANAGED_LABEL, PROJECT_LABEL};
+use crate::docker::DockerError;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// 模拟的容器
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FakeContainer {
+    pub name: String,
+    pub image: String,
+    /// 所属的 compose 服务
+    pub service: Option<String>,
+    pub running: bool,
+    pub exit_code: i64,
+    pub health: Option<String>,
+    /// RFC 3339 格式的启动时间
+    pub started_at: String,
+    pub ports: Vec<PublishedPort>,
+    pub logs: Vec<LogEntry>,
+    pub stats: Option<ContainerStats>,
+    pub labels: HashMap<String, String>,
+}
+
+impl FakeContainer {
+    pub fn new(name: &str, image: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            image: image.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn running(mut self) -> Self {
+        self.running = true;
+        self
+    }
+
+    pub fn service(mut self, service: &str) -> Self {
+        self.service = Some(service.to_string());
+        self
+    }
+
+    pub fn health(mut self, health: &str) -> Self {
+        self.health = Some(health.to_string());
+        self
+    }
+
+    pub fn port(mut self, host_port: u16, container_port: u16) -> Self {
+        self.ports.push(PublishedPort {
+            container_port,
+            protocol: "tcp".to_string(),
+            host_ip: Some("0.0.0.0".to_string()),
+            host_port: Some(host_port),
+        });
+        self
+    }
+
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn stats(mut self, stats: ContainerStats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn log(mut self, stream: OutputStream, message: &str) -> Self {
+        self.logs.push(LogEntry {
+            stream,
+            message: message.to_string(),
+        });
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    containers: BTreeMap<String, FakeContainer>,
+    services: Vec<FakeContainer>,
+    images: Vec<String>,
+    image_labels: HashMap<String, HashMap<String, String>>,
+    volumes: Vec<VolumeUsage>,
+    networks: Vec<NetworkUsage>,
+    calls: Vec<String>,
+    failures: HashMap<String, String>,
+    exec_output: ExecOutput,
+}
+
+/// 内存中的容器后端
+///
+/// 记录每一次调用，并模拟容器的生命周期，用于在没有 Docker 守护进程的机器上测试命令。
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    state: Mutex<FakeState>,
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一个已存在的容器
+    pub fn with_container(self, container: FakeContainer) -> Self {
+        self.lock()
+            .containers
+            .insert(container.name.clone(), container);
+        self
+    }
+
+    /// 声明 compose 项目中的服务，`compose_up` 时会创建对应的容器
+    pub fn with_service(self, container: FakeContainer) -> Self {
+        self.lock().services.push(container);
+        self
+    }
+
+    /// 添加一个本地镜像
+    pub fn with_image(self, image: &str) -> Self {
+        self.lock().images.push(image.to_string());
+        self
+    }
+
+    /// 添加一个卷
+    pub fn with_volume(self, volume: VolumeUsage) -> Self {
+        self.lock().volumes.push(volume);
+        self
+    }
+
+    /// 添加一个网络
+    pub fn with_network(self, network: NetworkUsage) -> Self {
+        self.lock().networks.push(network);
+        self
+    }
+
+    /// 添加一个带标签的本地镜像
+    pub fn with_labelled_image(self, image: &str, key: &str, value: &str) -> Self {
+        {
+            let mut state = self.lock();
+            if !state.images.iter().any(|i| i == image) {
+                state.images.push(image.to_string());
+            }
+            state
+                .image_labels
+                .entry(image.to_string())
+                .or_default()
+                .insert(key.to_string(), value.to_string());
+        }
+        self
+    }
+
+    /// 设置 exec 返回的结果
+    pub fn with_exec_output(self, output: ExecOutput) -> Self {
+        self.lock().exec_output = output;
+        self
+    }
+
+    /// 让指定操作（方法名，如 `compose_up`）返回失败
+    pub fn fail_on(self, operation: &str, message: &str) -> Self {
+        self.lock()
+            .failures
+            .insert(operation.to_string(), message.to_string());
+        self
+    }
+
+    /// 按顺序返回所有调用记录，格式为 `操作 参数`
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    /// 获取容器当前状态
+    pub fn container(&self, name: &str) -> Option<FakeContainer> {
+        self.lock().containers.get(name).cloned()
+    }
+
+    /// 当前所有本地镜像
+    pub fn images(&self) -> Vec<String> {
+        self.lock().images.clone()
+    }
+
+    /// 剩余的卷名称
+    pub fn volumes(&self) -> Vec<String> {
+        self.lock().volumes.iter().map(|v| v.name.clone()).collect()
+    }
+
+    /// 剩余的网络名称
+    pub fn networks(&self) -> Vec<String> {
+        self.lock()
+            .networks
+            .iter()
+            .map(|n| n.name.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 记录调用，并检查是否需要模拟失败
+    fn record(
+        &self,
+        operation: &str,
+        args: &str,
+    ) -> Result<MutexGuard<'_, FakeState>, DockerError> {
+        let mut state = self.lock();
+        state
+            .calls
+            .push(format!("{} {}", operation, args).trim().to_string());
+        match state.failures.get(operation) {
+            Some(message) => Err(DockerError::CommandFailed(message.clone())),
+            None => Ok(state),
+        }
+    }
+
+    /// 记录调用，并检查容器是否存在且正在运行
+    fn running_container(
+        &self,
+        operation: &str,
+        name: &str,
+        args: &str,
+    ) -> Result<(), DockerError> {
+        let state = self.record(operation, &format!("{} {}", name, args))?;
+        match state.containers.get(name) {
+            None => Err(DockerError::ContainerNotFound(name.to_string())),
+            Some(container) if !container.running => {
+                Err(DockerError::ContainerNotRunning(name.to_string()))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn set_running(&self, operation: &str, name: &str, running: bool) -> Result<(), DockerError> {
+        let mut state = self.record(operation, name)?;
+        let container = state
+            .containers
+            .get_mut(name)
+            .ok_or_else(|| DockerError::ContainerNotFound(name.to_string()))?;
+        container.running = running;
+        Ok(())
+    }
+}
+
+impl ContainerBackend for FakeBackend {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn container_exists(&self, name: &str) -> Result<bool, DockerError> {
+        Ok(self
+            .record("container_exists", name)?
+            .containers
+            .contains_key(name))
+    }
+
+    fn container_running(&self, name: &str) -> Result<bool, DockerError> {
+        Ok(self
+            .record("container_running", name)?
+            .containers
+            .get(name)
+            .is_some_and(|c| c.running))
+    }
+
+    fn start_container(&self, name: &str) -> Result<(), DockerError> {
+        self.set_running("start_container", name, true)
+    }
+
+    fn stop_container(&self, name: &str) -> Result<(), DockerError> {
+        self.set_running("stop_container", name, false)
+    }
+
+    fn restart_container(&self, name: &str) -> Result<(), DockerError> {
+        self.set_running("restart_container", name, true)
+    }
+
+    fn exec(&self, container: &str, command: &str) -> Result<ExecOutput, DockerError> {
+        self.running_container("exec", container, command)?;
+        Ok(self.lock().exec_output.clone())
+    }
+
+    fn exec_stream(&self, container: &str, command: &str) -> Result<(), DockerError> {
+        self.running_container("exec_stream", container, command)?;
+
+        let output = self.lock().exec_output.clone();
+        print!("{}", output.stdout);
+        eprint!("{}", output.stderr);
+        if output.success() {
+            Ok(())
+        } else {
+            Err(DockerError::CommandFailed(format!(
+                "执行命令失败，退出代码: {}",
+                output.exit_code
+            )))
+        }
+    }
+
+    fn exec_interactive(&self, container: &str, shell: &str) -> Result<(), DockerError> {
+        self.running_container("exec_interactive", container, shell)
+    }
+
+    fn logs(&self, container: &str, tail: Option<usize>) -> Result<Vec<LogEntry>, DockerError> {
+        let state = self.record("logs", &format!("{} tail={:?}", container, tail))?;
+        let logs = &state
+            .containers
+            .get(container)
+            .ok_or_else(|| DockerError::ContainerNotFound(container.to_string()))?
+            .logs;
+        let skip = tail.map(|n| logs.len().saturating_sub(n)).unwrap_or(0);
+        Ok(logs[skip..].to_vec())
+    }
+
+    fn follow_logs(&self, container: &str, tail: Option<usize>) -> Result<(), DockerError> {
+        let state = self.record("follow_logs", &format!("{} tail={:?}", container, tail))?;
+        let logs = &state
+            .containers
+            .get(container)
+            .ok_or_else(|| DockerError::ContainerNotFound(container.to_string()))?
+            .logs;
+        let skip = tail.map(|n| logs.len().saturating_sub(n)).unwrap_or(0);
+        for entry in &logs[skip..] {
+            println!("{}", entry.message);
+        }
+        Ok(())
+    }
+
+    fn list_containers(&self) -> Result<Vec<String>, DockerError> {
+        Ok(self
+            .record("list_containers", "")?
+            .containers
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    fn stats(&self, container: &str) -> Result<ContainerStats, DockerError> {
+        self.running_container("stats", container, "")?;
+        Ok(self
+            .lock()
+            .containers
+            .get(container)
+            .and_then(|c| c.stats.clone())
+            .unwrap_or_default())
+    }
+
+    fn inspect(&self, container: &str) -> Result<ContainerDetails, DockerError> {
+        let state = self.record("inspect", container)?;
+        let c = state
+            .containers
+            .get(container)
+            .ok_or_else(|| DockerError::ContainerNotFound(container.to_string()))?;
+
+        let mut labels = c.labels.clone();
+        if let Some(service) = &c.service {
+            labels.insert(SERVICE_LABEL.to_string(), service.clone());
+        }
+
+        Ok(ContainerDetails {
+            id: format!("fake-{}", c.name),
+            name: c.name.clone(),
+            image: c.image.clone(),
+            created: String::new(),
+            state: ContainerState {
+                status: if c.running { "running" } else { "exited" }.to_string(),
+                running: c.running,
+                exit_code: c.exit_code,
+                health: c.health.clone(),
+                started_at: c.started_at.clone(),
+                finished_at: String::new(),
+            },
+            ports: c.ports.clone(),
+            labels,
+            tty: false,
+        })
+    }
+
+    fn remove_container(&self, name: &str, force: bool) -> Result<(), DockerError> {
+        let mut state = self.record("remove_container", &format!("{} force={}", name, force))?;
+        match state.containers.get(name) {
+            None => Err(DockerError::ContainerNotFound(name.to_string())),
+            Some(c) if c.running && !force => Err(DockerError::CommandFailed(format!(
+                "容器 {} 正在运行，无法删除",
+                name
+            ))),
+            Some(_) => {
+                state.containers.remove(name);
+                Ok(())
+            }
+        }
+    }
+
+    fn build_image(&self, context: &Path, tag: &str) -> Result<BuildOutput, DockerError> {
+        let mut state = self.record("build_image", &format!("{} {}", context.display(), tag))?;
+        state.images.push(tag.to_string());
+        Ok(BuildOutput {
+            tag: tag.to_string(),
+            image_id: Some(format!("sha256:fake-{}", tag)),
+        })
+    }
+
+    fn pull_image(&self, image: &str) -> Result<(), DockerError> {
+        let mut state = self.record("pull_image", image)?;
+        if !state.images.iter().any(|i| i == image) {
+            state.images.push(image.to_string());
+        }
+        Ok(())
+    }
+
+    fn remove_image(&self, image: &str, force: bool) -> Result<(), DockerError> {
+        let mut state = self.record("remove_image", &format!("{} force={}", image, force))?;
+        if !state.images.iter().any(|i| i == image) {
+            return Err(DockerError::CommandFailed(format!("镜像不存在: {}", image)));
+        }
+        if !force && state.containers.values().any(|c| c.image == image) {
+            return Err(DockerError::CommandFailed(format!(
+                "镜像 {} 正在被容器使用，无法删除",
+                image
+            )));
+        }
+        state.images.retain(|i| i != image);
+        state.image_labels.remove(image);
+        Ok(())
+    }
+
+    fn remove_volume(&self, name: &str) -> Result<(), DockerError> {
+        let mut state = self.record("remove_volume", name)?;
+        if !state.volumes.iter().any(|v| v.name == name) {
+            return Err(DockerError::CommandFailed(format!("卷不存在: {}", name)));
+        }
+        state.volumes.retain(|v| v.name != name);
+        Ok(())
+    }
+
+    fn remove_network(&self, name: &str) -> Result<(), DockerError> {
+        let mut state = self.record("remove_network", name)?;
+        if !state.networks.iter().any(|n| n.name == name) {
+            return Err(DockerError::CommandFailed(format!("网络不存在: {}", name)));
+        }
+        state.networks.retain(|n| n.name != name);
+        Ok(())
+    }
+
+    fn run_container(&self, options: &RunOptions) -> Result<String, DockerError> {
+        let mut state = self.record(
+            "run_container",
+            &format!("{} {}", options.name, options.image),
+        )?;
+        if state.containers.contains_key(&options.name) {
+            return Err(DockerError::CommandFailed(format!(
+                "容器名称 {} 已被占用",
+                options.name
+            )));
+        }
+
+        let mut container = FakeContainer::new(&options.name, &options.image);
+        container.running = options.detach;
+        state.containers.insert(options.name.clone(), container);
+        Ok(format!("fake-{}", options.name))
+    }
+
+    fn compose_up(&self, project: &ComposeProject, detach: bool) -> Result<(), DockerError> {
+        let mut state = self.record("compose_up", &format!("detach={}", detach))?;
+        // 带有 mdde 环境标签的服务只属于对应的环境
+        let services: Vec<FakeContainer> = state
+            .services
+            .iter()
+            .filter(|c| !c.labels.contains_key(PROJECT_LABEL) || project.owns(&c.labels))
+            .cloned()
+            .collect();
+        for container in services {
+            let mut container = container.label(COMPOSE_PROJECT_LABEL, &project.name());
+            container.running = true;
+            state.containers.insert(container.name.clone(), container);
+        }
+        Ok(())
+    }
+
+    fn compose_down(
+        &self,
+        project: &ComposeProject,
+        remove_volumes: bool,
+    ) -> Result<(), DockerError> {
+        let mut state = self.record("compose_down", &format!("volumes={}", remove_volumes))?;
+        state
+            .containers
+            .retain(|_, c| !in_compose_project(c, project));
+        let name = project.name();
+        state
+            .networks
+            .retain(|n| n.labels.get(COMPOSE_PROJECT_LABEL) != Some(&name));
+        Ok(())
+    }
+
+    fn compose_config(&self, project: &ComposeProject) -> Result<String, DockerError> {
+        drop(self.record("compose_config", "")?);
+        // 不做变量替换，直接返回 compose 文件内容
+        std::fs::read_to_string(&project.compose_file)
+            .map_err(|e| DockerError::CommandFailed(e.to_string()))
+    }
+
+    fn compose_container_ids(&self, project: &ComposeProject) -> Result<Vec<String>, DockerError> {
+        let state = self.record("compose_container_ids", "")?;
+        Ok(state
+            .containers
+            .values()
+            .filter(|c| in_compose_project(c, project))
+            .map(|c| c.name.clone())
+            .collect())
+    }
+
+    fn compose_project_exists(&self, name: &str, working_dir: &Path) -> Result<bool, DockerError> {
+        let state = self.record("compose_project_exists", name)?;
+        let working_dir = working_dir.to_string_lossy();
+        Ok(state.containers.values().any(|c| {
+            c.labels.get(COMPOSE_PROJECT_LABEL).map(String::as_str) == Some(name)
+                && c.labels.get(COMPOSE_WORKING_DIR_LABEL).map(String::as_str)
+                    == Some(&*working_dir)
+        }))
+    }
+
+    fn prune(&self, target: PruneTarget, scope: PruneScope) -> Result<PruneReport, DockerError> {
+        let mut state = self.record("prune", &format!("{:?} {:?}", target, scope))?;
+        if target == PruneTarget::Volumes {
+            drop(state);
+            return prune::remove_unused_volumes(self, scope);
+        }
+        let mut report = PruneReport::default();
+        let in_scope = |labels: Option<&HashMap<String, String>>| {
+            scope == PruneScope::Global
+                || labels
+                    .and_then(|labels| labels.get(MANAGED_LABEL))
+                    .is_some_and(|value| value == "true")
+        };
+
+        if matches!(target, PruneTarget::System | PruneTarget::Containers) {
+            let stopped: Vec<String> = state
+                .containers
+                .values()
+                .filter(|c| !c.running && in_scope(Some(&c.labels)))
+                .map(|c| c.name.clone())
+                .collect();
+            for name in stopped {
+                state.containers.remove(&name);
+                report.deleted.push(name);
+            }
+        }
+
+        if matches!(target, PruneTarget::System | PruneTarget::Images) {
+            let used: Vec<String> = state.containers.values().map(|c| c.image.clone()).collect();
+            let image_labels = std::mem::take(&mut state.image_labels);
+            let (kept, removed): (Vec<String>, Vec<String>) = state
+                .images
+                .drain(..)
+                .partition(|image| used.contains(image) || !in_scope(image_labels.get(image)));
+            state.images = kept;
+            state.image_labels = image_labels;
+            report.deleted.extend(removed);
+        }
+
+        Ok(report)
+    }
+    fn disk_usage(&self) -> Result<DiskUsage, DockerError> {
+        let state = self.record("disk_usage", "")?;
+        // 镜像以名称作为 ID，容器不挂载卷也不连接网络，不模拟构建缓存
+        Ok(DiskUsage {
+            containers: state
+                .containers
+                .values()
+                .map(|c| ContainerUsage {
+                    id: c.name.clone(),
+                    name: c.name.clone(),
+                    image_id: c.image.clone(),
+                    state: if c.running { "running" } else { "exited" }.to_string(),
+                    labels: c.labels.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            images: state
+                .images
+                .iter()
+                .map(|image| ImageUsage {
+                    id: image.clone(),
+                    tags: vec![image.clone()],
+                    labels: state.image_labels.get(image).cloned().unwrap_or_default(),
+                    size: None,
+                })
+                .collect(),
+            volumes: state.volumes.clone(),
+            networks: state.networks.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+/// 容器是否属于 compose 项目：有服务名，且没有项目名称标签或与项目名称相同
+fn in_compose_project(container: &FakeContainer, project: &ComposeProject) -> bool {
+    container.service.is_some()
+        && container
+            .labels
+            .get(COMPOSE_PROJECT_LABEL)
+            .is_none_or(|name| *name == project.name())
+}
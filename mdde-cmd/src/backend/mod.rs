@@ -0,0 +1,505 @@
+pub mod cli;
+#[cfg(unix)]
+pub mod engine;
+pub mod fake;
+pub mod prune;
+pub mod service;
+pub mod stats;
+#[cfg(unix)]
+mod unix_http;
+
+pub use cli::CliBackend;
+#[cfg(unix)]
+pub use engine::EngineApiBackend;
+pub use fake::FakeBackend;
+pub use prune::{DiskUsage, PruneCandidate, ResourceKind};
+pub use service::{compose_services, ServiceStatus};
+pub use stats::ContainerStats;
+
+use crate::compose::{ComposeProject, MANAGED_LABEL};
+use crate::docker::DockerError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 容器后端，封装对容器引擎的所有操作
+///
+/// 命令模块只依赖这个 trait，不关心底层是 Docker Engine API 还是 `docker` 命令行。
+pub trait ContainerBackend: Send + Sync {
+    /// 后端名称，用于日志和诊断输出
+    fn name(&self) -> &'static str;
+
+    /// 检查容器是否存在
+    fn container_exists(&self, name: &str) -> Result<bool, DockerError>;
+
+    /// 检查容器是否正在运行
+    fn container_running(&self, name: &str) -> Result<bool, DockerError>;
+
+    /// 启动容器
+    fn start_container(&self, name: &str) -> Result<(), DockerError>;
+
+    /// 停止容器
+    fn stop_container(&self, name: &str) -> Result<(), DockerError>;
+
+    /// 重启容器
+    fn restart_container(&self, name: &str) -> Result<(), DockerError>;
+
+    /// 在容器中执行命令并收集输出
+    fn exec(&self, container: &str, command: &str) -> Result<ExecOutput, DockerError>;
+
+    /// 在容器中执行命令，实时输出结果
+    fn exec_stream(&self, container: &str, command: &str) -> Result<(), DockerError>;
+
+    /// 进入容器进行交互式操作
+    fn exec_interactive(&self, container: &str, shell: &str) -> Result<(), DockerError>;
+
+    /// 获取容器日志
+    fn logs(&self, container: &str, tail: Option<usize>) -> Result<Vec<LogEntry>, DockerError>;
+
+    /// 实时跟踪容器日志，直到容器停止或用户中断
+    fn follow_logs(&self, container: &str, tail: Option<usize>) -> Result<(), DockerError>;
+
+    /// 列出本机所有容器（包括已停止的）的 ID
+    fn list_containers(&self) -> Result<Vec<String>, DockerError>;
+
+    /// 采样一次容器的资源使用情况
+    fn stats(&self, container: &str) -> Result<ContainerStats, DockerError>;
+
+    /// 获取容器详细信息
+    fn inspect(&self, container: &str) -> Result<ContainerDetails, DockerError>;
+
+    /// 删除容器
+    fn remove_container(&self, name: &str, force: bool) -> Result<(), DockerError>;
+
+    /// 构建镜像
+    fn build_image(&self, context: &Path, tag: &str) -> Result<BuildOutput, DockerError>;
+
+    /// 拉取镜像
+    fn pull_image(&self, image: &str) -> Result<(), DockerError>;
+
+    /// 删除镜像，`force` 时同时删除镜像的所有标签
+    fn remove_image(&self, image: &str, force: bool) -> Result<(), DockerError>;
+
+    /// 删除卷
+    fn remove_volume(&self, name: &str) -> Result<(), DockerError>;
+
+    /// 删除网络
+    fn remove_network(&self, name: &str) -> Result<(), DockerError>;
+
+    /// 运行容器，返回容器 ID
+    fn run_container(&self, options: &RunOptions) -> Result<String, DockerError>;
+
+    /// 启动 compose 项目 (`up`)，前台运行时实时输出
+    fn compose_up(&self, project: &ComposeProject, detach: bool) -> Result<(), DockerError>;
+
+    /// 停止 compose 项目 (`down`)
+    fn compose_down(
+        &self,
+        project: &ComposeProject,
+        remove_volumes: bool,
+    ) -> Result<(), DockerError>;
+
+    /// 获取 compose 项目解析变量后的完整配置 (`config`)，YAML 格式
+    fn compose_config(&self, project: &ComposeProject) -> Result<String, DockerError>;
+
+    /// 获取 compose 项目中所有容器（包括已停止的）的 ID
+    fn compose_container_ids(&self, project: &ComposeProject) -> Result<Vec<String>, DockerError>;
+
+    /// 是否有属于 compose 项目 `name` 且项目目录为 `working_dir` 的容器（包括已停止的）
+    fn compose_project_exists(&self, name: &str, working_dir: &Path) -> Result<bool, DockerError>;
+
+    /// 清理未使用的资源，`scope` 决定是否只清理 mdde 的资源
+    fn prune(&self, target: PruneTarget, scope: PruneScope) -> Result<PruneReport, DockerError>;
+
+    /// 列出容器、镜像、卷、网络和构建缓存及其占用的空间 (`system df`)
+    fn disk_usage(&self) -> Result<DiskUsage, DockerError>;
+}
+
+/// 选择可用的容器后端
+///
+/// 优先使用 Docker Engine API（本地 unix 套接字），不可用时回退到 `docker` 命令行。
+pub fn detect() -> Arc<dyn ContainerBackend> {
+    #[cfg(unix)]
+    {
+        if let Some(backend) = EngineApiBackend::from_env() {
+            if backend.ping() {
+                return Arc::new(backend);
+            }
+        }
+    }
+
+    Arc::new(CliBackend)
+}
+
+/// 容器详细信息
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerDetails {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub created: String,
+    pub state: ContainerState,
+    pub ports: Vec<PublishedPort>,
+    pub labels: HashMap<String, String>,
+    pub tty: bool,
+}
+
+/// 容器运行状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerState {
+    /// created / running / paused / restarting / exited / dead
+    pub status: String,
+    pub running: bool,
+    pub exit_code: i64,
+    /// healthcheck 状态：starting / healthy / unhealthy，未配置时为 None
+    pub health: Option<String>,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+/// 已发布的端口
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedPort {
+    pub container_port: u16,
+    pub protocol: String,
+    pub host_ip: Option<String>,
+    pub host_port: Option<u16>,
+}
+
+impl fmt::Display for PublishedPort {
+    /// 与 `docker ps` 相同的格式，如 `0.0.0.0:8080->80/tcp`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.host_port, &self.host_ip) {
+            (Some(host_port), Some(ip)) if ip.contains(':') => {
+                write!(f, "[{}]:{}->", ip, host_port)?
+            }
+            (Some(host_port), Some(ip)) => write!(f, "{}:{}->", ip, host_port)?,
+            (Some(host_port), None) => write!(f, "{}->", host_port)?,
+            (None, _) => {}
+        }
+        write!(f, "{}/{}", self.container_port, self.protocol)
+    }
+}
+
+/// 命令执行结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecOutput {
+    pub exit_code: i64,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl ExecOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// 日志输出流
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// 单条日志
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub stream: OutputStream,
+    pub message: String,
+}
+
+/// 镜像构建结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildOutput {
+    pub tag: String,
+    pub image_id: Option<String>,
+}
+
+/// 运行容器的参数
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub image: String,
+    pub name: String,
+    /// 端口映射，格式: host_port:container_port
+    pub ports: Vec<String>,
+    /// 卷映射，格式: host_path:container_path
+    pub volumes: Vec<String>,
+    /// 环境变量，格式: KEY=VALUE
+    pub env: Vec<String>,
+    pub detach: bool,
+}
+
+/// 清理目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneTarget {
+    /// 所有未使用的容器、网络、镜像和构建缓存 (`docker system prune -a`)
+    System,
+    Images,
+    Containers,
+    /// 没有容器挂载的卷，包括命名卷，按名称逐个删除（见 [`prune::remove_unused_volumes`]）
+    Volumes,
+}
+
+/// 清理范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneScope {
+    /// 只清理带有 mdde 标签的资源（见 [`MANAGED_LABEL`]）
+    Managed,
+    /// 清理所有未使用的资源，包括与 mdde 无关的资源
+    Global,
+}
+
+impl PruneScope {
+    /// 标签过滤条件，形如 `mdde.managed=true`，`Global` 时为 `None`
+    pub fn label_filter(self) -> Option<String> {
+        match self {
+            PruneScope::Managed => Some(format!("{}=true", MANAGED_LABEL)),
+            PruneScope::Global => None,
+        }
+    }
+}
+
+/// 清理结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneReport {
+    /// 被删除的资源 (ID 或名称)
+    pub deleted: Vec<String>,
+    /// 回收的空间（字节）
+    pub space_reclaimed: u64,
+}
+
+/// `docker inspect` / `GET /containers/{id}/json` 的原始结构
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawInspect {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    created: String,
+    #[serde(default)]
+    config: RawConfig,
+    #[serde(default)]
+    state: RawState,
+    #[serde(default)]
+    network_settings: RawNetworkSettings,
+    #[serde(default)]
+    host_config: RawHostConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawConfig {
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    tty: bool,
+    #[serde(default)]
+    labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawState {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    running: bool,
+    #[serde(default)]
+    exit_code: i64,
+    #[serde(default)]
+    started_at: String,
+    #[serde(default)]
+    finished_at: String,
+    #[serde(default)]
+    health: Option<RawHealth>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawHealth {
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawNetworkSettings {
+    #[serde(default)]
+    ports: Option<HashMap<String, Option<Vec<RawPortBinding>>>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawHostConfig {
+    #[serde(default)]
+    port_bindings: Option<HashMap<String, Option<Vec<RawPortBinding>>>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawPortBinding {
+    #[serde(default)]
+    host_ip: String,
+    #[serde(default)]
+    host_port: String,
+}
+
+impl ContainerDetails {
+    /// 从引擎返回的 inspect JSON 解析容器信息
+    pub fn from_inspect_json(value: serde_json::Value) -> Result<Self, DockerError> {
+        let raw: RawInspect = serde_json::from_value(value)
+            .map_err(|e| DockerError::OutputParseFailed(e.to_string()))?;
+
+        // 已停止的容器没有运行时端口信息，使用创建时声明的端口绑定
+        let declared = raw
+            .network_settings
+            .ports
+            .filter(|ports| !ports.is_empty())
+            .or(raw.host_config.port_bindings)
+            .unwrap_or_default();
+
+        let mut ports = Vec::new();
+        for (key, bindings) in declared {
+            let (port, protocol) = key.split_once('/').unwrap_or((key.as_str(), "tcp"));
+            let container_port = port
+                .parse::<u16>()
+                .map_err(|e| DockerError::OutputParseFailed(format!("{}: {}", key, e)))?;
+
+            match bindings {
+                Some(bindings) if !bindings.is_empty() => {
+                    for binding in bindings {
+                        ports.push(PublishedPort {
+                            container_port,
+                            protocol: protocol.to_string(),
+                            host_ip: Some(binding.host_ip).filter(|ip| !ip.is_empty()),
+                            host_port: binding.host_port.parse().ok(),
+                        });
+                    }
+                }
+                _ => ports.push(PublishedPort {
+                    container_port,
+                    protocol: protocol.to_string(),
+                    host_ip: None,
+                    host_port: None,
+                }),
+            }
+        }
+        ports.sort_by(|a, b| {
+            (a.container_port, &a.protocol, &a.host_ip).cmp(&(
+                b.container_port,
+                &b.protocol,
+                &b.host_ip,
+            ))
+        });
+
+        Ok(Self {
+            id: raw.id,
+            name: raw.name.trim_start_matches('/').to_string(),
+            image: raw.config.image,
+            created: raw.created,
+            state: ContainerState {
+                status: raw.state.status,
+                running: raw.state.running,
+                exit_code: raw.state.exit_code,
+                health: raw.state.health.map(|h| h.status).filter(|s| !s.is_empty()),
+                started_at: raw.state.started_at,
+                finished_at: raw.state.finished_at,
+            },
+            ports,
+            labels: raw.config.labels.unwrap_or_default(),
+            tty: raw.config.tty,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_inspect_json() {
+        let value = json!({
+            "Id": "abc123",
+            "Name": "/mdde-dev",
+            "Created": "2024-01-01T00:00:00Z",
+            "Config": {
+                "Image": "node:22",
+                "Tty": false,
+                "Labels": { "com.docker.compose.service": "app" }
+            },
+            "State": {
+                "Status": "running",
+                "Running": true,
+                "ExitCode": 0,
+                "StartedAt": "2024-01-01T00:00:01Z",
+                "FinishedAt": "0001-01-01T00:00:00Z",
+                "Health": { "Status": "healthy" }
+            },
+            "NetworkSettings": {
+                "Ports": {
+                    "80/tcp": [
+                        { "HostIp": "0.0.0.0", "HostPort": "8080" },
+                        { "HostIp": "::", "HostPort": "8080" }
+                    ],
+                    "53/udp": null
+                }
+            }
+        });
+
+        let details = ContainerDetails::from_inspect_json(value).unwrap();
+        assert_eq!(details.name, "mdde-dev");
+        assert_eq!(details.image, "node:22");
+        assert!(details.state.running);
+        assert_eq!(details.state.health.as_deref(), Some("healthy"));
+        assert_eq!(
+            details.labels.get("com.docker.compose.service").unwrap(),
+            "app"
+        );
+        assert_eq!(details.ports.len(), 3);
+        assert_eq!(details.ports[0].container_port, 53);
+        assert_eq!(details.ports[0].protocol, "udp");
+        assert_eq!(details.ports[0].host_port, None);
+        assert_eq!(details.ports[1].host_port, Some(8080));
+    }
+
+    #[test]
+    fn test_from_inspect_json_stopped_container() {
+        let value = json!({
+            "Id": "def456",
+            "Name": "/stopped",
+            "State": {"Status": "exited", "Running": false, "ExitCode": 0},
+            "NetworkSettings": {"Ports": {}},
+            "HostConfig": {
+                "PortBindings": {"80/tcp": [{"HostIp": "", "HostPort": "8080"}]}
+            }
+        });
+
+        let details = ContainerDetails::from_inspect_json(value).unwrap();
+        assert_eq!(details.ports.len(), 1);
+        assert_eq!(details.ports[0].host_port, Some(8080));
+        assert_eq!(details.ports[0].host_ip, None);
+    }
+
+    #[test]
+    fn test_published_port_display() {
+        let mut port = PublishedPort {
+            container_port: 80,
+            protocol: "tcp".to_string(),
+            host_ip: Some("0.0.0.0".to_string()),
+            host_port: Some(8080),
+        };
+        assert_eq!(port.to_string(), "0.0.0.0:8080->80/tcp");
+
+        port.host_ip = Some("::".to_string());
+        assert_eq!(port.to_string(), "[::]:8080->80/tcp");
+
+        port.host_port = None;
+        assert_eq!(port.to_string(), "80/tcp");
+    }
+}
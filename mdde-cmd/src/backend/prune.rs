@@ -0,0 +1,613 @@
+use super::{ContainerBackend, PruneReport, PruneScope, PruneTarget};
+use crate::compose::MANAGED_LABEL;
+use crate::docker::DockerError;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// 资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceKind {
+    Container,
+    Image,
+    Volume,
+    Network,
+    BuildCache,
+}
+
+impl ResourceKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResourceKind::Container => "container",
+            ResourceKind::Image => "image",
+            ResourceKind::Volume => "volume",
+            ResourceKind::Network => "network",
+            ResourceKind::BuildCache => "build cache",
+        }
+    }
+}
+
+/// 清理时将被删除的一项资源
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruneCandidate {
+    pub kind: ResourceKind,
+    pub id: String,
+    /// 容器名、镜像标签或卷名，没有时为短 ID
+    pub name: String,
+    /// 占用的空间（字节），无法获取时为 `None`
+    pub size: Option<u64>,
+}
+
+/// 引擎自带、不会被清理的网络
+const PREDEFINED_NETWORKS: [&str; 4] = ["bridge", "host", "none", "podman"];
+
+/// Docker 23 起为匿名卷添加的标签，`volume prune` 默认只删除带有该标签的卷
+const ANONYMOUS_VOLUME_LABEL: &str = "com.docker.volume.anonymous";
+
+/// 容器引擎中的资源及其占用的空间，对应 Engine API 的 `/system/df` 和 `/networks`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub containers: Vec<ContainerUsage>,
+    pub images: Vec<ImageUsage>,
+    pub volumes: Vec<VolumeUsage>,
+    pub networks: Vec<NetworkUsage>,
+    pub build_cache: Vec<BuildCacheUsage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContainerUsage {
+    pub id: String,
+    pub name: String,
+    /// 镜像 ID（`sha256:...`）
+    pub image_id: String,
+    /// created / running / paused / restarting / exited / dead
+    pub state: String,
+    pub labels: HashMap<String, String>,
+    /// 容器可写层的大小
+    pub size: Option<u64>,
+    /// 挂载的命名卷
+    pub volumes: Vec<String>,
+    /// 连接的网络名称
+    pub networks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageUsage {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub labels: HashMap<String, String>,
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VolumeUsage {
+    pub name: String,
+    pub labels: HashMap<String, String>,
+    pub size: Option<u64>,
+}
+
+impl VolumeUsage {
+    /// 匿名卷：Docker 23 起带有 [`ANONYMOUS_VOLUME_LABEL`] 标签，其他引擎以 64 位十六进制的随机 ID 命名
+    pub fn is_anonymous(&self) -> bool {
+        self.labels.contains_key(ANONYMOUS_VOLUME_LABEL)
+            || (self.name.len() == 64 && self.name.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkUsage {
+    pub id: String,
+    pub name: String,
+    pub labels: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuildCacheUsage {
+    pub id: String,
+    pub size: Option<u64>,
+    pub in_use: bool,
+}
+
+impl ContainerUsage {
+    /// 已停止，container prune 会删除
+    pub fn is_stopped(&self) -> bool {
+        matches!(self.state.as_str(), "created" | "exited" | "dead")
+    }
+}
+
+impl ImageUsage {
+    /// 没有标签的悬空镜像
+    pub fn is_dangling(&self) -> bool {
+        self.tags.iter().all(|tag| tag == "<none>:<none>")
+    }
+
+    /// 第一个标签，没有标签时为短 ID
+    pub fn display_name(&self) -> String {
+        self.tags
+            .iter()
+            .find(|tag| *tag != "<none>:<none>")
+            .cloned()
+            .unwrap_or_else(|| short_id(&self.id).to_string())
+    }
+}
+
+impl DiskUsage {
+    /// 解析 Engine API `/system/df` 的返回
+    pub fn from_api_json(value: Value) -> Result<Self, DockerError> {
+        let raw: RawDiskUsage = serde_json::from_value(value)
+            .map_err(|e| DockerError::OutputParseFailed(e.to_string()))?;
+
+        Ok(Self {
+            containers: raw
+                .containers
+                .into_iter()
+                .map(|c| ContainerUsage {
+                    name: c
+                        .names
+                        .first()
+                        .map(|name| name.trim_start_matches('/').to_string())
+                        .unwrap_or_else(|| short_id(&c.id).to_string()),
+                    id: c.id,
+                    image_id: c.image_id,
+                    state: c.state,
+                    labels: c.labels,
+                    size: size(c.size_rw),
+                    volumes: c
+                        .mounts
+                        .into_iter()
+                        .filter(|mount| mount.kind == "volume")
+                        .filter_map(|mount| mount.name)
+                        .collect(),
+                    networks: c
+                        .network_settings
+                        .map(|settings| settings.networks.into_keys().collect())
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            images: raw
+                .images
+                .into_iter()
+                .map(|i| ImageUsage {
+                    id: i.id,
+                    tags: i.repo_tags,
+                    labels: i.labels,
+                    size: size(i.size),
+                })
+                .collect(),
+            volumes: raw
+                .volumes
+                .into_iter()
+                .map(|v| VolumeUsage {
+                    name: v.name,
+                    labels: v.labels,
+                    size: v.usage_data.and_then(|usage| size(usage.size)),
+                })
+                .collect(),
+            networks: Vec::new(),
+            build_cache: raw
+                .build_cache
+                .into_iter()
+                .map(|b| BuildCacheUsage {
+                    id: b.id,
+                    size: size(b.size),
+                    in_use: b.in_use,
+                })
+                .collect(),
+        })
+    }
+
+    /// 解析 Engine API `/networks` 的返回，podman 命令行的字段名为小写
+    pub fn networks_from_json(value: &Value) -> Vec<NetworkUsage> {
+        let field = |network: &Value, name: &str| -> Value {
+            let lower = name.to_lowercase();
+            network
+                .get(name)
+                .or_else(|| network.get(&lower))
+                .cloned()
+                .unwrap_or(Value::Null)
+        };
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|network| NetworkUsage {
+                id: field(network, "Id")
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                name: field(network, "Name")
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                labels: serde_json::from_value(field(network, "Labels")).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// `prune` 将会删除的资源，规则与对应的 prune 命令一致
+    ///
+    /// - 容器：已停止的容器
+    /// - 镜像：没有容器使用的镜像；全局清理单独的镜像时只包括悬空镜像，清理系统时先去掉将被删除的容器
+    /// - 网络：清理系统时包括没有运行中容器连接的网络，引擎自带的网络除外
+    /// - 卷：没有容器挂载的卷；mdde 的卷包括命名卷，全局清理时与 `volume prune` 一样只包括匿名卷，
+    ///   不会删除其他项目的命名数据卷（见 [`remove_unused_volumes`]）
+    /// - 构建缓存：只在全局清理系统时包括未使用的缓存
+    pub fn prune_candidates(&self, target: PruneTarget, scope: PruneScope) -> Vec<PruneCandidate> {
+        let in_scope = |labels: &HashMap<String, String>| {
+            scope == PruneScope::Global
+                || labels
+                    .get(MANAGED_LABEL)
+                    .is_some_and(|value| value == "true")
+        };
+        let mut candidates = Vec::new();
+
+        let containers: Vec<&ContainerUsage> =
+            if matches!(target, PruneTarget::System | PruneTarget::Containers) {
+                self.containers
+                    .iter()
+                    .filter(|c| c.is_stopped() && in_scope(&c.labels))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+        let removed: HashSet<&str> = containers.iter().map(|c| c.id.as_str()).collect();
+        candidates.extend(containers.iter().map(|c| PruneCandidate {
+            kind: ResourceKind::Container,
+            id: c.id.clone(),
+            name: c.name.clone(),
+            size: c.size,
+        }));
+
+        if matches!(target, PruneTarget::System | PruneTarget::Images) {
+            let all = target == PruneTarget::System || scope == PruneScope::Managed;
+            let used: HashSet<&str> = self
+                .containers
+                .iter()
+                .filter(|c| !removed.contains(c.id.as_str()))
+                .map(|c| c.image_id.as_str())
+                .collect();
+            candidates.extend(
+                self.images
+                    .iter()
+                    .filter(|i| !used.contains(i.id.as_str()) && in_scope(&i.labels))
+                    .filter(|i| all || i.is_dangling())
+                    .map(|i| PruneCandidate {
+                        kind: ResourceKind::Image,
+                        id: i.id.clone(),
+                        name: i.display_name(),
+                        size: i.size,
+                    }),
+            );
+        }
+
+        if target == PruneTarget::System {
+            // network prune 只保留有端点的网络，已停止的容器不占用端点
+            let used: HashSet<&str> = self
+                .containers
+                .iter()
+                .filter(|c| !c.is_stopped())
+                .flat_map(|c| c.networks.iter().map(String::as_str))
+                .collect();
+            candidates.extend(
+                self.networks
+                    .iter()
+                    .filter(|n| !PREDEFINED_NETWORKS.contains(&n.name.as_str()))
+                    .filter(|n| !used.contains(n.name.as_str()) && in_scope(&n.labels))
+                    .map(|n| PruneCandidate {
+                        kind: ResourceKind::Network,
+                        id: n.id.clone(),
+                        name: n.name.clone(),
+                        size: None,
+                    }),
+            );
+        }
+
+        if target == PruneTarget::Volumes {
+            let used: HashSet<&str> = self
+                .containers
+                .iter()
+                .flat_map(|c| c.volumes.iter().map(String::as_str))
+                .collect();
+            candidates.extend(
+                self.volumes
+                    .iter()
+                    .filter(|v| !used.contains(v.name.as_str()) && in_scope(&v.labels))
+                    .filter(|v| scope == PruneScope::Managed || v.is_anonymous())
+                    .map(|v| PruneCandidate {
+                        kind: ResourceKind::Volume,
+                        id: v.name.clone(),
+                        name: v.name.clone(),
+                        size: v.size,
+                    }),
+            );
+        }
+
+        if target == PruneTarget::System && scope == PruneScope::Global {
+            candidates.extend(self.build_cache.iter().filter(|b| !b.in_use).map(|b| {
+                PruneCandidate {
+                    kind: ResourceKind::BuildCache,
+                    id: b.id.clone(),
+                    name: short_id(&b.id).to_string(),
+                    size: b.size,
+                }
+            }));
+        }
+
+        candidates
+    }
+}
+
+/// 按 [`DiskUsage::prune_candidates`] 列出的卷逐个删除
+///
+/// Docker 23 起 `volume prune` 默认只删除匿名卷，podman 和旧版本则删除所有未使用的卷，
+/// 逐个删除保证不同引擎实际删除的卷都与 `clean --dry-run` 列出的一致。
+pub fn remove_unused_volumes(
+    backend: &dyn ContainerBackend,
+    scope: PruneScope,
+) -> Result<PruneReport, DockerError> {
+    let mut report = PruneReport::default();
+    for candidate in backend
+        .disk_usage()?
+        .prune_candidates(PruneTarget::Volumes, scope)
+    {
+        backend.remove_volume(&candidate.id)?;
+        report.space_reclaimed += candidate.size.unwrap_or_default();
+        report.deleted.push(candidate.name);
+    }
+    Ok(report)
+}
+
+/// 12 位的短 ID
+fn short_id(id: &str) -> &str {
+    let id = id.trim_start_matches("sha256:");
+    &id[..id.len().min(12)]
+}
+
+/// Engine API 用 -1 表示未计算的大小
+fn size(value: i64) -> Option<u64> {
+    u64::try_from(value).ok()
+}
+
+/// 字段为 null 时使用默认值
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawDiskUsage {
+    #[serde(default, deserialize_with = "null_as_default")]
+    containers: Vec<RawContainer>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    images: Vec<RawImage>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    volumes: Vec<RawVolume>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    build_cache: Vec<RawBuildCache>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawContainer {
+    id: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    names: Vec<String>,
+    #[serde(default, rename = "ImageID")]
+    image_id: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default = "unknown_size")]
+    size_rw: i64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    labels: HashMap<String, String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    mounts: Vec<RawMount>,
+    network_settings: Option<RawNetworkSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawNetworkSettings {
+    #[serde(default, deserialize_with = "null_as_default")]
+    networks: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawMount {
+    #[serde(default, rename = "Type")]
+    kind: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawImage {
+    id: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    repo_tags: Vec<String>,
+    #[serde(default = "unknown_size")]
+    size: i64,
+    #[serde(default, deserialize_with = "null_as_default")]
+    labels: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawVolume {
+    name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    labels: HashMap<String, String>,
+    usage_data: Option<RawVolumeUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawVolumeUsage {
+    #[serde(default = "unknown_size")]
+    size: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawBuildCache {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(default = "unknown_size")]
+    size: i64,
+    #[serde(default)]
+    in_use: bool,
+}
+
+fn unknown_size() -> i64 {
+    -1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn disk_usage() -> DiskUsage {
+        let mut usage = DiskUsage::from_api_json(json!({
+            "Containers": [
+                {
+                    "Id": "c1", "Names": ["/demo-app-1"], "ImageID": "sha256:app",
+                    "State": "exited", "SizeRw": 1024,
+                    "Labels": {"mdde.managed": "true"},
+                    "Mounts": [{"Type": "volume", "Name": "demo_data"}],
+                    "NetworkSettings": {"Networks": {"demo_default": {}}}
+                },
+                {
+                    "Id": "c2", "Names": ["/colleague"], "ImageID": "sha256:colleague",
+                    "State": "exited", "Labels": null, "Mounts": []
+                },
+                {
+                    "Id": "c3", "Names": ["/web"], "ImageID": "sha256:nginx",
+                    "State": "running", "Labels": {},
+                    "NetworkSettings": {"Networks": {"bridge": {}, "web": {}}}
+                }
+            ],
+            "Images": [
+                {"Id": "sha256:app", "RepoTags": ["demo-app:latest"], "Size": 2048, "Labels": {"mdde.managed": "true"}},
+                {"Id": "sha256:old", "RepoTags": null, "Size": 4096, "Labels": {"mdde.managed": "true"}},
+                {"Id": "sha256:colleague", "RepoTags": ["colleague:latest"], "Size": 8192, "Labels": null},
+                {"Id": "sha256:dangling", "RepoTags": ["<none>:<none>"], "Size": 16, "Labels": null},
+                {"Id": "sha256:nginx", "RepoTags": ["nginx:latest"], "Size": 100, "Labels": null}
+            ],
+            "Volumes": [
+                {"Name": "demo_data", "Labels": {"mdde.managed": "true"}, "UsageData": {"Size": 512, "RefCount": 1}},
+                {"Name": "demo_cache", "Labels": {"mdde.managed": "true"}, "UsageData": {"Size": -1, "RefCount": 0}},
+                {"Name": "other", "Labels": null, "UsageData": null},
+                {"Name": "3f9a0c1e5b7d2f4a6c8e0b1d3f5a7c9e1b3d5f7a9c0e2b4d6f8a0c2e4b6d8f0a", "Labels": null},
+                {"Name": "ad1c", "Labels": {"com.docker.volume.anonymous": ""}}
+            ],
+            "BuildCache": [
+                {"ID": "cache1", "Size": 300, "InUse": false},
+                {"ID": "cache2", "Size": 700, "InUse": true}
+            ]
+        }))
+        .unwrap();
+        usage.networks = DiskUsage::networks_from_json(&json!([
+            {"Id": "n1", "Name": "bridge", "Labels": {}},
+            {"Id": "n2", "Name": "demo_default", "Labels": {"mdde.managed": "true"}},
+            {"Id": "n3", "Name": "web", "Labels": {"mdde.managed": "true"}},
+            // podman 命令行的字段名为小写
+            {"id": "n4", "name": "unused", "labels": null}
+        ]));
+        usage
+    }
+
+    fn names(candidates: &[PruneCandidate]) -> Vec<(&str, &str)> {
+        candidates
+            .iter()
+            .map(|c| (c.kind.as_str(), c.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_from_api_json() {
+        let usage = disk_usage();
+        assert_eq!(usage.containers[0].name, "demo-app-1");
+        assert_eq!(usage.containers[0].volumes, vec!["demo_data"]);
+        assert_eq!(usage.containers[0].networks, vec!["demo_default"]);
+        assert_eq!(usage.networks[3].id, "n4");
+        assert_eq!(usage.networks[3].name, "unused");
+        assert_eq!(usage.containers[1].size, None);
+        assert!(usage.images[1].is_dangling());
+        assert_eq!(usage.volumes[0].size, Some(512));
+        assert_eq!(usage.volumes[1].size, None);
+        assert!(DiskUsage::from_api_json(json!({"BuildCache": null}))
+            .unwrap()
+            .build_cache
+            .is_empty());
+    }
+
+    #[test]
+    fn test_managed_candidates() {
+        let usage = disk_usage();
+        let candidates = usage.prune_candidates(PruneTarget::System, PruneScope::Managed);
+        assert_eq!(
+            names(&candidates),
+            vec![
+                ("container", "demo-app-1"),
+                // 容器被删除后镜像不再被使用
+                ("image", "demo-app:latest"),
+                ("image", "old"),
+                // 只有已停止的容器连接，运行中容器使用的网络和引擎自带的网络不清理
+                ("network", "demo_default")
+            ]
+        );
+        assert_eq!(candidates[0].size, Some(1024));
+
+        let candidates = usage.prune_candidates(PruneTarget::Images, PruneScope::Managed);
+        assert_eq!(names(&candidates), vec![("image", "old")]);
+
+        let candidates = usage.prune_candidates(PruneTarget::Volumes, PruneScope::Managed);
+        assert_eq!(names(&candidates), vec![("volume", "demo_cache")]);
+    }
+
+    #[test]
+    fn test_global_candidates() {
+        let usage = disk_usage();
+        let candidates = usage.prune_candidates(PruneTarget::Images, PruneScope::Global);
+        assert_eq!(
+            names(&candidates),
+            vec![("image", "old"), ("image", "dangling")]
+        );
+
+        let candidates = usage.prune_candidates(PruneTarget::System, PruneScope::Global);
+        assert_eq!(
+            names(&candidates),
+            vec![
+                ("container", "demo-app-1"),
+                ("container", "colleague"),
+                ("image", "demo-app:latest"),
+                ("image", "old"),
+                ("image", "colleague:latest"),
+                ("image", "dangling"),
+                ("network", "demo_default"),
+                ("network", "unused"),
+                ("build cache", "cache1")
+            ]
+        );
+
+        // 全局清理卷时不包括其他项目的命名卷
+        let candidates = usage.prune_candidates(PruneTarget::Volumes, PruneScope::Global);
+        assert_eq!(
+            names(&candidates),
+            vec![
+                (
+                    "volume",
+                    "3f9a0c1e5b7d2f4a6c8e0b1d3f5a7c9e1b3d5f7a9c0e2b4d6f8a0c2e4b6d8f0a"
+                ),
+                ("volume", "ad1c")
+            ]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use super::{ContainerBackend, ContainerDetails, PublishedPort};
+use crate::compose::ComposeProject;
+use crate::docker::DockerError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// compose 写入容器的服务名标签
+pub const SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// compose 写入容器的项目名称标签，见 [`ComposeProject::name`]
+pub const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// compose 写入容器的项目目录标签
+pub const COMPOSE_WORKING_DIR_LABEL: &str = "com.docker.compose.project.working_dir";
+
+/// compose 项目中单个服务容器的状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub service: String,
+    pub name: String,
+    pub container_id: String,
+    pub image: String,
+    /// created / running / paused / restarting / exited / dead
+    pub state: String,
+    /// healthcheck 状态，未配置时为 None
+    pub health: Option<String>,
+    /// 运行时长，仅在运行中时有值
+    pub uptime: Option<String>,
+    pub exit_code: i64,
+    pub ports: Vec<PublishedPort>,
+}
+
+impl ServiceStatus {
+    /// 根据容器详细信息构建，`now` 用于计算运行时长
+    pub fn from_details(details: ContainerDetails, now: DateTime<Utc>) -> Self {
+        let uptime = if details.state.running {
+            DateTime::parse_from_rfc3339(&details.state.started_at)
+                .ok()
+                .map(|started| format_uptime((now - started.with_timezone(&Utc)).num_seconds()))
+        } else {
+            None
+        };
+
+        let mut ports = details.ports;
+        ports.sort_by(|a, b| {
+            (a.container_port, &a.protocol, &a.host_ip).cmp(&(
+                b.container_port,
+                &b.protocol,
+                &b.host_ip,
+            ))
+        });
+
+        Self {
+            service: details
+                .labels
+                .get(SERVICE_LABEL)
+                .cloned()
+                .unwrap_or_else(|| details.name.clone()),
+            name: details.name,
+            container_id: details.id,
+            image: details.image,
+            state: details.state.status,
+            health: details.state.health,
+            uptime,
+            exit_code: details.state.exit_code,
+            ports,
+        }
+    }
+
+    /// 是否正在运行
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+
+    /// 容器 ID 的短格式
+    pub fn short_id(&self) -> &str {
+        let id = self.container_id.trim_start_matches("sha256:");
+        &id[..id.len().min(12)]
+    }
+}
+
+/// 将秒数格式化为紧凑的时长，如 `3d 4h`、`2h 5m`、`45s`
+pub fn format_uptime(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let (days, hours, minutes, secs) = (
+        seconds / 86_400,
+        seconds % 86_400 / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60,
+    );
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// 获取 compose 项目中所有服务容器的状态，按服务名排序
+///
+/// 容器列表来自 `compose ps`，详细信息来自后端的 inspect，不依赖命令行的表格输出。
+pub fn compose_services(
+    backend: &dyn ContainerBackend,
+    project: &ComposeProject,
+) -> Result<Vec<ServiceStatus>, DockerError> {
+    let now = Utc::now();
+    let mut services = Vec::new();
+
+    for id in backend.compose_container_ids(project)? {
+        match backend.inspect(&id) {
+            Ok(details) => services.push(ServiceStatus::from_details(details, now)),
+            // 容器可能在两次调用之间被删除
+            Err(DockerError::ContainerNotFound(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    services.sort_by(|a, b| (&a.service, &a.name).cmp(&(&b.service, &b.name)));
+    Ok(services)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ContainerState;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_from_details() {
+        let details = ContainerDetails {
+            id: "0123456789abcdef0123".to_string(),
+            name: "demo-app-1".to_string(),
+            image: "node:22".to_string(),
+            state: ContainerState {
+                status: "running".to_string(),
+                running: true,
+                health: Some("healthy".to_string()),
+                started_at: "2024-01-01T00:00:00.123456789Z".to_string(),
+                ..Default::default()
+            },
+            ports: vec![
+                PublishedPort {
+                    container_port: 443,
+                    protocol: "tcp".to_string(),
+                    host_ip: None,
+                    host_port: None,
+                },
+                PublishedPort {
+                    container_port: 80,
+                    protocol: "tcp".to_string(),
+                    host_ip: Some("0.0.0.0".to_string()),
+                    host_port: Some(8080),
+                },
+            ],
+            labels: HashMap::from([(SERVICE_LABEL.to_string(), "app".to_string())]),
+            ..Default::default()
+        };
+        let now = DateTime::parse_from_rfc3339("2024-01-01T02:05:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let status = ServiceStatus::from_details(details, now);
+        assert_eq!(status.service, "app");
+        assert_eq!(status.short_id(), "0123456789ab");
+        assert_eq!(status.uptime.as_deref(), Some("2h 5m"));
+        assert_eq!(status.health.as_deref(), Some("healthy"));
+        assert_eq!(status.ports[0].container_port, 80);
+        assert!(status.is_running());
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(-5), "0s");
+        assert_eq!(format_uptime(45), "45s");
+        assert_eq!(format_uptime(125), "2m 5s");
+        assert_eq!(format_uptime(3 * 86_400 + 4 * 3_600 + 59), "3d 4h");
+    }
+}
@@ -0,0 +1,180 @@
+use crate::docker::DockerError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 容器资源使用情况的一次采样
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerStats {
+    /// CPU 使用率，100 表示占满一个核心
+    pub cpu_percent: f64,
+    pub memory_usage: u64,
+    pub memory_limit: u64,
+    pub network_rx: u64,
+    pub network_tx: u64,
+}
+
+impl ContainerStats {
+    /// 解析 Engine API `/containers/{id}/stats?stream=false` 的返回
+    pub fn from_api_json(value: &Value) -> Self {
+        let cpu = &value["cpu_stats"];
+        let precpu = &value["precpu_stats"];
+        let cpu_delta = cpu["cpu_usage"]["total_usage"].as_f64().unwrap_or_default()
+            - precpu["cpu_usage"]["total_usage"]
+                .as_f64()
+                .unwrap_or_default();
+        let system_delta = cpu["system_cpu_usage"].as_f64().unwrap_or_default()
+            - precpu["system_cpu_usage"].as_f64().unwrap_or_default();
+        let online_cpus = cpu["online_cpus"]
+            .as_f64()
+            .or_else(|| {
+                cpu["cpu_usage"]["percpu_usage"]
+                    .as_array()
+                    .map(|cpus| cpus.len() as f64)
+            })
+            .unwrap_or(1.0);
+        let cpu_percent = if cpu_delta > 0.0 && system_delta > 0.0 {
+            cpu_delta / system_delta * online_cpus * 100.0
+        } else {
+            0.0
+        };
+
+        // 与 docker stats 一致，内存使用量不计入页缓存（cgroup v1 为 cache，v2 为 inactive_file）
+        let memory = &value["memory_stats"];
+        let usage = memory["usage"].as_u64().unwrap_or_default();
+        let cache = memory["stats"]["inactive_file"]
+            .as_u64()
+            .or_else(|| memory["stats"]["total_inactive_file"].as_u64())
+            .or_else(|| memory["stats"]["cache"].as_u64())
+            .unwrap_or_default();
+
+        let (network_rx, network_tx) = value["networks"]
+            .as_object()
+            .into_iter()
+            .flat_map(|networks| networks.values())
+            .fold((0, 0), |(rx, tx), network| {
+                (
+                    rx + network["rx_bytes"].as_u64().unwrap_or_default(),
+                    tx + network["tx_bytes"].as_u64().unwrap_or_default(),
+                )
+            });
+
+        Self {
+            cpu_percent,
+            memory_usage: usage.saturating_sub(cache),
+            memory_limit: memory["limit"].as_u64().unwrap_or_default(),
+            network_rx,
+            network_tx,
+        }
+    }
+
+    /// 解析 `docker stats --no-stream --format '{{json .}}'` 的单行输出
+    pub fn from_cli_json(line: &str) -> Result<Self, DockerError> {
+        let raw: RawCliStats = serde_json::from_str(line)
+            .map_err(|e| DockerError::OutputParseFailed(e.to_string()))?;
+        let (memory_usage, memory_limit) = split_pair(&raw.mem_usage);
+        let (network_rx, network_tx) = split_pair(&raw.net_io);
+
+        Ok(Self {
+            cpu_percent: raw
+                .cpu_perc
+                .trim()
+                .trim_end_matches('%')
+                .parse()
+                .unwrap_or_default(),
+            memory_usage,
+            memory_limit,
+            network_rx,
+            network_tx,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawCliStats {
+    #[serde(default, rename = "CPUPerc")]
+    cpu_perc: String,
+    #[serde(default)]
+    mem_usage: String,
+    #[serde(default, rename = "NetIO")]
+    net_io: String,
+}
+
+/// 解析形如 `12.5MiB / 1.944GiB` 的两个容量
+fn split_pair(text: &str) -> (u64, u64) {
+    let (left, right) = text.split_once('/').unwrap_or((text, ""));
+    (
+        parse_bytes(left).unwrap_or_default(),
+        parse_bytes(right).unwrap_or_default(),
+    )
+}
+
+/// 解析 docker 命令行输出的容量，同时支持十进制（kB、MB）和二进制（KiB、MiB）单位
+pub(crate) fn parse_bytes(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KIB" => 1024.0,
+        "MIB" => 1024.0 * 1024.0,
+        "GIB" => 1024.0 * 1024.0 * 1024.0,
+        "TIB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_api_json() {
+        let value = json!({
+            "cpu_stats": {
+                "cpu_usage": {"total_usage": 300},
+                "system_cpu_usage": 2000,
+                "online_cpus": 2
+            },
+            "precpu_stats": {
+                "cpu_usage": {"total_usage": 100},
+                "system_cpu_usage": 1000
+            },
+            "memory_stats": {
+                "usage": 10_000,
+                "limit": 100_000,
+                "stats": {"inactive_file": 2_000}
+            },
+            "networks": {
+                "eth0": {"rx_bytes": 100, "tx_bytes": 10},
+                "eth1": {"rx_bytes": 50, "tx_bytes": 5}
+            }
+        });
+
+        let stats = ContainerStats::from_api_json(&value);
+        assert!((stats.cpu_percent - 40.0).abs() < f64::EPSILON);
+        assert_eq!(stats.memory_usage, 8_000);
+        assert_eq!(stats.memory_limit, 100_000);
+        assert_eq!((stats.network_rx, stats.network_tx), (150, 15));
+    }
+
+    #[test]
+    fn test_from_cli_json() {
+        let line =
+            r#"{"CPUPerc":"1.50%","MemUsage":"1.5MiB / 2GiB","NetIO":"1.2kB / 0B","Name":"demo"}"#;
+        let stats = ContainerStats::from_cli_json(line).unwrap();
+        assert!((stats.cpu_percent - 1.5).abs() < f64::EPSILON);
+        assert_eq!(stats.memory_usage, 1_572_864);
+        assert_eq!(stats.memory_limit, 2_147_483_648);
+        assert_eq!((stats.network_rx, stats.network_tx), (1_200, 0));
+    }
+}
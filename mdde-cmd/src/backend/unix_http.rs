@@ -0,0 +1,268 @@
+use crate::docker::DockerError;
+use serde::de::DeserializeOwned;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+/// 通过 unix 套接字返回的 HTTP 响应
+pub struct Response {
+    pub status: u16,
+    body: Box<dyn Read>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 读取完整的响应体
+    pub fn bytes(mut self) -> Result<Vec<u8>, DockerError> {
+        let mut buf = Vec::new();
+        self.body
+            .read_to_end(&mut buf)
+            .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+        Ok(buf)
+    }
+
+    pub fn json<T: DeserializeOwned>(self) -> Result<T, DockerError> {
+        serde_json::from_slice(&self.bytes()?)
+            .map_err(|e| DockerError::OutputParseFailed(e.to_string()))
+    }
+
+    /// 以流的方式读取响应体（用于日志跟踪、构建进度等长连接）
+    pub fn into_reader(self) -> Box<dyn Read> {
+        self.body
+    }
+
+    /// 将失败的响应转换为错误，Engine API 的错误体格式为 `{"message": "..."}`
+    pub fn into_error(self) -> DockerError {
+        let status = self.status;
+        let message = match self.bytes() {
+            Ok(body) => serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).trim().to_string()),
+            Err(e) => e.to_string(),
+        };
+        DockerError::ApiError(status, message)
+    }
+}
+
+/// 发送一个 HTTP/1.1 请求
+pub fn request(
+    socket: &Path,
+    method: &str,
+    path: &str,
+    body: Option<(&str, &[u8])>,
+) -> Result<Response, DockerError> {
+    let mut stream =
+        UnixStream::connect(socket).map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: docker\r\nUser-Agent: mdde-cmd/{}\r\nConnection: close\r\n",
+        method,
+        path,
+        env!("CARGO_PKG_VERSION")
+    );
+    match body {
+        Some((content_type, data)) => {
+            head.push_str(&format!(
+                "Content-Type: {}\r\nContent-Length: {}\r\n",
+                content_type,
+                data.len()
+            ));
+        }
+        None => head.push_str("Content-Length: 0\r\n"),
+    }
+    head.push_str("\r\n");
+
+    stream
+        .write_all(head.as_bytes())
+        .and_then(|_| match body {
+            Some((_, data)) => stream.write_all(data),
+            None => Ok(()),
+        })
+        .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+
+    read_response(BufReader::new(stream)).map_err(|e| DockerError::CommandFailed(e.to_string()))
+}
+
+/// 解析响应头，并根据传输编码包装响应体
+fn read_response<R: BufRead + 'static>(mut reader: R) -> io::Result<Response> {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| invalid_data(format!("无效的状态行: {}", status_line.trim())))?;
+
+    let mut content_length = None;
+    let mut chunked = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse::<u64>().ok();
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.eq_ignore_ascii_case("chunked");
+            }
+        }
+    }
+
+    let body: Box<dyn Read> = if chunked {
+        Box::new(ChunkedReader::new(reader))
+    } else if let Some(length) = content_length {
+        Box::new(reader.take(length))
+    } else {
+        Box::new(reader)
+    };
+
+    Ok(Response { status, body })
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// `Transfer-Encoding: chunked` 解码器
+struct ChunkedReader<R> {
+    inner: R,
+    remaining: usize,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            done: false,
+        }
+    }
+
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let mut line = String::new();
+        self.inner.read_line(&mut line)?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        self.remaining = usize::from_str_radix(size, 16)
+            .map_err(|_| invalid_data(format!("无效的 chunk 大小: {}", line.trim())))?;
+
+        if self.remaining == 0 {
+            // 跳过 trailer
+            loop {
+                line.clear();
+                if self.inner.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                    break;
+                }
+            }
+            self.done = true;
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            self.next_chunk()?;
+            if self.done {
+                return Ok(0);
+            }
+        }
+
+        let max = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "chunk 数据不完整",
+            ));
+        }
+        self.remaining -= n;
+        if self.remaining == 0 {
+            let mut crlf = [0u8; 2];
+            self.inner.read_exact(&mut crlf)?;
+        }
+        Ok(n)
+    }
+}
+
+/// 读取一帧多路复用流 (`application/vnd.docker.multiplexed-stream`)
+///
+/// 每帧包含 8 字节头：流类型 (1=stdout, 2=stderr)、3 字节填充、4 字节大端长度。
+/// 流结束时返回 `None`。
+pub fn read_frame(reader: &mut dyn Read) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0u8; 8];
+    let mut filled = 0;
+    while filled < header.len() {
+        let n = reader.read(&mut header[filled..])?;
+        if n == 0 {
+            if filled == 0 {
+                return Ok(None);
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "多路复用帧头不完整",
+            ));
+        }
+        filled += n;
+    }
+
+    let size = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let mut payload = vec![0u8; size];
+    reader.read_exact(&mut payload)?;
+    Ok(Some((header[0], payload)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_chunked_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n";
+        let response = read_response(Cursor::new(raw.to_vec())).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.bytes().unwrap(), b"hello, world");
+    }
+
+    #[test]
+    fn test_read_content_length_response() {
+        let raw = b"HTTP/1.1 404 Not Found\r\nContent-Length: 26\r\n\r\n{\"message\":\"no such one\"}\nextra";
+        let response = read_response(Cursor::new(raw.to_vec())).unwrap();
+        assert!(!response.is_success());
+        match response.into_error() {
+            DockerError::ApiError(status, message) => {
+                assert_eq!(status, 404);
+                assert_eq!(message, "no such one");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let mut raw = vec![1, 0, 0, 0, 0, 0, 0, 3];
+        raw.extend_from_slice(b"out");
+        raw.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 3]);
+        raw.extend_from_slice(b"err");
+        let mut cursor = Cursor::new(raw);
+
+        assert_eq!(read_frame(&mut cursor).unwrap(), Some((1, b"out".to_vec())));
+        assert_eq!(read_frame(&mut cursor).unwrap(), Some((2, b"err".to_vec())));
+        assert_eq!(read_frame(&mut cursor).unwrap(), None);
+    }
+}
@@ -1,15 +1,36 @@
+use crate::backend;
+use crate::commands;
 use crate::commands::{
-    clean, create, doctor, env, exec, init, logs, restart, run, start, status, stop, version,
+    cache, clean, create, destroy, doctor, env, env_list, exec, init, logs, ls, port, restart, run,
+    start, status, stop, use_env, verify, version,
 };
-use crate::config::Config;
+use crate::compose;
+use crate::config::{Config, CONFIG_KEYS};
+use crate::container_engine;
 use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "mdde")]
 #[command(about = "一个基于 Rust 编写的跨平台命令行工具，用于管理 Docker 多语言开发环境")]
 #[command(version)]
 pub struct Cli {
+    /// 覆盖配置项，优先级高于配置文件和 MDDE_* 环境变量 (格式: key=value，可重复指定)
+    #[arg(short = 'c', long = "config", global = true, value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+
+    /// 项目根目录，默认从当前目录向上查找包含 .mdde/ 的目录
+    #[arg(long, global = true, value_name = "DIR")]
+    project_dir: Option<PathBuf>,
+
+    /// 使用的环境 (.mdde/envs/<NAME>/)，默认为 mdde use 设置的默认环境
+    #[arg(long = "env", global = true, value_name = "NAME")]
+    environment: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -32,13 +53,29 @@ pub enum Commands {
         #[arg(short, long)]
         name: Option<String>,
 
-        /// 应用端口 (格式: host_port:container_port)
+        /// 应用端口 (格式: [ip:][host_port:]container_port[/protocol]，如 8080:80)
         #[arg(long)]
         app_port: Option<String>,
 
+        /// 其他端口映射，可重复指定 (如: -p 127.0.0.1:5000:5000 -p 5353:53/udp)
+        #[arg(short = 'p', long = "port")]
+        ports: Vec<String>,
+
         /// 工作目录路径
         #[arg(short, long)]
         workspace: Option<String>,
+
+        /// 应用端口被占用时自动改用下一个空闲端口
+        #[arg(long)]
+        auto_port: bool,
+
+        /// 不访问模板服务器，只使用本地缓存的模板
+        #[arg(long)]
+        offline: bool,
+
+        /// 模板变量的值，可重复指定 (如: --set node_version=20 --set with_db=true)
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        variables: Vec<String>,
     },
 
     /// 启动指定的开发环境
@@ -46,6 +83,18 @@ pub enum Commands {
         /// 后台运行
         #[arg(short, long)]
         detach: bool,
+
+        /// 后台运行并等待所有服务就绪 (healthcheck 通过或 app_port 可连接)
+        #[arg(long)]
+        wait: bool,
+
+        /// 等待就绪的超时时间 (秒)
+        #[arg(long, default_value_t = 120, requires = "wait")]
+        timeout: u64,
+
+        /// 应用端口被占用时自动改用下一个空闲端口
+        #[arg(long)]
+        auto_port: bool,
     },
 
     /// 停止指定的开发环境
@@ -53,6 +102,25 @@ pub enum Commands {
         /// 停止后删除容器
         #[arg(long)]
         remove: bool,
+
+        /// 停止本机登记的所有环境
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// 彻底删除当前环境：容器、命名卷、网络、构建的镜像和环境目录
+    Destroy {
+        /// 保留环境目录（cfg.env、compose 文件等）
+        #[arg(long, conflicts_with = "archive")]
+        keep_config: bool,
+
+        /// 删除前将环境目录打包到项目根目录下的 tar 文件
+        #[arg(long)]
+        archive: bool,
+
+        /// 跳过确认直接删除
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// 重启指定的开发环境
@@ -77,6 +145,14 @@ pub enum Commands {
         /// 输出格式
         #[arg(long, value_enum, default_value = "table")]
         format: OutputFormat,
+
+        /// 持续刷新状态，并显示资源使用情况 (按 Ctrl+C 退出)
+        #[arg(short, long)]
+        watch: bool,
+
+        /// 刷新间隔 (秒)
+        #[arg(long, default_value_t = 2, requires = "watch")]
+        interval: u64,
     },
 
     /// 查看指定环境的日志
@@ -97,7 +173,7 @@ pub enum Commands {
         follow: bool,
     },
 
-    /// 清理未使用的 Docker 资源
+    /// 清理 mdde 创建的未使用的 Docker 资源
     Clean {
         /// 清理所有未使用的资源
         #[arg(long)]
@@ -114,6 +190,18 @@ pub enum Commands {
         /// 清理未使用的卷
         #[arg(long)]
         volumes: bool,
+
+        /// 清理本机所有未使用的资源，默认只清理带有 mdde 标签的资源
+        #[arg(long)]
+        global: bool,
+
+        /// 只列出将被删除的资源及其大小，不执行清理
+        #[arg(long)]
+        dry_run: bool,
+
+        /// 跳过确认直接清理
+        #[arg(short, long)]
+        yes: bool,
     },
 
     /// 检查系统环境和配置
@@ -136,6 +224,132 @@ pub enum Commands {
         #[arg(long, short)]
         del: Option<String>,
     },
+
+    /// 列出登记的开发环境及其状态，默认只显示当前项目
+    Ls {
+        /// 显示本机所有项目的环境
+        #[arg(short, long)]
+        all: bool,
+
+        /// 移除目录已不存在的环境
+        #[arg(long)]
+        prune: bool,
+
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 列出项目中的环境
+    EnvList {
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 切换项目的默认环境
+    Use {
+        /// 环境名称
+        name: String,
+    },
+
+    /// 查看生效的配置
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// 管理端口映射
+    Port {
+        #[command(subcommand)]
+        action: PortAction,
+    },
+
+    /// 管理下载的模板缓存
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// 按创建时记录的摘要检查环境中的模板文件是否被修改
+    Verify {
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// 显示合并后的配置
+    Show {
+        /// 显示每个配置项的来源
+        #[arg(long)]
+        origin: bool,
+
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 检查 docker-compose.yml 引用的变量是否都在 cfg.env 中正确定义
+    Check {
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 输出合并覆盖文件并展开变量后的完整 compose 模型，不需要容器引擎
+    Render {
+        /// 输出格式 (table 与 yaml 相同)
+        #[arg(long, value_enum, default_value = "yaml")]
+        format: OutputFormat,
+
+        /// 显示密码、令牌等敏感值，默认遮盖
+        #[arg(long)]
+        show_secrets: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PortAction {
+    /// 添加端口映射 (格式: [ip:][host_port:]container_port[/protocol])
+    Add {
+        /// 端口映射，如 8080:80、127.0.0.1:5000:5000、7000-7002:7000-7002、5353:53/udp
+        #[arg(required = true)]
+        mappings: Vec<String>,
+
+        /// 端口被占用时自动改用下一个空闲端口
+        #[arg(long)]
+        auto_port: bool,
+    },
+
+    /// 删除端口映射
+    Rm {
+        /// 完整的端口映射或宿主机端口
+        #[arg(required = true)]
+        mappings: Vec<String>,
+    },
+
+    /// 列出端口映射
+    Ls {
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// 列出缓存的模板文件
+    Ls {
+        /// 输出格式
+        #[arg(long, value_enum, default_value = "table")]
+        format: OutputFormat,
+    },
+
+    /// 清空模板缓存
+    Clear,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -146,36 +360,135 @@ pub enum OutputFormat {
 }
 
 impl Cli {
+    /// 应用 `--project-dir` 和 `--env`，需要在加载配置之前调用
+    pub fn apply_project_options(&self) -> Result<(), MddeError> {
+        project::set_project_dir(self.project_dir.clone())?;
+        project::set_environment(self.environment.clone())
+    }
+
+    /// 解析命令行中的 `--config key=value` 覆盖项
+    pub fn config_overrides(&self) -> Result<Vec<(String, String)>, MddeError> {
+        self.overrides
+            .iter()
+            .map(|item| {
+                let (key, value) = item.split_once('=').ok_or_else(|| {
+                    MddeError::InvalidArgument(i18n::tf("config_override_format", &[item]))
+                })?;
+                let key = key.trim();
+                if !CONFIG_KEYS.contains(&key) {
+                    return Err(MddeError::InvalidArgument(i18n::tf(
+                        "unknown_config_key",
+                        &[&key],
+                    )));
+                }
+                Ok((key.to_string(), value.trim().to_string()))
+            })
+            .collect()
+    }
+
+    /// 命令是否用于查看或编辑 `cfg.env`，这些命令宽松加载配置
+    pub fn inspects_env_file(&self) -> bool {
+        matches!(
+            self.command,
+            Commands::Env { .. }
+                | Commands::Config {
+                    action: ConfigAction::Show { .. }
+                }
+        )
+    }
+
     pub async fn execute(self, config: Config) -> Result<(), MddeError> {
+        let overrides = self.config_overrides()?;
+        container_engine::set_preferred_engine(config.engine);
+        compose::set_preferred_flavor(config.compose);
+        let shared = backend::detect();
+        let backend = shared.as_ref();
+
         match self.command {
             Commands::Init { host } => init::execute(host, config).await,
             Commands::Create {
                 dev_env,
                 name,
                 app_port,
+                ports,
                 workspace,
-            } => create::execute(dev_env, name, app_port, workspace, config).await,
-            Commands::Start { detach } => start::execute(detach, config).await,
-            Commands::Stop { remove } => stop::execute(remove, config).await,
-            Commands::Restart => restart::execute(config).await,
-            Commands::Run { command } => run::execute(command, config).await,
-            Commands::Exec { shell } => exec::execute(shell, config).await,
-            Commands::Status { format } => status::execute(format, config).await,
+                auto_port,
+                offline,
+                variables,
+            } => {
+                let options = create::CreateOptions {
+                    dev_env,
+                    name,
+                    ports: app_port.into_iter().chain(ports).collect(),
+                    workspace,
+                    auto_port,
+                    offline,
+                    variables,
+                };
+                create::execute(options, config, backend).await
+            }
+            Commands::Start {
+                detach,
+                wait,
+                timeout,
+                auto_port,
+            } => {
+                let wait = wait.then(|| Duration::from_secs(timeout));
+                start::execute(detach, wait, auto_port, config, backend).await
+            }
+            Commands::Stop { remove, all: true } => stop::execute_all(remove, backend).await,
+            Commands::Stop { remove, all: false } => stop::execute(remove, config, backend).await,
+            Commands::Destroy {
+                keep_config,
+                archive,
+                yes,
+            } => destroy::execute(keep_config, archive, yes, config, backend).await,
+            Commands::Restart => restart::execute(config, backend).await,
+            Commands::Run { command } => run::execute(command, config, backend).await,
+            Commands::Exec { shell } => exec::execute(shell, config, backend).await,
+            Commands::Status {
+                format,
+                watch,
+                interval,
+            } => status::execute(format, watch, interval, config, shared.clone()).await,
             Commands::Logs {
                 lines,
                 tail,
                 all,
                 follow,
-            } => logs::execute(lines, tail, all, follow, config).await,
+            } => logs::execute(lines, tail, all, follow, config, backend).await,
             Commands::Clean {
                 all,
                 images,
                 containers,
                 volumes,
-            } => clean::execute(all, images, containers, volumes, config).await,
+                global,
+                dry_run,
+                yes,
+            } => {
+                let options = clean::CleanOptions {
+                    all,
+                    images,
+                    containers,
+                    volumes,
+                    global,
+                    dry_run,
+                    yes,
+                };
+                clean::execute(options, config, backend).await
+            }
             Commands::Doctor => doctor::execute(config).await,
             Commands::Version => version::execute().await,
             Commands::Env { set, ls, del } => env::execute(set, ls, del, config).await,
+            Commands::Ls { all, prune, format } => ls::execute(all, prune, format, backend).await,
+            Commands::EnvList { format } => env_list::execute(format).await,
+            Commands::Use { name } => use_env::execute(name).await,
+            Commands::Config { action } => {
+                commands::config::execute(action, &overrides, config).await
+            }
+            Commands::Port { action } => port::execute(action, config, backend).await,
+            Commands::Cache { action } => cache::execute(action).await,
+            Commands::Verify { format } => verify::execute(format).await,
         }
     }
 }
@@ -0,0 +1,80 @@
+use crate::cli::{CacheAction, OutputFormat};
+use crate::error::MddeError;
+use crate::i18n;
+use crate::table;
+use crate::template_cache::{CacheEntry, TemplateCache};
+use crate::utils;
+use colored::*;
+use serde_json::json;
+use std::path::Path;
+use tracing::info;
+
+pub async fn execute(action: CacheAction) -> Result<(), MddeError> {
+    let cache = TemplateCache::open()
+        .ok_or_else(|| MddeError::Config(i18n::t("cache_dir_unknown").to_string()))?;
+
+    match action {
+        CacheAction::Ls { format } => {
+            info!("列出模板缓存: {}", cache.dir().display());
+            println!("{}", render(&cache.entries()?, cache.dir(), format)?);
+        }
+        CacheAction::Clear => {
+            info!("清空模板缓存: {}", cache.dir().display());
+            let (count, size) = cache.clear()?;
+            println!(
+                "{}",
+                i18n::tf("cache_cleared", &[&count, &utils::format_file_size(size)]).green()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn render(entries: &[CacheEntry], dir: &Path, format: OutputFormat) -> Result<String, MddeError> {
+    let data = json!({
+        "dir": dir,
+        "entries": entries,
+        "total": entries.len()
+    });
+
+    match format {
+        OutputFormat::Table => {
+            if entries.is_empty() {
+                return Ok(i18n::tf("cache_empty", &[&dir.display()]));
+            }
+
+            let headers = ["HOST", "PATH", "SIZE", "ETAG", "FETCHED"];
+            let rows: Vec<[String; 5]> = entries
+                .iter()
+                .map(|entry| {
+                    [
+                        entry.base_url.clone(),
+                        entry.path.clone(),
+                        if entry.missing {
+                            "404".to_string()
+                        } else {
+                            utils::format_file_size(entry.size)
+                        },
+                        entry.etag.clone().unwrap_or_else(|| "-".to_string()),
+                        entry
+                            .fetched_at
+                            .with_timezone(&chrono::Local)
+                            .format("%Y-%m-%d %H:%M:%S")
+                            .to_string(),
+                    ]
+                })
+                .collect();
+
+            Ok(table::render(&headers, &rows).join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": "success",
+                "data": data
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
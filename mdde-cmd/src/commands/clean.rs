@@ -1,93 +1,242 @@
+use crate::backend::{ContainerBackend, PruneCandidate, PruneScope, PruneTarget};
 use crate::config::Config;
 use crate::error::MddeError;
-use crate::i18n;
+use crate::i18n::{self, MessageKey};
+use crate::table;
+use crate::utils;
 use colored::*;
-use std::process::Command;
+use std::io::{self, IsTerminal};
 use tracing::info;
 
+/// 一类待清理的资源：清理目标、开始和完成时的提示、出错时的前缀
+type Step = (PruneTarget, MessageKey, MessageKey, &'static str);
+
+/// `mdde clean` 的参数
+#[derive(Debug, Clone, Default)]
+pub struct CleanOptions {
+    /// 清理所有类型的未使用资源
+    pub all: bool,
+    /// 清理未使用的镜像
+    pub images: bool,
+    /// 清理已停止的容器
+    pub containers: bool,
+    /// 清理未使用的卷
+    pub volumes: bool,
+    /// 清理本机所有未使用的资源，而不只是带有 mdde 标签的资源
+    pub global: bool,
+    /// 只列出将被删除的资源
+    pub dry_run: bool,
+    /// 跳过确认直接清理
+    pub yes: bool,
+}
+
+/// 默认只清理带有 mdde 标签的资源，`global` 为 true 时清理本机所有未使用的资源
+///
+/// 清理前列出将被删除的资源并请求确认，`dry_run` 时只列出，`yes` 时跳过确认直接清理。
 pub async fn execute(
-    all: bool,
-    images: bool,
-    containers: bool,
-    volumes: bool,
+    options: CleanOptions,
     _config: Config,
+    backend: &dyn ContainerBackend,
 ) -> Result<(), MddeError> {
+    let CleanOptions {
+        all,
+        images,
+        containers,
+        volumes,
+        global,
+        dry_run,
+        yes,
+    } = options;
     info!("清理 Docker 资源");
 
-    if all {
-        println!("{}", i18n::t("clean_all_resources").yellow());
-
+    let steps: Vec<Step> = if all {
         // 清理所有未使用的资源
-        let mut cmd = Command::new("docker");
-        cmd.arg("system").arg("prune").arg("-a").arg("-f");
-
-        let output = cmd.output()?;
-        if output.status.success() {
-            let result = String::from_utf8_lossy(&output.stdout);
-            println!("{}", i18n::t("clean_completed").green());
-            println!("{}", result);
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(MddeError::Docker(format!("清理失败: {}", stderr)));
-        }
+        vec![(
+            PruneTarget::System,
+            "clean_all_resources",
+            "clean_completed",
+            "清理失败",
+        )]
     } else {
         // 分别清理不同类型的资源
-        if images {
-            println!("{}", i18n::t("clean_images").yellow());
-            let mut cmd = Command::new("docker");
-            cmd.arg("image").arg("prune").arg("-f");
-
-            let output = cmd.output()?;
-            if output.status.success() {
-                let result = String::from_utf8_lossy(&output.stdout);
-                println!("{}", i18n::t("images_clean_completed").green());
-                println!("{}", result);
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(MddeError::Docker(format!("镜像清理失败: {}", stderr)));
-            }
-        }
+        [
+            (
+                images,
+                (
+                    PruneTarget::Images,
+                    "clean_images",
+                    "images_clean_completed",
+                    "镜像清理失败",
+                ),
+            ),
+            (
+                containers,
+                (
+                    PruneTarget::Containers,
+                    "clean_containers",
+                    "containers_clean_completed",
+                    "容器清理失败",
+                ),
+            ),
+            (
+                volumes,
+                (
+                    PruneTarget::Volumes,
+                    "clean_volumes",
+                    "volumes_clean_completed",
+                    "卷清理失败",
+                ),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(selected, step)| selected.then_some(step))
+        .collect()
+    };
 
-        if containers {
-            println!("{}", i18n::t("clean_containers").yellow());
-            let mut cmd = Command::new("docker");
-            cmd.arg("container").arg("prune").arg("-f");
-
-            let output = cmd.output()?;
-            if output.status.success() {
-                let result = String::from_utf8_lossy(&output.stdout);
-                println!("{}", i18n::t("containers_clean_completed").green());
-                println!("{}", result);
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(MddeError::Docker(format!("容器清理失败: {}", stderr)));
-            }
-        }
+    if steps.is_empty() {
+        println!("{}", i18n::t("specify_resource_type").yellow());
+        println!("{}", i18n::t("use_all_flag"));
+        println!("{}", i18n::t("use_images_flag"));
+        println!("{}", i18n::t("use_containers_flag"));
+        println!("{}", i18n::t("use_volumes_flag"));
+        return Ok(());
+    }
 
-        if volumes {
-            println!("{}", i18n::t("clean_volumes").yellow());
-            let mut cmd = Command::new("docker");
-            cmd.arg("volume").arg("prune").arg("-f");
-
-            let output = cmd.output()?;
-            if output.status.success() {
-                let result = String::from_utf8_lossy(&output.stdout);
-                println!("{}", i18n::t("volumes_clean_completed").green());
-                println!("{}", result);
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(MddeError::Docker(format!("卷清理失败: {}", stderr)));
-            }
+    let scope = if global {
+        PruneScope::Global
+    } else {
+        PruneScope::Managed
+    };
+    match scope {
+        PruneScope::Managed => println!("{}", i18n::t("clean_scope_managed")),
+        PruneScope::Global => println!("{}", i18n::t("clean_scope_global").yellow()),
+    }
+
+    if dry_run || !yes {
+        let usage = backend
+            .disk_usage()
+            .map_err(|e| MddeError::Docker(format!("获取资源占用失败: {}", e)))?;
+        let candidates: Vec<PruneCandidate> = steps
+            .iter()
+            .flat_map(|(target, ..)| usage.prune_candidates(*target, scope))
+            .collect();
+
+        if candidates.is_empty() {
+            println!("{}", i18n::t("clean_nothing").green());
+            return Ok(());
         }
+        println!("{}", render(&candidates));
 
-        if !images && !containers && !volumes {
-            println!("{}", i18n::t("specify_resource_type").yellow());
-            println!("{}", i18n::t("use_all_flag"));
-            println!("{}", i18n::t("use_images_flag"));
-            println!("{}", i18n::t("use_containers_flag"));
-            println!("{}", i18n::t("use_volumes_flag"));
+        if dry_run {
+            println!("{}", i18n::t("clean_dry_run_hint"));
+            return Ok(());
         }
+        if !io::stdin().is_terminal() {
+            return Err(MddeError::InvalidArgument(
+                i18n::t("clean_confirm_required").to_string(),
+            ));
+        }
+        if !utils::confirm(i18n::t("clean_confirm_prompt"))? {
+            println!("{}", i18n::t("clean_cancelled"));
+            return Ok(());
+        }
+    }
+
+    for (target, start_message, done_message, error_prefix) in steps {
+        prune(
+            backend,
+            scope,
+            target,
+            start_message,
+            done_message,
+            error_prefix,
+        )?;
     }
 
     Ok(())
 }
+
+/// 待清理资源的表格，大小未知时显示 `-`，最后一行为已知大小的合计
+fn render(candidates: &[PruneCandidate]) -> String {
+    let headers = ["TYPE", "NAME", "SIZE"];
+    let rows: Vec<[String; 3]> = candidates
+        .iter()
+        .map(|candidate| {
+            [
+                candidate.kind.as_str().to_string(),
+                candidate.name.clone(),
+                candidate
+                    .size
+                    .map_or_else(|| "-".to_string(), utils::format_file_size),
+            ]
+        })
+        .collect();
+
+    let total: u64 = candidates.iter().filter_map(|c| c.size).sum();
+    let mut lines = table::render(&headers, &rows);
+    lines.push(i18n::tf(
+        "clean_reclaimable_total",
+        &[&candidates.len(), &utils::format_file_size(total)],
+    ));
+    lines.join("\n")
+}
+
+/// 清理一类资源并输出结果
+fn prune(
+    backend: &dyn ContainerBackend,
+    scope: PruneScope,
+    target: PruneTarget,
+    start_message: MessageKey,
+    done_message: MessageKey,
+    error_prefix: &str,
+) -> Result<(), MddeError> {
+    println!("{}", i18n::t(start_message).yellow());
+
+    let report = backend
+        .prune(target, scope)
+        .map_err(|e| MddeError::Docker(format!("{}: {}", error_prefix, e)))?;
+
+    println!("{}", i18n::t(done_message).green());
+    for item in &report.deleted {
+        println!("  {}", item);
+    }
+    println!(
+        "{}",
+        i18n::tf(
+            "reclaimed_space",
+            &[&utils::format_file_size(report.space_reclaimed)]
+        )
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ResourceKind;
+
+    #[test]
+    fn test_render_candidates() {
+        let candidates = vec![
+            PruneCandidate {
+                kind: ResourceKind::Container,
+                id: "c1".to_string(),
+                name: "demo-app-1".to_string(),
+                size: Some(2048),
+            },
+            PruneCandidate {
+                kind: ResourceKind::Volume,
+                id: "demo_data".to_string(),
+                name: "demo_data".to_string(),
+                size: None,
+            },
+        ];
+
+        let output = render(&candidates);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "container  demo-app-1  2.0 KB");
+        assert_eq!(lines[2], "volume     demo_data   -");
+        assert!(lines[3].contains("2.0 KB"), "{}", lines[3]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::cli::{ConfigAction, OutputFormat};
+use crate::compose::ComposeProject;
+use crate::compose_model;
+use crate::config::{Config, ConfigValue};
+use crate::config_check::{self, Report, Severity};
+use crate::error::MddeError;
+use crate::i18n;
+use crate::table;
+use colored::*;
+use serde_json::{json, Map, Value};
+use tracing::info;
+
+pub async fn execute(
+    action: ConfigAction,
+    overrides: &[(String, String)],
+    config: Config,
+) -> Result<(), MddeError> {
+    match action {
+        ConfigAction::Show { origin, format } => {
+            info!("显示生效的配置");
+            let (_, values) = Config::load_layers_with(overrides, true).await?;
+            println!("{}", render(&values, origin, format)?);
+            Ok(())
+        }
+        ConfigAction::Check { format } => {
+            info!("检查 compose 变量");
+            let project = ComposeProject::current()?;
+            let report = config_check::check_project(&project)?;
+            println!("{}", render_check(&report, format)?);
+            ensure_no_errors(&report)
+        }
+        ConfigAction::Render {
+            format,
+            show_secrets,
+        } => {
+            info!("输出解析后的 compose 模型");
+            let project = ComposeProject::current()?;
+            let mut model = compose_model::resolve(&project, &config)?;
+            if !show_secrets {
+                compose_model::mask_secrets(&mut model);
+            }
+            println!("{}", render_model(&model, format)?);
+            Ok(())
+        }
+    }
+}
+
+/// 按指定格式渲染 compose 模型，table 格式与 yaml 相同
+pub fn render_model(model: &serde_yaml::Value, format: OutputFormat) -> Result<String, MddeError> {
+    match format {
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": "success",
+                "data": model
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Table | OutputFormat::Yaml => {
+            serde_yaml::to_string(model).map_err(MddeError::Yaml)
+        }
+    }
+}
+
+/// 检查结果中有错误时返回 `MddeError::Config`
+pub fn ensure_no_errors(report: &Report) -> Result<(), MddeError> {
+    if report.has_errors() {
+        return Err(MddeError::Config(i18n::tf(
+            "check_failed",
+            &[&report.errors()],
+        )));
+    }
+    Ok(())
+}
+
+/// 每个问题一行，建议在下一行
+pub fn issue_lines(report: &Report) -> Vec<String> {
+    let mut lines = Vec::new();
+    for issue in &report.issues {
+        lines.push(match issue.severity {
+            Severity::Error => format!("  ✗ {}", issue.message).red().to_string(),
+            Severity::Warning => format!("  ⚠ {}", issue.message).yellow().to_string(),
+        });
+        if let Some(suggestion) = &issue.suggestion {
+            lines.push(format!("    → {}", suggestion).dimmed().to_string());
+        }
+    }
+    lines
+}
+
+/// 按指定格式渲染检查结果：引用的变量及其取值来源，以及发现的问题
+pub fn render_check(report: &Report, format: OutputFormat) -> Result<String, MddeError> {
+    match format {
+        OutputFormat::Table => {
+            let mut lines = Vec::new();
+            if report.variables.is_empty() {
+                lines.push(i18n::t("check_no_variables").to_string());
+            } else {
+                lines.push(i18n::t("check_variables_header").yellow().to_string());
+                let rows: Vec<[String; 4]> = report
+                    .variables
+                    .iter()
+                    .map(|variable| {
+                        [
+                            variable.name.clone(),
+                            variable
+                                .lines
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join(","),
+                            variable.source.as_str().to_string(),
+                            variable.value.clone().unwrap_or_else(|| "-".to_string()),
+                        ]
+                    })
+                    .collect();
+                let headers = ["NAME", "LINES", "SOURCE", "VALUE"];
+                lines.extend(
+                    table::render(&headers, &rows)
+                        .into_iter()
+                        .map(|line| format!("  {}", line)),
+                );
+            }
+
+            if report.issues.is_empty() {
+                lines.push(i18n::t("check_passed").green().to_string());
+            } else {
+                lines.push(String::new());
+                lines.push(i18n::t("check_issues_header").yellow().to_string());
+                lines.extend(issue_lines(report));
+            }
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": if report.has_errors() { "error" } else { "success" },
+                "data": report
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(report).map_err(MddeError::Yaml),
+    }
+}
+
+/// 按指定格式渲染生效的配置，`origin` 为 true 时附带每个配置项的来源
+pub fn render(
+    values: &[ConfigValue],
+    origin: bool,
+    format: OutputFormat,
+) -> Result<String, MddeError> {
+    let data: Map<String, Value> = values
+        .iter()
+        .map(|value| {
+            let item = if origin {
+                json!({
+                    "value": value.value,
+                    "origin": {
+                        "layer": value.source.layer(),
+                        "location": value.source.location(),
+                    }
+                })
+            } else {
+                json!(value.value)
+            };
+            (value.key.clone(), item)
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Table => {
+            let key_width = values.iter().map(|v| v.key.len()).max().unwrap_or(0);
+            let value_width = values.iter().map(|v| v.value.len()).max().unwrap_or(0);
+
+            let mut lines = vec![i18n::t("effective_config").yellow().to_string()];
+            for value in values {
+                let line = format!(
+                    "{:<key_width$} = {:<value_width$}",
+                    value.key,
+                    value.value,
+                    key_width = key_width,
+                    value_width = value_width
+                );
+                if origin {
+                    lines.push(format!(
+                        "{}  {}",
+                        line,
+                        format!("# {}", value.source).dimmed()
+                    ));
+                } else {
+                    lines.push(line.trim_end().to_string());
+                }
+            }
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": "success",
+                "data": data
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigSource;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_render_with_origin() {
+        let values = vec![
+            ConfigValue::new(
+                "host",
+                "http://intranet",
+                ConfigSource::User(PathBuf::from("/home/dev/.config/mdde/config.toml")),
+            ),
+            ConfigValue::new(
+                "engine",
+                "podman",
+                ConfigSource::Environment("MDDE_ENGINE".to_string()),
+            ),
+        ];
+
+        let output = render(&values, true, OutputFormat::Json).unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["data"]["host"]["value"], "http://intranet");
+        assert_eq!(value["data"]["host"]["origin"]["layer"], "user");
+        assert_eq!(value["data"]["engine"]["origin"]["location"], "MDDE_ENGINE");
+
+        let output = render(&values, false, OutputFormat::Json).unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["data"]["engine"], "podman");
+
+        let table = render(&values, true, OutputFormat::Table).unwrap();
+        assert!(table.contains("MDDE_ENGINE"));
+    }
+}
@@ -1,12 +1,23 @@
+use crate::backend::ContainerBackend;
+use crate::compose::ComposeProject;
 use crate::config::Config;
 use crate::error::MddeError;
-use crate::http::MddeClient;
 use crate::i18n;
+use crate::port_mapping::PortMapping;
+use crate::ports::PortCheck;
+use crate::project;
+use crate::registry::{Registry, RegistryEntry};
+use crate::template_cache::TemplateCache;
+use crate::template_integrity::{self, LockedFile, TemplateLock};
+use crate::template_manifest::TemplateFile;
+use crate::template_source::{TemplateRef, TemplateSources, DEFAULT_SOURCE};
+use crate::template_vars::{self, TemplateVariable};
+use chrono::Utc;
 use colored::*;
 use serde::Deserialize;
-use std::collections::HashMap;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use tracing::info;
 
 /// 开发环境信息
@@ -16,55 +27,80 @@ pub struct DevEnvironment {
     pub name: String,
     /// 环境描述，如 "Node.js 22 开发环境"
     pub description: String,
+    /// 模板的文件清单，为空时使用模板目录中的 `manifest.json` 或默认清单
+    #[serde(default)]
+    pub files: Vec<TemplateFile>,
+    /// 模板声明的变量
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
 }
 
-/// 验证应用端口格式是否为 number:number
-fn validate_app_port(port_mapping: &str) -> Result<(u16, u16), MddeError> {
-    let parts: Vec<&str> = port_mapping.split(':').collect();
-
-    if parts.len() != 2 {
-        return Err(MddeError::InvalidPortFormat(
-            i18n::tf("port_format_error", &[&port_mapping])
-        ));
-    }
-
-    let host_port = parts[0].parse::<u16>().map_err(|_| {
-        MddeError::InvalidPortFormat(
-            i18n::tf("invalid_host_port", &[&parts[0]])
-        )
-    })?;
-
-    let container_port = parts[1].parse::<u16>().map_err(|_| {
-        MddeError::InvalidPortFormat(
-            i18n::tf("invalid_container_port", &[&parts[1]])
-        )
-    })?;
-
-    if host_port == 0 || container_port == 0 {
-        return Err(MddeError::InvalidPortFormat(i18n::t("port_cannot_be_zero").to_string()));
+impl DevEnvironment {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            files: Vec::new(),
+            variables: Vec::new(),
+        }
     }
+}
 
-    Ok((host_port, container_port))
+/// `mdde create` 的参数
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// 模板名称，可以写作 `source:name` 指定模板来源，为空时交互式选择
+    pub dev_env: Option<String>,
+    /// 容器名称，为空时交互式询问
+    pub name: Option<String>,
+    /// 端口映射，第一个作为应用端口
+    pub ports: Vec<String>,
+    /// 工作目录，默认为项目根目录
+    pub workspace: Option<String>,
+    /// 端口被占用时自动选择空闲端口
+    pub auto_port: bool,
+    /// 只使用本地缓存的模板
+    pub offline: bool,
+    /// `name=value` 形式的模板变量
+    pub variables: Vec<String>,
 }
 
+/// `dev_env` 未指定来源时按 [`Config::template_sources`] 的顺序查找模板。
+///
+/// 下载的文件与清单中的摘要不一致时不写入任何文件，写入的文件及其摘要记录在 [`template_integrity::LOCK_FILE`] 中。
+/// 模板声明了变量时，`variables` 中未指定的变量在终端中询问，否则使用默认值。
 pub async fn execute(
-    dev_env: Option<String>,
-    name: Option<String>,
-    app_port: Option<String>,
-    workspace: Option<String>,
+    options: CreateOptions,
     mut config: Config,
+    backend: &dyn ContainerBackend,
 ) -> Result<(), MddeError> {
+    let CreateOptions {
+        dev_env,
+        name,
+        ports,
+        workspace,
+        auto_port,
+        offline,
+        variables,
+    } = options;
+    let provided = parse_variables(&variables)?;
+
+    // 所有模板来源，HTTP 来源下载的模板保存在本地缓存中
+    let sources = TemplateSources::new(config.template_sources()?, TemplateCache::open(), offline)
+        .with_trusted_keys(config.trusted_keys.clone());
+
     // 获取开发环境类型，如果没有提供则交互式询问
     let dev_env = match dev_env {
         Some(env) => {
             if env.trim().is_empty() {
-                get_dev_env_interactively(&config).await?
+                get_dev_env_interactively(&sources).await?
             } else {
                 env
             }
         }
-        None => get_dev_env_interactively(&config).await?,
+        None => get_dev_env_interactively(&sources).await?,
     };
+    let template = TemplateRef::parse(&dev_env);
 
     // 获取环境名称，如果没有提供则交互式询问
     let name = match name {
@@ -80,71 +116,117 @@ pub async fn execute(
 
     info!("创建开发环境: {} ({})", name.clone(), dev_env);
 
-    // 验证应用端口格式
-    let validated_app_port = if let Some(ref port_str) = app_port {
-        let (host_port, container_port) = validate_app_port(port_str)?;
-        Some((host_port, container_port, port_str.clone()))
-    } else {
-        None
-    };
+    // 验证端口映射格式，第一个为应用端口
+    let mut mappings = Vec::new();
+    for port_str in &ports {
+        mappings.extend(PortMapping::parse_list(port_str)?);
+    }
 
-    // 确定工作目录
-    let workspace_path = workspace
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")));
+    // 确定项目根目录和工作目录，未指定工作目录时使用项目根目录
+    let root = project::root()?;
+    let workspace_path = workspace.map(PathBuf::from).unwrap_or_else(|| root.clone());
 
-    // 创建 HTTP 客户端
-    let client = MddeClient::new(&config.host);
+    // 检查端口是否被占用，单个端口冲突时换用空闲端口
+    if !mappings.is_empty() {
+        PortCheck::collect(backend, &ComposeProject::active()?)
+            .resolve_mappings(&mut mappings, auto_port)?;
+    }
 
-    // 确保 .mdde 目录存在
-    let mdde_dir = workspace_path.join(".mdde");
+    // 确保环境目录存在，default 环境为 .mdde/，其他环境为 .mdde/envs/<name>/
+    let mdde_dir = project::env_dir()?;
     tokio::fs::create_dir_all(&mdde_dir).await?;
 
-    // 下载 docker-compose.yml 文件
-    let compose_content = client
-        .download_script(&dev_env, "docker-compose.yml")
-        .await?;
-
-    // 保存 docker-compose.yml 文件
-    let compose_path = mdde_dir.join("docker-compose.yml");
-    tokio::fs::write(&compose_path, compose_content).await?;
-    println!("{}", i18n::t("downloaded_compose").green());
-
-    // 下载 Dockerfile 文件（如果存在）
-    match client.download_script(&dev_env, "Dockerfile").await {
-        Ok(dockerfile_content) => {
-            let dockerfile_path = mdde_dir.join("Dockerfile");
-            tokio::fs::write(&dockerfile_path, dockerfile_content).await?;
-            println!("{}", i18n::t("downloaded_dockerfile").green());
-        }
-        Err(MddeError::HttpStatus(404)) => {
-            // Dockerfile 不存在，这是正常情况
-            println!("{}", i18n::t("dockerfile_not_exists").yellow());
+    // 确定模板所在的来源和文件清单
+    let resolved = sources.resolve(&template).await?;
+    let source = resolved.source;
+    println!(
+        "{}",
+        i18n::tf("template_source_label", &[&source.name, &source.location])
+    );
+    if resolved.signed {
+        println!("{}", i18n::t("template_signature_verified").green());
+    }
+    // 记录的模板名称，非默认来源时带上来源名称
+    let dev_env = if source.name == DEFAULT_SOURCE {
+        template.name.clone()
+    } else {
+        format!("{}:{}", source.name, template.name)
+    };
+
+    // 确定模板变量的值，下载文件前完成输入
+    let values = template_vars::resolve_values(
+        &resolved.manifest.variables,
+        provided,
+        prompt_variable,
+    )?;
+
+    // 先下载清单中的所有文件并检查摘要，必需的文件下载失败或任何文件被篡改时不写入任何文件
+    let mut downloaded = Vec::new();
+    for file in &resolved.manifest.files {
+        match sources.download(source, &template.name, &file.path).await {
+            Ok(content) => {
+                file.check_digest(&content)?;
+                downloaded.push((file, content));
+            }
+            Err(e) if !file.optional => return Err(e),
+            Err(e) if e.is_not_found() => {
+                // 可选文件不存在，这是正常情况
+                println!("{}", i18n::tf("optional_file_not_exists", &[&file.path]).yellow());
+            }
+            Err(e) => {
+                // 其他错误，记录但不中断流程
+                println!(
+                    "{}",
+                    i18n::tf("optional_file_download_failed", &[&file.path, &e]).yellow()
+                );
+            }
         }
-        Err(e) => {
-            // 其他错误，记录但不中断流程
-            println!("{}", i18n::tf("dockerfile_download_failed", &[&e]).yellow());
+    }
+
+    // 摘要针对模板中的原始内容，检查通过后再渲染变量
+    if !resolved.manifest.variables.is_empty() {
+        for (file, content) in &mut downloaded {
+            *content = template_vars::render(content, &values).map_err(|e| {
+                MddeError::Config(i18n::tf("template_render_failed", &[&file.path, &e]))
+            })?;
         }
     }
 
+    let mut locked = Vec::new();
+    for (file, content) in downloaded {
+        write_template_file(&mdde_dir.join(file.target()), &content, file.executable).await?;
+        println!("{}", i18n::tf("downloaded_template_file", &[&file.target()]).green());
+        locked.push(LockedFile {
+            target: file.target().to_string(),
+            sha256: template_integrity::sha256_hex(content.as_bytes()),
+        });
+    }
+    TemplateLock {
+        template: dev_env.clone(),
+        source: source.name.clone(),
+        signed: resolved.signed,
+        created_at: Utc::now(),
+        files: locked,
+        variables: values,
+    }
+    .save(&mdde_dir)?;
+
     // 更新环境变量文件
-    let mut env_vars = Config::load_env_file().await?;
-    env_vars.insert("container_name".to_string(), name.clone());
-    if let Some((_, _, port_str)) = &validated_app_port {
-        env_vars.insert("app_port".to_string(), port_str.clone());
+    let mut env_vars = Config::load_env_document().await?;
+    env_vars.set("container_name", &name);
+    env_vars.set("template", &dev_env);
+    if !mappings.is_empty() {
+        Config::ports_into_env(&mut env_vars, &mappings);
     }
-    env_vars.insert(
-        "workspace".to_string(),
-        workspace_path.to_string_lossy().to_string(),
-    );
+    env_vars.set("workspace", &workspace_path.to_string_lossy());
 
     Config::save_env_file(&env_vars).await?;
 
     // 更新配置
     let mut updates = HashMap::new();
     updates.insert("container_name".to_string(), name.clone());
-    if let Some((_, _, port_str)) = &validated_app_port {
-        updates.insert("app_port".to_string(), port_str.clone());
+    if !mappings.is_empty() {
+        config.set_port_mappings(mappings.clone());
     }
     updates.insert(
         "workspace".to_string(),
@@ -152,20 +234,64 @@ pub async fn execute(
     );
     config.update(updates).await?;
 
+    // 登记到本机的环境列表，登记失败不影响创建
+    let entry = RegistryEntry {
+        project_dir: root.clone(),
+        environment: project::environment()?,
+        container_name: name.clone(),
+        template: dev_env.clone(),
+        ports: mappings.iter().map(ToString::to_string).collect(),
+        created_at: Utc::now(),
+    };
+    let registered = Registry::load().and_then(|mut registry| {
+        registry.register(entry);
+        registry.save()
+    });
+    if let Err(e) = registered {
+        println!("{}", i18n::tf("registry_update_failed", &[&e]).yellow());
+    }
+
     println!("{}", i18n::t("env_created_success").green());
     println!("{}", i18n::tf("env_name_label", &[&name]));
+    println!("{}", i18n::tf("environment_label", &[&project::environment()?]));
     println!("{}", i18n::tf("env_type_label", &[&dev_env]));
     println!("{}", i18n::tf("workspace_label", &[&workspace_path.display()]));
-    if let Some((host_port, container_port, port_str)) = &validated_app_port {
-        println!("{}", i18n::tf("app_port_label", &[&port_str, &host_port, &container_port]));
+    for mapping in &mappings {
+        println!(
+            "{}",
+            i18n::tf(
+                "app_port_label",
+                &[mapping, &mapping.host_range(), &mapping.container]
+            )
+        );
     }
-    println!("{}", i18n::t("config_file_label"));
-    println!("{}", i18n::t("env_file_label"));
+    let relative_dir = mdde_dir.strip_prefix(&root).unwrap_or(&mdde_dir);
+    println!(
+        "{}",
+        i18n::tf("config_file_label", &[&relative_dir.join("docker-compose.yml").display()])
+    );
+    println!(
+        "{}",
+        i18n::tf("env_file_label", &[&relative_dir.join("cfg.env").display()])
+    );
 
     // 检查是否下载了 Dockerfile
     let dockerfile_path = mdde_dir.join("Dockerfile");
     if dockerfile_path.exists() {
-        println!("{}", i18n::t("custom_image_label"));
+        println!(
+            "{}",
+            i18n::tf("custom_image_label", &[&relative_dir.join("Dockerfile").display()])
+        );
+    }
+
+    // 项目中还没有默认环境时，新建的命名环境成为默认环境
+    let environment = project::environment()?;
+    if environment != project::DEFAULT_ENV
+        && project::default_environment(&root)?.is_none()
+        && project::environments(&root) == [environment.as_str()]
+    {
+        project::set_default_environment(&root, &environment)?;
+        println!("{}", i18n::tf("default_env_set", &[&environment]).green());
     }
 
     println!("\n{}", i18n::t("next_steps").yellow());
@@ -176,21 +302,99 @@ pub async fn execute(
     Ok(())
 }
 
+/// 解析 `--set` 指定的变量，同一变量指定多次时使用最后一次的值
+fn parse_variables(variables: &[String]) -> Result<BTreeMap<String, String>, MddeError> {
+    variables
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => {
+                Ok((name.trim().to_string(), value.to_string()))
+            }
+            _ => Err(MddeError::InvalidArgument(i18n::tf(
+                "invalid_variable_format",
+                &[pair],
+            ))),
+        })
+        .collect()
+}
+
+/// 在终端中询问变量的值，输入无效或必需的变量没有输入时重新询问；直接回车或不在终端中运行时使用默认值
+fn prompt_variable(variable: &TemplateVariable) -> Result<Option<String>, MddeError> {
+    if !io::stdin().is_terminal() {
+        return Ok(None);
+    }
+
+    if !variable.description.is_empty() {
+        println!("{}", variable.description.cyan());
+    }
+    if !variable.choices.is_empty() {
+        println!("{}", i18n::tf("variable_choices", &[&variable.choices.join(", ")]));
+    }
+    loop {
+        print!("{}", i18n::tf("variable_prompt", &[&variable.name]));
+        if let Some(default) = &variable.default {
+            print!(" [{}]", default);
+        }
+        print!(": ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        let input = input.trim_end_matches(['\r', '\n']);
+        if input.is_empty() {
+            if variable.default.is_none() {
+                continue;
+            }
+            return Ok(None);
+        }
+        match variable.check(input) {
+            Ok(_) => return Ok(Some(input.to_string())),
+            Err(e) => println!("{}", e.to_string().yellow()),
+        }
+    }
+}
+
+/// 写入模板文件，`executable` 时在 Unix 上设置可执行权限
+async fn write_template_file(path: &Path, content: &str, executable: bool) -> Result<(), MddeError> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, content).await?;
+
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).await?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+
+    Ok(())
+}
+
 /// 交互式获取开发环境类型，从服务器动态获取环境列表
-async fn get_dev_env_interactively(config: &Config) -> Result<String, MddeError> {
+///
+/// 配置了多个来源时列出每个环境所在的来源，可以输入 `source:name` 选择特定来源的模板
+async fn get_dev_env_interactively(sources: &TemplateSources) -> Result<String, MddeError> {
     println!("{}", i18n::t("select_env_type").cyan());
 
-    // 尝试从服务器获取环境列表
-    let client = MddeClient::new(&config.host);
-    let environments = match client.get_environments().await {
+    // 尝试从所有来源获取合并后的环境列表
+    let environments: Vec<(String, DevEnvironment)> = match sources.environments().await {
         Ok(envs) => {
             println!("{}", i18n::t("env_list_from_server").green());
-            envs
+            envs.into_iter()
+                .map(|env| (env.source, env.environment))
+                .collect()
         }
         Err(e) => {
             println!("{}", i18n::tf("env_list_failed", &[&e]).yellow());
             println!("{}", i18n::t("using_default_env_list").yellow());
             get_default_environments()
+                .into_iter()
+                .map(|env| (DEFAULT_SOURCE.to_string(), env))
+                .collect()
         }
     };
 
@@ -199,8 +403,12 @@ async fn get_dev_env_interactively(config: &Config) -> Result<String, MddeError>
     }
 
     println!("{}", i18n::t("available_options"));
-    for env in &environments {
-        println!("  - {}    ({})", env.name.cyan(), env.description);
+    for (source, env) in &environments {
+        if sources.is_multiple() {
+            println!("  - {}    ({})  [{}]", env.name.cyan(), env.description, source);
+        } else {
+            println!("  - {}    ({})", env.name.cyan(), env.description);
+        }
     }
 
     print!("{}", i18n::t("enter_env_type"));
@@ -216,9 +424,12 @@ async fn get_dev_env_interactively(config: &Config) -> Result<String, MddeError>
     }
 
     // 验证输入的环境类型是否有效
-    let valid_env_names: Vec<&str> = environments.iter().map(|e| e.name.as_str()).collect();
+    let selected = TemplateRef::parse(dev_env);
+    let valid = environments.iter().any(|(source, env)| {
+        env.name == selected.name && selected.source.as_ref().is_none_or(|s| s == source)
+    });
 
-    if !valid_env_names.contains(&dev_env) {
+    if !valid {
         return Err(MddeError::InvalidInput(
             i18n::tf("invalid_env_type", &[&dev_env])
         ));
@@ -229,52 +440,22 @@ async fn get_dev_env_interactively(config: &Config) -> Result<String, MddeError>
 
 /// 获取默认的开发环境列表（作为回退选项）
 fn get_default_environments() -> Vec<DevEnvironment> {
-    vec![
-        DevEnvironment {
-            name: "dotnet9".to_string(),
-            description: i18n::t("dotnet9_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "dotnet8".to_string(),
-            description: i18n::t("dotnet8_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "dotnet6".to_string(),
-            description: i18n::t("dotnet6_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "java21".to_string(),
-            description: i18n::t("java21_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "java18".to_string(),
-            description: i18n::t("java18_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "java11".to_string(),
-            description: i18n::t("java11_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "node22".to_string(),
-            description: i18n::t("node22_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "node20".to_string(),
-            description: i18n::t("node20_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "node18".to_string(),
-            description: i18n::t("node18_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "python312".to_string(),
-            description: i18n::t("python312_desc").to_string(),
-        },
-        DevEnvironment {
-            name: "python311".to_string(),
-            description: i18n::t("python311_desc").to_string(),
-        },
+    [
+        ("dotnet9", "dotnet9_desc"),
+        ("dotnet8", "dotnet8_desc"),
+        ("dotnet6", "dotnet6_desc"),
+        ("java21", "java21_desc"),
+        ("java18", "java18_desc"),
+        ("java11", "java11_desc"),
+        ("node22", "node22_desc"),
+        ("node20", "node20_desc"),
+        ("node18", "node18_desc"),
+        ("python312", "python312_desc"),
+        ("python311", "python311_desc"),
     ]
+    .into_iter()
+    .map(|(name, description)| DevEnvironment::new(name, i18n::t(description)))
+    .collect()
 }
 
 /// 交互式获取环境名称
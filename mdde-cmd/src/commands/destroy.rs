@@ -0,0 +1,326 @@
+use crate::backend::service::COMPOSE_PROJECT_LABEL;
+use crate::backend::ContainerBackend;
+use crate::compose::ComposeProject;
+use crate::config::Config;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
+use crate::registry::Registry;
+use crate::utils;
+use colored::*;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 彻底删除当前环境：容器、命名卷、网络、构建的镜像和环境目录
+///
+/// 执行前列出将被删除的内容并请求确认，`yes` 时跳过确认。`keep_config` 时保留环境目录，
+/// `archive` 时先将环境目录打包到项目根目录下的 tar 文件。项目中没有其他环境时一并删除 `.mdde/`
+/// 和 `.gitignore` 中的条目。
+pub async fn execute(
+    keep_config: bool,
+    archive: bool,
+    yes: bool,
+    _config: Config,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
+    let project = ComposeProject::current()?.with_legacy_fallback(backend);
+    info!("销毁开发环境: {}", project.environment);
+
+    let usage = backend
+        .disk_usage()
+        .map_err(|e| MddeError::Docker(format!("获取资源占用失败: {}", e)))?;
+    let containers: Vec<_> = usage
+        .containers
+        .iter()
+        .filter(|c| project.owns(&c.labels))
+        .collect();
+    let volumes: Vec<_> = usage
+        .volumes
+        .iter()
+        .filter(|v| project.owns(&v.labels))
+        .collect();
+    let networks: Vec<_> = usage
+        .networks
+        .iter()
+        .filter(|n| project.owns(&n.labels))
+        .collect();
+    let images: Vec<_> = usage
+        .images
+        .iter()
+        .filter(|i| project.owns(&i.labels))
+        .collect();
+
+    // compose down 按项目名称删除容器、卷和网络，这些资源都必须在下面列出的范围内
+    let compose_name = project.name();
+    let in_compose =
+        |labels: &HashMap<String, String>| labels.get(COMPOSE_PROJECT_LABEL) == Some(&compose_name);
+    let foreign: Vec<&str> = usage
+        .containers
+        .iter()
+        .filter(|c| in_compose(&c.labels) && !project.owns(&c.labels))
+        .map(|c| c.name.as_str())
+        .chain(
+            usage
+                .volumes
+                .iter()
+                .filter(|v| in_compose(&v.labels) && !project.owns(&v.labels))
+                .map(|v| v.name.as_str()),
+        )
+        .chain(
+            usage
+                .networks
+                .iter()
+                .filter(|n| in_compose(&n.labels) && !project.owns(&n.labels))
+                .map(|n| n.name.as_str()),
+        )
+        .collect();
+    if !foreign.is_empty() {
+        return Err(MddeError::Docker(i18n::tf(
+            "destroy_foreign_resources",
+            &[&compose_name, &foreign.join(", ")],
+        )));
+    }
+
+    // 项目中只有这一个环境时删除整个 .mdde/
+    let last_env = project::environments(&project.root) == [project.environment.clone()];
+    let files = env_files(&project, last_env)?;
+    let archive_path = archive.then(|| archive_path(&project));
+
+    let list = |items: Vec<String>| {
+        if items.is_empty() {
+            i18n::t("destroy_none").to_string()
+        } else {
+            items.join(", ")
+        }
+    };
+    let relative = |path: &Path| {
+        path.strip_prefix(&project.root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let file_names = list(files.iter().map(|path| relative(path)).collect());
+
+    println!(
+        "{}",
+        i18n::tf(
+            "destroy_summary",
+            &[&project.environment, &project.root.display()]
+        )
+        .yellow()
+    );
+    println!(
+        "{}",
+        i18n::tf(
+            "destroy_containers",
+            &[&list(containers.iter().map(|c| c.name.clone()).collect())]
+        )
+    );
+    println!(
+        "{}",
+        i18n::tf(
+            "destroy_volumes",
+            &[&list(volumes.iter().map(|v| v.name.clone()).collect())]
+        )
+    );
+    println!(
+        "{}",
+        i18n::tf(
+            "destroy_images",
+            &[&list(images.iter().map(|i| i.display_name()).collect())]
+        )
+    );
+    println!(
+        "{}",
+        i18n::tf(
+            "destroy_networks",
+            &[&list(networks.iter().map(|n| n.name.clone()).collect())]
+        )
+    );
+    match (&archive_path, keep_config) {
+        (_, true) => println!("{}", i18n::tf("destroy_files_keep", &[&file_names])),
+        (Some(path), false) => println!(
+            "{}",
+            i18n::tf("destroy_files_archive", &[&relative(path), &file_names])
+        ),
+        (None, false) => println!("{}", i18n::tf("destroy_files_delete", &[&file_names])),
+    }
+    if last_env && !keep_config {
+        println!("{}", i18n::t("destroy_gitignore"));
+    }
+
+    if !yes {
+        if !io::stdin().is_terminal() {
+            return Err(MddeError::InvalidArgument(
+                i18n::t("destroy_confirm_required").to_string(),
+            ));
+        }
+        if !utils::confirm(i18n::t("destroy_confirm_prompt"))? {
+            println!("{}", i18n::t("destroy_cancelled"));
+            return Ok(());
+        }
+    }
+
+    println!("{}", i18n::t("destroying_environment").yellow());
+    backend
+        .compose_down(&project, true)
+        .map_err(|e| MddeError::Docker(i18n::tf("stop_failed", &[&e])))?;
+
+    // 不属于这个 compose 项目的资源（如旧版本以目录名作为项目名称创建的）compose down 不会删除，逐个删除
+    for container in containers.iter().filter(|c| !in_compose(&c.labels)) {
+        if let Err(e) = backend.remove_container(&container.name, true) {
+            println!(
+                "{}",
+                i18n::tf("destroy_container_failed", &[&container.name, &e]).yellow()
+            );
+        }
+    }
+    for volume in volumes.iter().filter(|v| !in_compose(&v.labels)) {
+        if let Err(e) = backend.remove_volume(&volume.name) {
+            println!(
+                "{}",
+                i18n::tf("destroy_volume_failed", &[&volume.name, &e]).yellow()
+            );
+        }
+    }
+    for network in networks.iter().filter(|n| !in_compose(&n.labels)) {
+        if let Err(e) = backend.remove_network(&network.name) {
+            println!(
+                "{}",
+                i18n::tf("destroy_network_failed", &[&network.name, &e]).yellow()
+            );
+        }
+    }
+
+    // 镜像删除失败（如被其他容器使用）不影响其余步骤
+    for image in &images {
+        if let Err(e) = backend.remove_image(&image.id, true) {
+            println!(
+                "{}",
+                i18n::tf("destroy_image_failed", &[&image.display_name(), &e]).yellow()
+            );
+        }
+    }
+
+    if let Some(path) = &archive_path {
+        write_archive(path, &project.root, &files)?;
+        println!(
+            "{}",
+            i18n::tf("destroy_archived", &[&path.display()]).green()
+        );
+    }
+
+    if !keep_config {
+        for path in &files {
+            if path.is_dir() {
+                std::fs::remove_dir_all(path)?;
+            } else if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+        if project::default_environment(&project.root)?.as_deref()
+            == Some(project.environment.as_str())
+        {
+            project::set_default_environment(&project.root, project::DEFAULT_ENV)?;
+        }
+        if last_env {
+            Config::remove_gitignore_entry(&project.root).await?;
+        }
+
+        let mut registry = Registry::load()?;
+        if registry.unregister(&project.root, &project.environment) {
+            registry.save()?;
+        }
+    }
+
+    println!(
+        "{}",
+        i18n::tf("destroy_completed", &[&project.environment]).green()
+    );
+
+    Ok(())
+}
+
+/// 环境的文件：`last_env` 时为整个 `.mdde/`；命名环境为它的目录；
+/// `default` 环境为 `.mdde/` 下除其他环境和默认环境记录以外的文件
+fn env_files(project: &ComposeProject, last_env: bool) -> Result<Vec<PathBuf>, MddeError> {
+    let mdde_dir = project.root.join(project::MDDE_DIR);
+    if last_env {
+        return Ok(vec![mdde_dir]);
+    }
+    if project.environment != project::DEFAULT_ENV {
+        return Ok(vec![project::env_dir_in(
+            &project.root,
+            &project.environment,
+        )]);
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&mdde_dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            name != project::ENVS_DIR && name != project::DEFAULT_ENV_FILE
+        })
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// 归档文件的路径，如 `mdde-dev-20240101-120000.tar`
+fn archive_path(project: &ComposeProject) -> PathBuf {
+    project.root.join(format!(
+        "mdde-{}-{}.tar",
+        project.environment,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+/// 将文件打包为 tar，包中的路径相对于项目根目录
+fn write_archive(path: &Path, root: &Path, files: &[PathBuf]) -> Result<(), MddeError> {
+    let mut archive = tar::Builder::new(std::fs::File::create(path)?);
+    archive.follow_symlinks(false);
+    for file in files {
+        let name = file.strip_prefix(root).unwrap_or(file);
+        if file.is_dir() {
+            archive.append_dir_all(name, file)?;
+        } else {
+            archive.append_path_with_name(file, name)?;
+        }
+    }
+    archive.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mdde_dir = dir.path().join(project::MDDE_DIR);
+        std::fs::create_dir_all(mdde_dir.join(project::ENVS_DIR).join("test")).unwrap();
+        std::fs::write(mdde_dir.join("docker-compose.yml"), "").unwrap();
+        std::fs::write(mdde_dir.join("cfg.env"), "").unwrap();
+        std::fs::write(mdde_dir.join(project::DEFAULT_ENV_FILE), "test\n").unwrap();
+
+        let default = ComposeProject::new(dir.path());
+        assert_eq!(
+            env_files(&default, false).unwrap(),
+            vec![
+                mdde_dir.join("cfg.env"),
+                mdde_dir.join("docker-compose.yml")
+            ]
+        );
+        assert_eq!(env_files(&default, true).unwrap(), vec![mdde_dir.clone()]);
+
+        let test = ComposeProject::in_environment(dir.path(), "test");
+        assert_eq!(
+            env_files(&test, false).unwrap(),
+            vec![mdde_dir.join(project::ENVS_DIR).join("test")]
+        );
+    }
+}
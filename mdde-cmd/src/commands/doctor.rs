@@ -1,8 +1,14 @@
+#[cfg(unix)]
+use crate::backend::EngineApiBackend;
+use crate::commands;
+use crate::compose::{self, ComposeProject};
 use crate::config::Config;
+use crate::config_check;
+use crate::container_engine::{self, ContainerEngine};
 use crate::error::MddeError;
 use crate::i18n;
+use crate::project;
 use colored::*;
-use std::process::Command;
 use tracing::info;
 
 pub async fn execute(_config: Config) -> Result<(), MddeError> {
@@ -11,11 +17,11 @@ pub async fn execute(_config: Config) -> Result<(), MddeError> {
     println!("{}", "🔍 MDDE 系统诊断".blue().bold());
     println!("{}", "=".repeat(50));
 
-    // 检查 Docker
-    check_docker()?;
+    // 检查容器引擎
+    check_engine(&_config)?;
 
     // 检查 Docker Compose
-    check_docker_compose()?;
+    check_docker_compose(&_config)?;
 
     // 检查网络连接
     check_network_connection(&_config).await?;
@@ -23,59 +29,108 @@ pub async fn execute(_config: Config) -> Result<(), MddeError> {
     // 检查配置文件
     check_config_files()?;
 
+    // 检查 compose 变量
+    check_compose_variables()?;
+
     println!("{}", "=".repeat(50));
     println!("{}", "✓ 诊断完成".green());
 
     Ok(())
 }
 
-fn check_docker() -> Result<(), MddeError> {
-    println!("{}", "\n🐳 检查 Docker...".cyan());
+fn check_engine(config: &Config) -> Result<(), MddeError> {
+    let engine = container_engine::current();
+    println!(
+        "{}",
+        format!("\n🐳 {}", i18n::tf("check_engine", &[&engine])).cyan()
+    );
 
-    let output = Command::new("docker").arg("--version").output();
-    match output {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout);
-            println!("{}", i18n::t("docker_installed").green());
-            println!("{}", i18n::tf("docker_version", &[&version.trim()]));
+    let source = if config.engine.is_some() {
+        i18n::t("engine_source_configured")
+    } else {
+        i18n::t("engine_source_detected")
+    };
+
+    let (install_hint, start_hint) = match engine {
+        ContainerEngine::Docker => ("install_docker", "start_docker"),
+        ContainerEngine::Podman => ("install_podman", "start_podman"),
+    };
+
+    match engine.version() {
+        Some(version) => {
+            println!(
+                "{}",
+                i18n::tf("engine_installed", &[&engine, &source]).green()
+            );
+            println!("{}", i18n::tf("docker_version", &[&version]));
         }
-        _ => {
-            println!("{}", i18n::t("docker_not_installed").red());
-            println!("{}", i18n::t("install_docker"));
-            return Err(MddeError::Docker("Docker 未安装".to_string()));
+        None => {
+            println!("{}", i18n::tf("engine_not_installed", &[&engine]).red());
+            println!("{}", i18n::t(install_hint));
+            return Err(MddeError::Docker(format!("{} 未安装", engine)));
         }
     }
 
-    // 检查 Docker 服务状态
-    let output = Command::new("docker").arg("info").output();
+    // 检查引擎服务状态
+    let output = engine.command().arg("info").output();
     match output {
         Ok(output) if output.status.success() => {
-            println!("{}", i18n::t("docker_running").green());
+            println!("{}", i18n::tf("engine_running", &[&engine]).green());
         }
         _ => {
-            println!("{}", i18n::t("docker_not_running").red());
-            println!("{}", i18n::t("start_docker"));
-            return Err(MddeError::Docker("Docker 服务未运行".to_string()));
+            println!("{}", i18n::tf("engine_not_running", &[&engine]).red());
+            println!("{}", i18n::t(start_hint));
+            return Err(MddeError::Docker(format!("{} 服务未运行", engine)));
         }
     }
 
+    // 检查 API 套接字，不可用时命令会回退到命令行
+    #[cfg(unix)]
+    match EngineApiBackend::for_engine(engine) {
+        Some(backend) if backend.ping() => {
+            println!(
+                "{}",
+                i18n::tf("engine_api_socket", &[&backend.socket().display()])
+            );
+        }
+        _ => println!("{}", i18n::t("engine_api_unavailable").yellow()),
+    }
+
     Ok(())
 }
 
-fn check_docker_compose() -> Result<(), MddeError> {
+fn check_docker_compose(config: &Config) -> Result<(), MddeError> {
     println!(
         "{}",
         format!("\n{}", i18n::t("check_docker_compose")).cyan()
     );
 
-    let output = Command::new("docker-compose").arg("--version").output();
-    match output {
-        Ok(output) if output.status.success() => {
-            let version = String::from_utf8_lossy(&output.stdout);
+    let source = if config.compose.is_some() {
+        i18n::t("compose_source_configured")
+    } else {
+        i18n::t("compose_source_detected")
+    };
+
+    let engine = container_engine::current();
+    let selected = compose::flavor()
+        .ok()
+        .and_then(|flavor| flavor.version(engine).map(|version| (flavor, version)));
+    match selected {
+        Some((flavor, version)) => {
             println!("{}", i18n::t("docker_compose_installed").green());
-            println!("{}", i18n::tf("docker_version", &[&version.trim()]));
+            println!(
+                "{}",
+                i18n::tf("compose_flavor", &[&flavor.describe(engine), &source])
+            );
+            println!("{}", i18n::tf("docker_version", &[&version]));
         }
-        _ => {
+        None => {
+            if let Some(flavor) = config.compose {
+                println!(
+                    "{}",
+                    i18n::tf("compose_flavor_unavailable", &[&flavor.describe(engine)]).red()
+                );
+            }
             println!("{}", i18n::t("docker_compose_not_installed").red());
             println!("{}", i18n::t("install_docker_compose"));
             return Err(MddeError::Docker("Docker Compose 未安装".to_string()));
@@ -114,23 +169,53 @@ async fn check_network_connection(config: &Config) -> Result<(), MddeError> {
 fn check_config_files() -> Result<(), MddeError> {
     println!("{}", format!("\n{}", i18n::t("check_config_files")).cyan());
 
-    // 检查当前目录的配置文件
-    let current_dir = std::env::current_dir()?;
-    let compose_file = current_dir.join("docker-compose.yml");
-    let env_file = current_dir.join(".mdde").join("cfg.env");
+    // 检查项目根目录的配置文件
+    let root = project::root()?;
+    let project = ComposeProject::active()?;
+    println!(
+        "{}",
+        i18n::tf(
+            "current_environment",
+            &[&project::environment()?, &project::env_dir()?.display()]
+        )
+    );
+    let (compose_file, env_file) = (project.compose_file, project.env_file);
 
     if compose_file.exists() {
         println!("{}", i18n::t("docker_compose_exists").green());
     } else {
         println!("{}", i18n::t("docker_compose_not_exists").yellow());
-        println!("{}", i18n::tf("current_dir", &[&current_dir.display()]));
+        println!("{}", i18n::tf("project_root", &[&root.display()]));
     }
 
     if env_file.exists() {
         println!("{}", i18n::t("mdde_env_exists").green());
     } else {
         println!("{}", i18n::t("mdde_env_not_exists").yellow());
-        println!("{}", i18n::tf("current_dir", &[&current_dir.display()]));
+        println!("{}", i18n::tf("project_root", &[&root.display()]));
+    }
+
+    Ok(())
+}
+
+fn check_compose_variables() -> Result<(), MddeError> {
+    println!(
+        "{}",
+        format!("\n{}", i18n::t("check_compose_variables")).cyan()
+    );
+
+    let project = ComposeProject::active()?;
+    if project.ensure_exists().is_err() {
+        println!("{}", i18n::t("docker_compose_not_exists").yellow());
+        return Ok(());
+    }
+
+    let report = config_check::check_project(&project)?;
+    if report.issues.is_empty() {
+        println!("{}", i18n::t("check_passed").green());
+    }
+    for line in commands::config::issue_lines(&report) {
+        println!("{}", line);
     }
 
     Ok(())
@@ -4,6 +4,7 @@ use crate::i18n;
 use colored::*;
 use tracing::info;
 
+/// 管理 `cfg.env`，文件有语法错误时宽松解析，使这些命令可以修复它
 pub async fn execute(
     set: Option<String>,
     ls: bool,
@@ -44,22 +45,25 @@ pub async fn execute(
 async fn list_env_vars() -> Result<(), MddeError> {
     info!("{}", i18n::t("display_env_vars"));
 
-    let env_vars = Config::load_env_file().await?;
+    let env_vars = Config::load_env_document_lenient().await?.0;
 
     if env_vars.is_empty() {
         println!("{}", i18n::t("env_file_empty").yellow());
-        println!("{}", i18n::t("file_location"));
+        println!(
+            "{}",
+            i18n::tf("file_location", &[&Config::get_env_file_path()?.display()])
+        );
         return Ok(());
     }
 
-    println!("{}", i18n::t("env_config_header").cyan());
+    println!(
+        "{}",
+        i18n::tf("env_config_header", &[&Config::get_env_file_path()?.display()]).cyan()
+    );
     println!("{}", "================================".cyan());
 
-    // 按键名排序显示
-    let mut sorted_vars: Vec<_> = env_vars.iter().collect();
-    sorted_vars.sort_by_key(|(key, _)| *key);
-
-    for (key, value) in sorted_vars {
+    // 按文件中的顺序显示
+    for (key, value) in env_vars.entries() {
         println!("{}={}", key.green(), value);
     }
 
@@ -76,13 +80,13 @@ async fn set_env_var(key_value: &str) -> Result<(), MddeError> {
     info!("{}: {}={}", i18n::t("set_env_var"), key, value);
 
     // 加载现有环境变量
-    let mut env_vars = Config::load_env_file().await?;
+    let mut env_vars = Config::load_env_document_lenient().await?.0;
 
     // 检查是否是更新现有变量
     let is_update = env_vars.contains_key(&key);
 
-    // 设置新值
-    env_vars.insert(key.clone(), value.clone());
+    // 设置新值，其他行保持不变
+    env_vars.set(&key, &value);
 
     // 保存到文件
     Config::save_env_file(&env_vars).await?;
@@ -102,17 +106,14 @@ async fn delete_env_var(key: &str) -> Result<(), MddeError> {
     info!("{}: {}", i18n::t("delete_env_var"), key);
 
     // 加载现有环境变量
-    let mut env_vars = Config::load_env_file().await?;
+    let mut env_vars = Config::load_env_document_lenient().await?.0;
 
-    // 检查变量是否存在
-    if !env_vars.contains_key(key) {
+    // 删除变量，检查变量是否存在
+    let Some(old_value) = env_vars.remove(key) else {
         return Err(MddeError::InvalidArgument(
             i18n::tf("env_var_not_exists", &[&key])
         ));
-    }
-
-    // 删除变量
-    let old_value = env_vars.remove(key).unwrap();
+    };
 
     // 保存到文件
     Config::save_env_file(&env_vars).await?;
@@ -0,0 +1,107 @@
+use crate::cli::OutputFormat;
+use crate::env_file::EnvFile;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
+use crate::table;
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+use tracing::info;
+
+/// 项目中的一个环境
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvironmentInfo {
+    pub name: String,
+    /// 环境目录，相对于项目根目录
+    pub path: String,
+    /// cfg.env 中的 `container_name`
+    pub container_name: Option<String>,
+    /// 是否为项目记录的默认环境
+    pub default: bool,
+    /// 是否为本次命令使用的环境
+    pub active: bool,
+}
+
+pub async fn execute(format: OutputFormat) -> Result<(), MddeError> {
+    info!("列出项目中的环境");
+
+    let root = project::root()?;
+    let environments = collect(&root, &project::environment()?);
+    println!("{}", render(&environments, format)?);
+
+    Ok(())
+}
+
+/// 收集项目中已创建的环境，`active` 为当前使用的环境
+pub fn collect(root: &Path, active: &str) -> Vec<EnvironmentInfo> {
+    // 记录无效时 `project::environment` 已经报错，这里只用于显示
+    let default = project::default_environment(root)
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| project::DEFAULT_ENV.to_string());
+
+    project::environments(root)
+        .into_iter()
+        .map(|name| {
+            let env_dir = project::env_dir_in(root, &name);
+            let container_name = std::fs::read_to_string(env_dir.join("cfg.env"))
+                .ok()
+                .and_then(|content| EnvFile::parse(&content).ok())
+                .and_then(|env| env.get("container_name").map(str::to_string));
+            EnvironmentInfo {
+                path: env_dir
+                    .strip_prefix(root)
+                    .unwrap_or(&env_dir)
+                    .display()
+                    .to_string(),
+                container_name,
+                default: name == default,
+                active: name == active,
+                name,
+            }
+        })
+        .collect()
+}
+
+/// 按指定格式渲染环境列表，表格中当前使用的环境以 `*` 标记
+pub fn render(environments: &[EnvironmentInfo], format: OutputFormat) -> Result<String, MddeError> {
+    let data = json!({
+        "environments": environments,
+        "total": environments.len()
+    });
+
+    match format {
+        OutputFormat::Table => {
+            if environments.is_empty() {
+                return Ok(i18n::t("no_environments").to_string());
+            }
+
+            let headers = ["", "NAME", "CONTAINER", "DEFAULT", "PATH"];
+            let rows: Vec<[String; 5]> = environments
+                .iter()
+                .map(|env| {
+                    [
+                        if env.active { "*" } else { "" }.to_string(),
+                        env.name.clone(),
+                        env.container_name
+                            .clone()
+                            .unwrap_or_else(|| "-".to_string()),
+                        if env.default { "yes" } else { "" }.to_string(),
+                        env.path.clone(),
+                    ]
+                })
+                .collect();
+
+            Ok(table::render(&headers, &rows).join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": "success",
+                "data": data
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
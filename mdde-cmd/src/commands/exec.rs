@@ -1,11 +1,15 @@
+use crate::backend::ContainerBackend;
 use crate::config::Config;
-use crate::docker::DockerCommand;
 use crate::error::MddeError;
 use crate::i18n;
 use colored::*;
 use tracing::info;
 
-pub async fn execute(shell: String, config: Config) -> Result<(), MddeError> {
+pub async fn execute(
+    shell: String,
+    config: Config,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
     let container_name = config
         .container_name
         .clone()
@@ -24,12 +28,12 @@ pub async fn execute(shell: String, config: Config) -> Result<(), MddeError> {
     println!("{}", i18n::t("exit_hint").yellow());
 
     // 检查容器是否正在运行
-    if !DockerCommand::container_running(&container_name)? {
+    if !backend.container_running(&container_name)? {
         return Err(MddeError::ContainerNotRunning(container_name));
     }
 
     // 进入容器进行交互式操作
-    match DockerCommand::exec_interactive(&container_name, &shell) {
+    match backend.exec_interactive(&container_name, &shell) {
         Ok(_) => {
             println!("{}", i18n::t("exited_container").green());
         }
@@ -25,17 +25,11 @@ pub async fn execute(host: Option<String>, mut config: Config) -> Result<(), Mdd
     // 验证URL格式
     validate_url(&host)?;
 
-    // 更新配置
+    // 更新配置，写入环境变量文件（不存在时创建），文件中的其他内容保持不变
     let mut updates = HashMap::new();
     updates.insert("host".to_string(), host.clone());
     config.update(updates).await?;
 
-    // 创建环境变量文件
-    let mut env_vars = HashMap::new();
-    env_vars.insert("host".to_string(), host.clone());
-
-    Config::save_env_file(&env_vars).await?;
-
     println!("{}", i18n::t("init_success").green());
     println!("{}", i18n::tf("server_address", &[&host]));
     println!("{}", i18n::t("env_file_created"));
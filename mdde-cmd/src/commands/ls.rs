@@ -0,0 +1,155 @@
+use crate::backend::{self, ContainerBackend};
+use crate::cli::OutputFormat;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
+use crate::registry::{Registry, RegistryEntry};
+use crate::table::Table;
+use colored::*;
+use serde::Serialize;
+use serde_json::json;
+use tracing::info;
+
+/// 登记的环境及其当前状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EnvironmentState {
+    #[serde(flatten)]
+    pub entry: RegistryEntry,
+    /// running / partial / stopped / missing（目录已不存在）/ unknown（无法查询容器引擎）
+    pub state: String,
+    pub running: usize,
+    pub total: usize,
+}
+
+/// `all` 为 false 时只列出当前项目的环境，`prune` 为 true 时先移除目录已不存在的环境
+pub async fn execute(
+    all: bool,
+    prune: bool,
+    format: OutputFormat,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
+    info!("列出登记的开发环境");
+
+    let mut registry = Registry::load()?;
+    if prune {
+        let removed = registry.prune();
+        registry.save()?;
+        if format == OutputFormat::Table {
+            for entry in &removed {
+                println!(
+                    "{}",
+                    i18n::tf(
+                        "registry_pruned",
+                        &[&entry.environment, &entry.project_dir.display()]
+                    )
+                    .yellow()
+                );
+            }
+        }
+    }
+
+    let entries = if all {
+        registry.environments.iter().collect()
+    } else {
+        registry.in_project(&project::root()?)
+    };
+    let states: Vec<_> = entries
+        .into_iter()
+        .map(|entry| live_state(entry, backend))
+        .collect();
+    println!("{}", render(&states, format)?);
+
+    Ok(())
+}
+
+/// 查询环境的服务容器，得出环境的状态
+pub fn live_state(entry: &RegistryEntry, backend: &dyn ContainerBackend) -> EnvironmentState {
+    let (state, running, total) = if !entry.exists() {
+        ("missing", 0, 0)
+    } else {
+        match backend::compose_services(
+            backend,
+            &entry.compose_project().with_legacy_fallback(backend),
+        ) {
+            Ok(services) => {
+                let running = services.iter().filter(|s| s.is_running()).count();
+                let state = match running {
+                    0 => "stopped",
+                    n if n == services.len() => "running",
+                    _ => "partial",
+                };
+                (state, running, services.len())
+            }
+            Err(_) => ("unknown", 0, 0),
+        }
+    };
+
+    EnvironmentState {
+        entry: entry.clone(),
+        state: state.to_string(),
+        running,
+        total,
+    }
+}
+
+/// 按指定格式渲染环境列表
+pub fn render(states: &[EnvironmentState], format: OutputFormat) -> Result<String, MddeError> {
+    let data = json!({
+        "environments": states,
+        "total": states.len()
+    });
+
+    match format {
+        OutputFormat::Table => {
+            if states.is_empty() {
+                return Ok(i18n::t("no_registered_environments").to_string());
+            }
+
+            let headers = ["PROJECT", "ENV", "CONTAINER", "TEMPLATE", "PORTS", "STATE"];
+            let rows: Vec<[String; 6]> = states
+                .iter()
+                .map(|state| {
+                    let ports = if state.entry.ports.is_empty() {
+                        "-".to_string()
+                    } else {
+                        state.entry.ports.join(", ")
+                    };
+                    let status = if state.total > 0 {
+                        format!("{} ({}/{})", state.state, state.running, state.total)
+                    } else {
+                        state.state.clone()
+                    };
+                    [
+                        state.entry.project_dir.display().to_string(),
+                        state.entry.environment.clone(),
+                        state.entry.container_name.clone(),
+                        state.entry.template.clone(),
+                        ports,
+                        status,
+                    ]
+                })
+                .collect();
+
+            let table = Table::new(&headers, &rows);
+            let mut lines = vec![table.header()];
+            lines.extend(rows.iter().zip(states).map(|(row, state)| {
+                let line = table.row(row);
+                match state.state.as_str() {
+                    "running" => line.green().to_string(),
+                    "missing" => line.red().to_string(),
+                    "partial" | "unknown" => line.yellow().to_string(),
+                    _ => line,
+                }
+            }));
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": "success",
+                "data": data
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
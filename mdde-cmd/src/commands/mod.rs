@@ -1,13 +1,21 @@
+pub mod cache;
 pub mod clean;
+pub mod config;
 pub mod create;
+pub mod destroy;
 pub mod doctor;
 pub mod env;
+pub mod env_list;
 pub mod exec;
 pub mod init;
 pub mod logs;
+pub mod ls;
+pub mod port;
 pub mod restart;
 pub mod run;
 pub mod start;
 pub mod status;
 pub mod stop;
+pub mod use_env;
+pub mod verify;
 pub mod version;
@@ -0,0 +1,205 @@
+use crate::backend::ContainerBackend;
+use crate::cli::{OutputFormat, PortAction};
+use crate::compose::ComposeProject;
+use crate::config::Config;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::port_mapping::PortMapping;
+use crate::ports::PortCheck;
+use crate::table;
+use colored::*;
+use serde_json::{json, Value};
+use tracing::info;
+
+pub async fn execute(
+    action: PortAction,
+    _config: Config,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
+    // 检查 docker-compose.yml 和 .mdde/cfg.env 文件是否存在
+    let project = ComposeProject::current()?;
+
+    let mut env_vars = Config::load_env_document().await?;
+    let mut mappings = Config::ports_from_env(&env_vars.to_map())?;
+
+    match action {
+        PortAction::Ls { format } => {
+            println!("{}", render(&mappings, format)?);
+            return Ok(());
+        }
+        PortAction::Add {
+            mappings: added,
+            auto_port,
+        } => {
+            let mut added = added
+                .iter()
+                .map(|mapping| PortMapping::parse_list(mapping))
+                .collect::<Result<Vec<_>, _>>()?
+                .concat();
+
+            for mapping in &added {
+                if let Some(existing) = mappings.iter().find(|m| overlaps(m, mapping)) {
+                    return Err(MddeError::InvalidArgument(i18n::tf(
+                        "port_mapping_exists",
+                        &[mapping, existing],
+                    )));
+                }
+            }
+
+            PortCheck::collect(backend, &project).resolve_mappings(&mut added, auto_port)?;
+            for mapping in added {
+                info!("添加端口映射: {}", mapping);
+                println!("{}", i18n::tf("port_added", &[&mapping]).green());
+                mappings.push(mapping);
+            }
+        }
+        PortAction::Rm { mappings: removed } => {
+            for target in removed {
+                let before = mappings.len();
+                mappings.retain(|mapping| !matches_target(mapping, &target));
+                if mappings.len() == before {
+                    return Err(MddeError::InvalidArgument(i18n::tf(
+                        "port_mapping_not_found",
+                        &[&target],
+                    )));
+                }
+                info!("删除端口映射: {}", target);
+                println!("{}", i18n::tf("port_removed", &[&target]).green());
+            }
+        }
+    }
+
+    Config::ports_into_env(&mut env_vars, &mappings);
+    Config::save_env_file(&env_vars).await?;
+    println!("{}", i18n::t("port_restart_hint"));
+
+    Ok(())
+}
+
+/// 两个映射在同一协议下占用了相同的宿主机端口
+fn overlaps(a: &PortMapping, b: &PortMapping) -> bool {
+    let (a_range, b_range) = (a.host_range(), b.host_range());
+    a.protocol == b.protocol && a_range.start <= b_range.end && b_range.start <= a_range.end
+}
+
+/// `rm` 的参数可以是完整的映射，也可以只是宿主机端口
+fn matches_target(mapping: &PortMapping, target: &str) -> bool {
+    match target.parse::<u16>() {
+        Ok(port) => mapping.host_range().contains(port),
+        Err(_) => target
+            .parse::<PortMapping>()
+            .is_ok_and(|target| &target == mapping),
+    }
+}
+
+/// 按指定格式渲染端口映射，第一个映射为 `app_port`
+pub fn render(mappings: &[PortMapping], format: OutputFormat) -> Result<String, MddeError> {
+    let ports: Vec<Value> = mappings
+        .iter()
+        .enumerate()
+        .map(|(index, mapping)| {
+            json!({
+                "mapping": mapping.to_string(),
+                "variable": variable(index),
+                "host_ip": mapping.host_ip,
+                "host_port": mapping.host_range().to_string(),
+                "container_port": mapping.container.to_string(),
+                "protocol": mapping.protocol.as_str(),
+            })
+        })
+        .collect();
+    let data = json!({
+        "ports": ports,
+        "total": mappings.len()
+    });
+
+    match format {
+        OutputFormat::Table => {
+            if mappings.is_empty() {
+                return Ok(i18n::t("no_port_mappings").to_string());
+            }
+
+            let headers = [
+                "VARIABLE",
+                "HOST IP",
+                "HOST PORT",
+                "CONTAINER PORT",
+                "PROTOCOL",
+            ];
+            let rows: Vec<[String; 5]> = mappings
+                .iter()
+                .enumerate()
+                .map(|(index, mapping)| {
+                    [
+                        variable(index).to_string(),
+                        mapping.host_ip.clone().unwrap_or_else(|| "-".to_string()),
+                        mapping.host_range().to_string(),
+                        mapping.container.to_string(),
+                        mapping.protocol.to_string(),
+                    ]
+                })
+                .collect();
+
+            Ok(table::render(&headers, &rows).join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": "success",
+                "data": data
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
+
+/// 映射在 cfg.env 中对应的变量
+fn variable(index: usize) -> &'static str {
+    if index == 0 {
+        "app_port"
+    } else {
+        "ports"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(s: &str) -> PortMapping {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(overlaps(&mapping("8080:80"), &mapping("8080:81")));
+        assert!(overlaps(
+            &mapping("7000-7005:7000-7005"),
+            &mapping("7003:80")
+        ));
+        assert!(!overlaps(&mapping("5353:53/udp"), &mapping("5353:53")));
+        assert!(!overlaps(&mapping("8080:80"), &mapping("8081:80")));
+    }
+
+    #[test]
+    fn test_matches_target() {
+        assert!(matches_target(&mapping("8080:80"), "8080"));
+        assert!(matches_target(&mapping("8080:80"), "8080:80"));
+        assert!(matches_target(&mapping("7000-7002:7000-7002"), "7001"));
+        assert!(!matches_target(&mapping("8080:80"), "8080:80/udp"));
+        assert!(!matches_target(&mapping("8080:80"), "80"));
+    }
+
+    #[test]
+    fn test_render_json() {
+        let mappings = vec![mapping("8080:80"), mapping("127.0.0.1:5353:53/udp")];
+        let output = render(&mappings, OutputFormat::Json).unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(value["data"]["total"], 2);
+        assert_eq!(value["data"]["ports"][0]["variable"], "app_port");
+        assert_eq!(value["data"]["ports"][1]["variable"], "ports");
+        assert_eq!(value["data"]["ports"][1]["host_ip"], "127.0.0.1");
+        assert_eq!(value["data"]["ports"][1]["protocol"], "udp");
+    }
+}
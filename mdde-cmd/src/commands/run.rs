@@ -1,11 +1,15 @@
+use crate::backend::ContainerBackend;
 use crate::config::Config;
-use crate::docker::DockerCommand;
 use crate::error::MddeError;
 use crate::i18n;
 use colored::*;
 use tracing::info;
 
-pub async fn execute(command: Vec<String>, config: Config) -> Result<(), MddeError> {
+pub async fn execute(
+    command: Vec<String>,
+    config: Config,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
     let container_name = config
         .container_name
         .clone()
@@ -24,12 +28,12 @@ pub async fn execute(command: Vec<String>, config: Config) -> Result<(), MddeErr
     );
 
     // 检查容器是否正在运行
-    if !DockerCommand::container_running(&container_name)? {
+    if !backend.container_running(&container_name)? {
         return Err(MddeError::ContainerNotRunning(container_name));
     }
 
     // 执行命令，实时输出
-    match DockerCommand::exec_command_stream(&container_name, &command_str) {
+    match backend.exec_stream(&container_name, &command_str) {
         Ok(()) => {
             println!("{}", i18n::t("command_success").green());
         }
@@ -1,19 +1,71 @@
+use crate::backend::{self, ContainerBackend, ServiceStatus};
+use crate::commands;
+use crate::compose::ComposeProject;
 use crate::config::Config;
-use crate::docker::DockerCommand;
+use crate::config_check;
 use crate::error::MddeError;
 use crate::i18n;
+use crate::port_mapping::PortMapping;
+use crate::ports::{self, PortCheck};
 use colored::*;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
 use tracing::info;
 
-pub async fn execute(detach: bool, _config: Config) -> Result<(), MddeError> {
-    let name = _config.container_name.clone();
+/// 等待就绪时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
 
-    info!("{}", i18n::tf("start_env_name", &[&name.clone().unwrap_or_default()]));
+/// 服务的就绪状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Readiness {
+    Ready,
+    /// 仍在等待，附带原因
+    Waiting(String),
+    /// 已退出，不会再就绪
+    Failed(String),
+}
+
+/// `wait` 为 `Some` 时以后台方式启动，并在超时时间内等待所有服务就绪
+///
+/// 启动前检查 compose 文件引用的变量和发布的宿主机端口，`auto_port` 为 true 时 cfg.env 中的端口冲突会自动换用空闲端口。
+pub async fn execute(
+    detach: bool,
+    wait: Option<Duration>,
+    auto_port: bool,
+    mut config: Config,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
+    let detach = detach || wait.is_some();
+    let name = config.container_name.clone();
+
+    info!(
+        "{}",
+        i18n::tf("start_env_name", &[&name.clone().unwrap_or_default()])
+    );
+
+    let project = ComposeProject::current()?;
+
+    // 检查 cfg.env 是否定义了 compose 文件引用的变量，有错误时不启动
+    let report = config_check::check_project(&project)?;
+    for line in commands::config::issue_lines(&report) {
+        println!("{}", line);
+    }
+    commands::config::ensure_no_errors(&report)?;
+
+    let project = project.with_legacy_fallback(backend);
+    project.write_labels_override()?;
+    project.write_ports_override(&config)?;
+    if let Some(mappings) = check_ports(backend, &project, auto_port).await? {
+        config.set_port_mappings(mappings);
+        project.write_ports_override(&config)?;
+    }
 
     println!("{}", i18n::t("starting_environment").yellow());
 
-    // 使用新的实时输出方法启动环境
-    match DockerCommand::start_environment_stream(detach) {
+    // 使用实时输出的方式启动环境
+    match backend.compose_up(&project, detach) {
         Ok(()) => {
             println!("{}", i18n::t("environment_started").green());
             if detach {
@@ -27,5 +79,247 @@ pub async fn execute(detach: bool, _config: Config) -> Result<(), MddeError> {
         }
     }
 
+    if let Some(timeout) = wait {
+        println!(
+            "{}",
+            i18n::tf("waiting_for_services", &[&timeout.as_secs()]).yellow()
+        );
+        // 应用端口只用于判断主服务是否就绪
+        let app_port = match &config.app_port {
+            Some(mapping) => project
+                .primary_service(config.container_name.as_deref())?
+                .map(|service| (service, mapping.host_port())),
+            None => None,
+        };
+        wait_until_ready(backend, &project, app_port, timeout).await?;
+        println!("{}", i18n::t("all_services_ready").green());
+    }
+
     Ok(())
 }
+
+/// 检查 compose 文件发布的宿主机端口是否被其他进程或环境占用
+///
+/// cfg.env 中的单端口映射可以换用空闲端口，换用后保存并返回新的端口映射；其他端口冲突直接报错。
+/// 无法解析 compose 配置时跳过检查，由 compose 自己报告错误。
+async fn check_ports(
+    backend: &dyn ContainerBackend,
+    project: &ComposeProject,
+    auto_port: bool,
+) -> Result<Option<Vec<PortMapping>>, MddeError> {
+    let published = match backend.compose_config(project) {
+        Ok(config) => ports::published_ports(&config).unwrap_or_default(),
+        Err(e) => {
+            info!("跳过端口检查: {}", e);
+            return Ok(None);
+        }
+    };
+    if published.is_empty() {
+        return Ok(None);
+    }
+
+    println!("{}", i18n::t("checking_ports"));
+    let mut check = PortCheck::collect(backend, project);
+    let mut env_vars = Config::load_env_document().await?;
+    let mut mappings = Config::ports_from_env(&env_vars.to_map())?;
+    let mut remapped = false;
+
+    let mut conflicts = Vec::new();
+    for port in published {
+        let Some(holder) = check.holder(port.port, &port.protocol) else {
+            continue;
+        };
+
+        let managed = mappings.iter_mut().find(|mapping| {
+            mapping.host_range().is_single()
+                && mapping.host_port() == port.port
+                && mapping.protocol.as_str() == port.protocol
+        });
+        match managed {
+            Some(mapping) => {
+                let free = check.resolve(port.port, &port.protocol, &holder, auto_port)?;
+                check.reserve(free, &port.protocol);
+                *mapping = mapping.with_host_port(free);
+                println!("{}", i18n::tf("port_remapped", &[mapping]).green());
+                remapped = true;
+            }
+            None => {
+                println!("{}", i18n::tf("port_in_use", &[&port, &holder]).red());
+                conflicts.push(format!("{} ({})", port, holder));
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(MddeError::PortConflict(conflicts));
+    }
+    if !remapped {
+        return Ok(None);
+    }
+
+    Config::ports_into_env(&mut env_vars, &mappings);
+    Config::save_env_file(&env_vars).await?;
+    Ok(Some(mappings))
+}
+
+/// 等待 compose 项目中的所有服务就绪
+///
+/// 配置了 healthcheck 的服务需要变为 healthy；没有 healthcheck 的服务需要处于运行状态，
+/// `app_port` 为主服务及其应用端口，主服务没有 healthcheck 时还需要该端口可以建立 TCP 连接。
+/// 还没有任何服务容器时视为未就绪。以退出码 0 结束的一次性服务（如初始化容器）视为已完成。
+pub async fn wait_until_ready(
+    backend: &dyn ContainerBackend,
+    project: &ComposeProject,
+    app_port: Option<(String, u16)>,
+    timeout: Duration,
+) -> Result<(), MddeError> {
+    let deadline = Instant::now() + timeout;
+    let mut reported: HashMap<String, Readiness> = HashMap::new();
+
+    loop {
+        let services = backend::compose_services(backend, project)?;
+        let port_open = match &app_port {
+            Some((_, port)) => Some(port_accepts_connections(*port).await),
+            None => None,
+        };
+
+        let mut not_ready = Vec::new();
+        let mut failed = false;
+        if services.is_empty() {
+            not_ready.push(i18n::t("service_containers_missing").to_string());
+        }
+        for service in &services {
+            let state = match &app_port {
+                Some((app, port)) if *app == service.service => {
+                    readiness(service, Some(*port), port_open)
+                }
+                _ => readiness(service, None, None),
+            };
+
+            // 只在状态变化时输出，避免刷屏
+            if reported.get(&service.service) != Some(&state) {
+                match &state {
+                    Readiness::Ready => {
+                        println!("{}", i18n::tf("service_ready", &[&service.service]).green())
+                    }
+                    Readiness::Waiting(reason) => println!(
+                        "{}",
+                        i18n::tf("service_waiting", &[&service.service, reason])
+                    ),
+                    Readiness::Failed(reason) => println!(
+                        "{}",
+                        i18n::tf("service_failed", &[&service.service, reason]).red()
+                    ),
+                }
+            }
+
+            match &state {
+                Readiness::Ready => {}
+                Readiness::Waiting(reason) => {
+                    not_ready.push(format!("{} ({})", service.service, reason))
+                }
+                Readiness::Failed(reason) => {
+                    failed = true;
+                    not_ready.push(format!("{} ({})", service.service, reason));
+                }
+            }
+            reported.insert(service.service.clone(), state);
+        }
+
+        if not_ready.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        if failed || now >= deadline {
+            return Err(MddeError::ServicesNotReady(not_ready));
+        }
+
+        tokio::time::sleep(POLL_INTERVAL.min(deadline - now)).await;
+    }
+}
+
+fn readiness(service: &ServiceStatus, app_port: Option<u16>, port_open: Option<bool>) -> Readiness {
+    match (service.state.as_str(), service.health.as_deref()) {
+        ("exited", _) if service.exit_code == 0 => Readiness::Ready,
+        ("exited" | "dead", _) => Readiness::Failed(format!(
+            "{}, exit code {}",
+            service.state, service.exit_code
+        )),
+        ("running", Some("healthy")) => Readiness::Ready,
+        (_, Some(health)) => Readiness::Waiting(health.to_string()),
+        ("running", None) => match (app_port, port_open) {
+            (Some(port), Some(false)) => Readiness::Waiting(format!("port {}", port)),
+            _ => Readiness::Ready,
+        },
+        (state, None) => Readiness::Waiting(state.to_string()),
+    }
+}
+
+/// 检查本机端口是否可以建立 TCP 连接
+async fn port_accepts_connections(port: u16) -> bool {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    matches!(
+        tokio::time::timeout(POLL_INTERVAL, TcpStream::connect(addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(state: &str, health: Option<&str>) -> ServiceStatus {
+        ServiceStatus {
+            service: "app".to_string(),
+            state: state.to_string(),
+            health: health.map(str::to_string),
+            exit_code: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_readiness() {
+        assert_eq!(
+            readiness(&service("running", Some("healthy")), None, None),
+            Readiness::Ready
+        );
+        assert_eq!(
+            readiness(
+                &service("running", Some("starting")),
+                Some(8080),
+                Some(true)
+            ),
+            Readiness::Waiting("starting".to_string())
+        );
+        assert_eq!(
+            readiness(&service("running", None), Some(8080), Some(false)),
+            Readiness::Waiting("port 8080".to_string())
+        );
+        assert_eq!(
+            readiness(&service("running", None), None, None),
+            Readiness::Ready
+        );
+        assert_eq!(
+            readiness(&service("created", None), None, None),
+            Readiness::Waiting("created".to_string())
+        );
+        assert_eq!(
+            readiness(&service("exited", None), None, None),
+            Readiness::Failed("exited, exit code 1".to_string())
+        );
+
+        // 成功结束的一次性服务不阻止启动
+        let completed = ServiceStatus {
+            exit_code: 0,
+            ..service("exited", None)
+        };
+        assert_eq!(readiness(&completed, None, None), Readiness::Ready);
+        let dead = ServiceStatus {
+            exit_code: 0,
+            ..service("dead", None)
+        };
+        assert!(matches!(readiness(&dead, None, None), Readiness::Failed(_)));
+    }
+}
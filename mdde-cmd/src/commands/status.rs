@@ -1,97 +1,394 @@
+use crate::backend::{self, ContainerBackend, ContainerStats, ServiceStatus};
 use crate::cli::OutputFormat;
+use crate::compose::ComposeProject;
 use crate::config::Config;
 use crate::error::MddeError;
 use crate::i18n;
+use crate::table::Table;
+use crate::utils::format_file_size;
+use chrono::{DateTime, Local};
 use colored::*;
+use serde::Serialize;
 use serde_json::json;
-use std::process::Command;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::info;
 
-pub async fn execute(format: OutputFormat, _config: Config) -> Result<(), MddeError> {
+/// 表格的列标题
+const TABLE_HEADERS: [&str; 8] = [
+    "SERVICE",
+    "CONTAINER ID",
+    "IMAGE",
+    "STATE",
+    "HEALTH",
+    "UPTIME",
+    "EXIT CODE",
+    "PORTS",
+];
+
+/// watch 模式额外的资源使用列
+const STATS_HEADERS: [&str; 3] = ["CPU %", "MEM USAGE / LIMIT", "NET I/O"];
+
+/// watch 模式下单个服务的快照
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceSnapshot {
+    #[serde(flatten)]
+    pub status: ServiceStatus,
+    /// 资源使用情况，容器未运行时为 None
+    pub stats: Option<ContainerStats>,
+    /// 与上一次刷新相比的状态变化，如 `starting → healthy`
+    pub transition: Option<String>,
+}
+
+/// 表格中的一行
+struct TableRow {
+    cells: Vec<String>,
+    state: String,
+    highlight: bool,
+}
+
+pub async fn execute(
+    format: OutputFormat,
+    watch: bool,
+    interval: u64,
+    _config: Config,
+    backend: Arc<dyn ContainerBackend>,
+) -> Result<(), MddeError> {
     info!("查看开发环境状态");
 
-    // 检查 docker-compose.yml 文件是否存在
-    let compose_file = std::env::current_dir()?
-        .join(".mdde")
-        .join("docker-compose.yml");
-    if !compose_file.exists() {
-        return Err(MddeError::FileOperation(
-            "docker-compose.yml 文件不存在".to_string(),
-        ));
-    }
+    // 检查 docker-compose.yml 和 .mdde/cfg.env 文件是否存在
+    let project = ComposeProject::current()?.with_legacy_fallback(backend.as_ref());
 
-    // 检查 .mdde.env 文件是否存在
-    let env_file = std::env::current_dir()?.join(".mdde").join("cfg.env");
-    if !env_file.exists() {
-        return Err(MddeError::FileOperation(
-            ".mdde/cfg.env 文件不存在".to_string(),
-        ));
+    if watch {
+        return watch_services(
+            format,
+            Duration::from_secs(interval.max(1)),
+            &project,
+            &backend,
+        )
+        .await;
     }
 
-    // 获取 docker-compose 状态
-    let mut cmd = Command::new("docker-compose");
-    cmd.arg("--env-file").arg(".mdde/cfg.env");
-    cmd.arg("--file").arg(".mdde/docker-compose.yml");
-    cmd.arg("ps");
+    // 获取各服务容器的状态
+    let services = backend::compose_services(backend.as_ref(), &project)
+        .map_err(|e| MddeError::Docker(format!("获取状态失败: {}", e)))?;
 
-    let output = cmd.output()?;
+    println!("{}", render(&services, format)?);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(MddeError::Docker(format!("获取状态失败: {}", stderr)));
-    }
+    Ok(())
+}
 
-    let status_output = String::from_utf8_lossy(&output.stdout);
+/// 按指定格式渲染服务状态
+pub fn render(services: &[ServiceStatus], format: OutputFormat) -> Result<String, MddeError> {
+    let data = json!({
+        "containers": services,
+        "total": services.len()
+    });
 
     match format {
         OutputFormat::Table => {
-            println!("{}", i18n::t("environment_status").yellow());
-            println!("{}", status_output);
+            let rows = services
+                .iter()
+                .map(|service| TableRow {
+                    cells: table_row(service),
+                    state: service.state.clone(),
+                    highlight: false,
+                })
+                .collect();
+            Ok(format!(
+                "{}\n{}",
+                i18n::t("environment_status").yellow(),
+                render_table(&TABLE_HEADERS, rows)
+            ))
         }
         OutputFormat::Json => {
-            // 解析状态输出并转换为 JSON
-            let status_info = parse_status_output(&status_output);
             let json_output = json!({
                 "status": "success",
-                "data": status_info
+                "data": data
             });
-            println!("{}", serde_json::to_string_pretty(&json_output)?);
+            Ok(serde_json::to_string_pretty(&json_output)?)
         }
-        OutputFormat::Yaml => {
-            // 解析状态输出并转换为 YAML
-            let status_info = parse_status_output(&status_output);
-            let yaml_output = serde_yaml::to_string(&status_info).map_err(MddeError::Yaml)?;
-            println!("{}", yaml_output);
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
+
+/// 持续刷新服务状态，直到按下 Ctrl+C
+async fn watch_services(
+    format: OutputFormat,
+    interval: Duration,
+    project: &ComposeProject,
+    backend: &Arc<dyn ContainerBackend>,
+) -> Result<(), MddeError> {
+    let mut phases = HashMap::new();
+    // 整个 watch 过程中只监听一次，采样和渲染期间按下的 Ctrl+C 也不会丢失
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        let services = backend::compose_services(backend.as_ref(), project)
+            .map_err(|e| MddeError::Docker(format!("获取状态失败: {}", e)))?;
+        let stats = tokio::select! {
+            _ = &mut ctrl_c => break,
+            stats = collect_stats(backend, &services) => stats,
+        };
+        let snapshots = snapshot(services, stats, &phases);
+        phases = snapshots
+            .iter()
+            .map(|s| (s.status.name.clone(), phase(&s.status)))
+            .collect();
+
+        let output = render_watch(&snapshots, format, Local::now())?;
+        if format == OutputFormat::Table {
+            // 清屏并将光标移回左上角，原地刷新表格
+            print!("\x1B[2J\x1B[H");
+        }
+        println!("{}", output);
+
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            _ = tokio::time::sleep(interval) => {}
         }
     }
 
     Ok(())
 }
 
-fn parse_status_output(output: &str) -> serde_json::Value {
-    let lines: Vec<&str> = output.lines().collect();
-    let mut containers = Vec::new();
+/// 并行采样运行中容器的资源使用情况，采样失败的容器忽略
+async fn collect_stats(
+    backend: &Arc<dyn ContainerBackend>,
+    services: &[ServiceStatus],
+) -> HashMap<String, ContainerStats> {
+    // 每次采样都需要等待引擎计算 CPU 使用率，串行执行会随服务数量线性变慢
+    let handles: Vec<_> = services
+        .iter()
+        .filter(|service| service.is_running())
+        .map(|service| {
+            let backend = Arc::clone(backend);
+            let name = service.name.clone();
+            let id = service.container_id.clone();
+            tokio::task::spawn_blocking(move || (name, backend.stats(&id)))
+        })
+        .collect();
 
-    for line in lines.iter().skip(1) {
-        if line.trim().is_empty() {
-            continue;
+    let mut stats = HashMap::new();
+    for handle in handles {
+        if let Ok((name, Ok(sample))) = handle.await {
+            stats.insert(name, sample);
         }
+    }
+    stats
+}
 
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 4 {
-            let container = json!({
-                "name": parts[0],
-                "command": parts[1],
-                "service": parts[2],
-                "state": parts[3],
-                "ports": if parts.len() > 4 { parts[4] } else { "" }
-            });
-            containers.push(container);
+/// 服务的可观察状态，运行中且配置了 healthcheck 时使用健康状态
+fn phase(service: &ServiceStatus) -> String {
+    match &service.health {
+        Some(health) if service.is_running() => health.clone(),
+        _ => service.state.clone(),
+    }
+}
+
+/// 组合状态和资源使用情况，并与上一次的状态比较得出变化
+pub fn snapshot(
+    services: Vec<ServiceStatus>,
+    mut stats: HashMap<String, ContainerStats>,
+    previous: &HashMap<String, String>,
+) -> Vec<ServiceSnapshot> {
+    services
+        .into_iter()
+        .map(|status| {
+            let current = phase(&status);
+            let transition = previous
+                .get(&status.name)
+                .filter(|before| **before != current)
+                .map(|before| format!("{} → {}", before, current));
+
+            ServiceSnapshot {
+                stats: stats.remove(&status.name),
+                status,
+                transition,
+            }
+        })
+        .collect()
+}
+
+/// 渲染一次 watch 刷新的结果
+///
+/// JSON 每次输出一行（NDJSON），YAML 每次输出一个以 `---` 开头的文档，便于脚本逐条解析。
+pub fn render_watch(
+    snapshots: &[ServiceSnapshot],
+    format: OutputFormat,
+    timestamp: DateTime<Local>,
+) -> Result<String, MddeError> {
+    let data = json!({
+        "timestamp": timestamp.to_rfc3339(),
+        "containers": snapshots,
+    });
+
+    match format {
+        OutputFormat::Table => {
+            let headers: Vec<&str> = TABLE_HEADERS.into_iter().chain(STATS_HEADERS).collect();
+            let rows = snapshots
+                .iter()
+                .map(|snapshot| {
+                    let mut cells = table_row(&snapshot.status);
+                    cells.extend(stats_cells(snapshot.stats.as_ref()));
+                    if let Some(transition) = &snapshot.transition {
+                        cells[3] = format!("{} ({})", cells[3], transition);
+                    }
+                    TableRow {
+                        cells,
+                        state: snapshot.status.state.clone(),
+                        highlight: snapshot.transition.is_some(),
+                    }
+                })
+                .collect();
+
+            Ok(format!(
+                "{} {}\n{}\n\n{}",
+                i18n::t("environment_status").yellow(),
+                timestamp.format("%H:%M:%S"),
+                render_table(&headers, rows),
+                i18n::t("watch_exit_hint").dimmed()
+            ))
+        }
+        OutputFormat::Json => Ok(serde_json::to_string(&data)?),
+        OutputFormat::Yaml => Ok(format!("---\n{}", serde_yaml::to_string(&data)?)),
+    }
+}
+
+fn stats_cells(stats: Option<&ContainerStats>) -> [String; 3] {
+    match stats {
+        Some(stats) => [
+            format!("{:.2}%", stats.cpu_percent),
+            format!(
+                "{} / {}",
+                format_file_size(stats.memory_usage),
+                format_file_size(stats.memory_limit)
+            ),
+            format!(
+                "{} / {}",
+                format_file_size(stats.network_rx),
+                format_file_size(stats.network_tx)
+            ),
+        ],
+        None => ["-".to_string(), "-".to_string(), "-".to_string()],
+    }
+}
+
+/// 渲染为对齐的表格，按运行情况着色，发生状态变化的行加粗反显
+fn render_table(headers: &[&str], rows: Vec<TableRow>) -> String {
+    if rows.is_empty() {
+        return i18n::t("no_services").to_string();
+    }
+
+    let cells: Vec<&Vec<String>> = rows.iter().map(|row| &row.cells).collect();
+    let table = Table::new(headers, &cells);
+
+    let mut lines = vec![table.header()];
+    for row in &rows {
+        let line = table.row(&row.cells);
+        let line = match row.state.as_str() {
+            "running" => line.green(),
+            "exited" | "dead" => line.red(),
+            _ => line.yellow(),
+        };
+        lines.push(if row.highlight {
+            line.bold().reversed().to_string()
+        } else {
+            line.to_string()
+        });
+    }
+    lines.join("\n")
+}
+
+fn table_row(service: &ServiceStatus) -> Vec<String> {
+    let ports: Vec<String> = service.ports.iter().map(ToString::to_string).collect();
+    vec![
+        service.service.clone(),
+        service.short_id().to_string(),
+        service.image.clone(),
+        service.state.clone(),
+        service.health.clone().unwrap_or_else(|| "-".to_string()),
+        service.uptime.clone().unwrap_or_else(|| "-".to_string()),
+        if service.is_running() {
+            "-".to_string()
+        } else {
+            service.exit_code.to_string()
+        },
+        if ports.is_empty() {
+            "-".to_string()
+        } else {
+            ports.join(", ")
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, state: &str, health: Option<&str>) -> ServiceStatus {
+        ServiceStatus {
+            service: name.to_string(),
+            name: name.to_string(),
+            container_id: format!("{}-id", name),
+            state: state.to_string(),
+            health: health.map(str::to_string),
+            ..Default::default()
         }
     }
 
-    json!({
-        "containers": containers,
-        "total": containers.len()
-    })
+    #[test]
+    fn test_snapshot_transitions() {
+        let previous = HashMap::from([
+            ("web".to_string(), "starting".to_string()),
+            ("db".to_string(), "running".to_string()),
+            ("cache".to_string(), "running".to_string()),
+        ]);
+        let services = vec![
+            service("web", "running", Some("healthy")),
+            service("db", "exited", None),
+            service("cache", "running", None),
+            service("worker", "running", None),
+        ];
+        let stats = HashMap::from([(
+            "web".to_string(),
+            ContainerStats {
+                cpu_percent: 12.5,
+                ..Default::default()
+            },
+        )]);
+
+        let snapshots = snapshot(services, stats, &previous);
+        assert_eq!(
+            snapshots[0].transition.as_deref(),
+            Some("starting → healthy")
+        );
+        assert_eq!(snapshots[1].transition.as_deref(), Some("running → exited"));
+        assert_eq!(snapshots[2].transition, None);
+        assert_eq!(snapshots[3].transition, None);
+        assert!(snapshots[0].stats.is_some());
+        assert!(snapshots[1].stats.is_none());
+    }
+
+    #[test]
+    fn test_render_watch_json_is_single_line() {
+        let snapshots = snapshot(
+            vec![service("web", "running", Some("healthy"))],
+            HashMap::new(),
+            &HashMap::from([("web".to_string(), "starting".to_string())]),
+        );
+
+        let line = render_watch(&snapshots, OutputFormat::Json, Local::now()).unwrap();
+        assert!(!line.contains('\n'));
+
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["containers"][0]["service"], "web");
+        assert_eq!(value["containers"][0]["transition"], "starting → healthy");
+        assert!(value["timestamp"].is_string());
+
+        let yaml = render_watch(&snapshots, OutputFormat::Yaml, Local::now()).unwrap();
+        assert!(yaml.starts_with("---\n"));
+    }
 }
@@ -1,11 +1,18 @@
+use crate::backend::ContainerBackend;
+use crate::commands::ls;
+use crate::compose::ComposeProject;
 use crate::config::Config;
 use crate::error::MddeError;
 use crate::i18n;
+use crate::registry::Registry;
 use colored::*;
-use std::process::Command;
 use tracing::info;
 
-pub async fn execute(remove: bool, _config: Config) -> Result<(), MddeError> {
+pub async fn execute(
+    remove: bool,
+    _config: Config,
+    backend: &dyn ContainerBackend,
+) -> Result<(), MddeError> {
     let name = _config
         .container_name
         .clone()
@@ -13,50 +20,67 @@ pub async fn execute(remove: bool, _config: Config) -> Result<(), MddeError> {
 
     info!("{}", i18n::tf("stop_env_name", &[&name]));
 
-    // 检查 docker-compose.yml 文件是否存在
-    let compose_file = std::env::current_dir()?
-        .join(".mdde")
-        .join("docker-compose.yml");
-    if !compose_file.exists() {
-        return Err(MddeError::FileOperation(
-            i18n::t("docker_compose_not_exists").to_string(),
-        ));
-    }
+    // 检查 docker-compose.yml 和 .mdde/cfg.env 文件是否存在
+    let project = ComposeProject::current()?.with_legacy_fallback(backend);
+
+    println!("{}", i18n::t("stopping_environment").yellow());
 
-    // 检查 .mdde.env 文件是否存在
-    let env_file = std::env::current_dir()?.join(".mdde").join("cfg.env");
-    if !env_file.exists() {
-        return Err(MddeError::FileOperation(
-            i18n::t("mdde_cfg_env_not_exists").to_string(),
-        ));
+    match backend.compose_down(&project, remove) {
+        Ok(()) => {
+            println!("{}", i18n::t("environment_stopped").green());
+            if remove {
+                println!("{}", i18n::t("containers_volumes_removed"));
+            }
+        }
+        Err(e) => {
+            return Err(MddeError::Docker(i18n::tf("stop_failed", &[&e])));
+        }
     }
 
-    // 构建 docker-compose 命令
-    let mut cmd = Command::new("docker-compose");
-    cmd.arg("--env-file").arg(".mdde/cfg.env");
-    cmd.arg("--file").arg(".mdde/docker-compose.yml");
+    Ok(())
+}
 
-    if remove {
-        cmd.arg("down").arg("--volumes");
-    } else {
-        cmd.arg("down");
-    }
+/// 停止本机登记的所有环境，没有容器或目录已不存在的环境跳过，某个环境失败时继续停止其他环境
+pub async fn execute_all(remove: bool, backend: &dyn ContainerBackend) -> Result<(), MddeError> {
+    info!("停止所有登记的开发环境");
 
-    println!("{}", i18n::t("stopping_environment").yellow());
-    println!("{}", i18n::tf("command", &[&format!("{:?}", cmd).cyan()]));
+    let registry = Registry::load()?;
+    let mut failed = Vec::new();
 
-    // 执行命令
-    let output = cmd.output()?;
+    for entry in &registry.environments {
+        let label = format!("{} ({})", entry.environment, entry.project_dir.display());
+        let state = ls::live_state(entry, backend);
+        match state.state.as_str() {
+            "missing" => {
+                println!("{}", i18n::tf("registry_env_missing", &[&label]).yellow());
+                continue;
+            }
+            _ if state.total == 0 && state.state != "unknown" => continue,
+            _ => {}
+        }
 
-    if output.status.success() {
-        println!("{}", i18n::t("environment_stopped").green());
-        if remove {
-            println!("{}", i18n::t("containers_volumes_removed"));
+        println!(
+            "{}",
+            i18n::tf("stopping_registered_env", &[&label]).yellow()
+        );
+        match backend.compose_down(
+            &entry.compose_project().with_legacy_fallback(backend),
+            remove,
+        ) {
+            Ok(()) => println!("{}", i18n::t("environment_stopped").green()),
+            Err(e) => {
+                println!("{}", i18n::tf("stop_failed", &[&e]).red());
+                failed.push(label);
+            }
         }
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(MddeError::Docker(i18n::tf("stop_failed", &[&stderr])));
     }
 
-    Ok(())
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(MddeError::Docker(i18n::tf(
+            "stop_failed",
+            &[&failed.join(", ")],
+        )))
+    }
 }
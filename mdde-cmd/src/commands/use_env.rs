@@ -0,0 +1,24 @@
+use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
+use colored::*;
+use tracing::info;
+
+/// 将项目的默认环境切换为 `name`，之后未指定 `--env` 的命令都使用该环境
+pub async fn execute(name: String) -> Result<(), MddeError> {
+    info!("切换默认环境: {}", name);
+
+    project::validate_env_name(&name)?;
+    let root = project::root()?;
+    if !project::environments(&root).contains(&name) {
+        return Err(MddeError::InvalidArgument(i18n::tf(
+            "env_not_found",
+            &[&name],
+        )));
+    }
+
+    project::set_default_environment(&root, &name)?;
+    println!("{}", i18n::tf("default_env_set", &[&name]).green());
+
+    Ok(())
+}
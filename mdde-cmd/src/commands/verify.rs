@@ -0,0 +1,135 @@
+use crate::cli::OutputFormat;
+use crate::compose::ComposeProject;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
+use crate::table::Table;
+use crate::template_integrity::{FileCheck, FileStatus, TemplateLock, LOCK_FILE};
+use colored::*;
+use serde_json::json;
+use tracing::info;
+
+/// 按创建环境时记录的摘要检查环境目录中的模板文件，有文件被修改或缺失时返回错误
+pub async fn execute(format: OutputFormat) -> Result<(), MddeError> {
+    let project = ComposeProject::current()?;
+    let env_dir = project::env_dir_in(&project.root, &project.environment);
+    info!("校验模板文件: {}", env_dir.display());
+
+    let lock = TemplateLock::load(&env_dir)?.ok_or_else(|| {
+        MddeError::Config(i18n::tf(
+            "verify_no_lock",
+            &[&env_dir.join(LOCK_FILE).display()],
+        ))
+    })?;
+    let checks = lock.check(&env_dir)?;
+    println!("{}", render(&lock, &checks, format)?);
+
+    let failed = failed_count(&checks);
+    if failed > 0 {
+        return Err(MddeError::IntegrityCheck(i18n::tf(
+            "verify_failed",
+            &[&failed],
+        )));
+    }
+    Ok(())
+}
+
+/// 被修改或缺失的文件数量
+fn failed_count(checks: &[FileCheck]) -> usize {
+    checks
+        .iter()
+        .filter(|check| check.status != FileStatus::Ok)
+        .count()
+}
+
+/// 按指定格式输出检查结果，表格中的摘要只显示前 12 位，JSON 中有文件被修改或缺失时状态为 `failed`
+pub fn render(
+    lock: &TemplateLock,
+    checks: &[FileCheck],
+    format: OutputFormat,
+) -> Result<String, MddeError> {
+    let failed = failed_count(checks);
+    let data = json!({
+        "template": lock.template,
+        "source": lock.source,
+        "signed": lock.signed,
+        "created_at": lock.created_at,
+        "variables": lock.variables,
+        "failed": failed,
+        "files": checks
+    });
+
+    match format {
+        OutputFormat::Table => {
+            let headers = ["FILE", "STATUS", "SHA256"];
+            let rows: Vec<[String; 3]> = checks
+                .iter()
+                .map(|check| {
+                    [
+                        check.target.clone(),
+                        check.status.as_str().to_string(),
+                        check.expected.chars().take(12).collect(),
+                    ]
+                })
+                .collect();
+
+            let table = Table::new(&headers, &rows);
+            let mut lines = vec![i18n::tf("verify_template", &[&lock.template, &lock.source])];
+            if lock.signed {
+                lines.push(i18n::t("verify_signed").to_string());
+            }
+            lines.push(table.header());
+            lines.extend(rows.iter().zip(checks).map(|(row, check)| {
+                let line = table.row(row);
+                match check.status {
+                    FileStatus::Ok => line,
+                    FileStatus::Modified | FileStatus::Missing => line.red().to_string(),
+                }
+            }));
+            Ok(lines.join("\n"))
+        }
+        OutputFormat::Json => {
+            let json_output = json!({
+                "status": if failed > 0 { "failed" } else { "success" },
+                "data": data
+            });
+            Ok(serde_json::to_string_pretty(&json_output)?)
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&data).map_err(MddeError::Yaml),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_json_reports_failures() {
+        let lock = TemplateLock {
+            template: "node22".to_string(),
+            source: "default".to_string(),
+            signed: false,
+            created_at: chrono::Utc::now(),
+            files: Vec::new(),
+            variables: Default::default(),
+        };
+        let check = |target: &str, status| FileCheck {
+            target: target.to_string(),
+            status,
+            expected: "0".repeat(64),
+            actual: None,
+        };
+        let status = |checks: &[FileCheck]| {
+            let output: serde_json::Value =
+                serde_json::from_str(&render(&lock, checks, OutputFormat::Json).unwrap()).unwrap();
+            (output["status"].clone(), output["data"]["failed"].clone())
+        };
+
+        let mut checks = vec![check("docker-compose.yml", FileStatus::Ok)];
+        assert_eq!(status(&checks), (json!("success"), json!(0)));
+
+        checks.push(check("Dockerfile", FileStatus::Modified));
+        checks.push(check(".env", FileStatus::Missing));
+        assert_eq!(status(&checks), (json!("failed"), json!(2)));
+    }
+}
@@ -0,0 +1,593 @@
+use crate::backend::ContainerBackend;
+use crate::config::Config;
+use crate::container_engine::{self, ContainerEngine};
+use crate::docker::DockerError;
+use crate::env_file::EnvFile;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::port_mapping::PortMapping;
+use crate::project;
+use crate::template_integrity::sha256_hex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+/// mdde 资源的标签，值为 `true`，`clean` 默认只清理带有该标签的资源
+pub const MANAGED_LABEL: &str = "mdde.managed";
+/// 资源所属项目的根目录
+pub const PROJECT_LABEL: &str = "mdde.project";
+/// 资源所属的环境名称
+pub const ENVIRONMENT_LABEL: &str = "mdde.environment";
+/// 创建环境时使用的模板
+pub const TEMPLATE_LABEL: &str = "mdde.template";
+/// 旧版本中 default 环境的 compose 项目名称，即 compose 根据 `.mdde` 目录推导出的默认名称
+pub const LEGACY_PROJECT_NAME: &str = "mdde";
+
+/// mdde 管理的 compose 项目，对应环境目录下的 compose 文件和环境变量文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComposeProject {
+    /// 项目根目录，compose 命令在该目录下执行
+    pub root: PathBuf,
+    /// 环境名称，见 [`project::environment`]
+    pub environment: String,
+    pub compose_file: PathBuf,
+    pub env_file: PathBuf,
+    /// 用户自己维护的 compose 覆盖文件，存在时一并传给 compose
+    pub override_file: PathBuf,
+    /// 为容器、网络、卷和构建的镜像添加 mdde 标签的 compose 覆盖文件，存在时一并传给 compose
+    pub labels_file: PathBuf,
+    /// 由 cfg.env 中的端口映射生成的 compose 覆盖文件，存在时一并传给 compose
+    pub ports_file: PathBuf,
+    /// 沿用旧版本的项目名称 [`LEGACY_PROJECT_NAME`]，见 [`ComposeProject::with_legacy_fallback`]
+    pub legacy_name: bool,
+}
+
+impl ComposeProject {
+    /// 根据项目根目录构建，使用 `.mdde/` 下的 `default` 环境
+    pub fn new(root: &Path) -> Self {
+        Self::in_environment(root, project::DEFAULT_ENV)
+    }
+
+    /// 根据项目根目录和环境名称构建
+    pub fn in_environment(root: &Path, environment: &str) -> Self {
+        let env_dir = project::env_dir_in(root, environment);
+        Self {
+            root: root.to_path_buf(),
+            environment: environment.to_string(),
+            compose_file: env_dir.join("docker-compose.yml"),
+            env_file: env_dir.join("cfg.env"),
+            override_file: env_dir.join("docker-compose.override.yml"),
+            labels_file: env_dir.join("docker-compose.labels.yml"),
+            ports_file: env_dir.join("docker-compose.ports.yml"),
+            legacy_name: false,
+        }
+    }
+
+    /// 当前项目（见 [`project::root`]）中当前环境（见 [`project::environment`]）的文件，不检查是否存在
+    pub fn active() -> Result<Self, MddeError> {
+        Ok(Self::in_environment(
+            &project::root()?,
+            &project::environment()?,
+        ))
+    }
+
+    /// 当前项目的当前环境，并检查必需的文件是否存在
+    pub fn current() -> Result<Self, MddeError> {
+        let name = project::environment()?;
+        let project = Self::active()?;
+        if name != project::DEFAULT_ENV && !project.compose_file.exists() {
+            return Err(MddeError::InvalidArgument(i18n::tf(
+                "env_not_found",
+                &[&name],
+            )));
+        }
+        project.ensure_exists()?;
+        Ok(project)
+    }
+
+    /// compose 项目名称 `mdde-<根目录哈希>-<环境>`
+    ///
+    /// 不使用 compose 默认的目录名，不同检出目录中同名的环境不会共用容器、网络和卷。
+    /// 环境名称只包含小写字母、数字、`-` 和 `_`（见 [`project::validate_env_name`]），可以直接用在项目名称中。
+    pub fn name(&self) -> String {
+        if self.legacy_name {
+            return LEGACY_PROJECT_NAME.to_string();
+        }
+        let hash = sha256_hex(self.root.to_string_lossy().as_bytes());
+        format!("mdde-{}-{}", &hash[..12], self.environment)
+    }
+
+    /// default 环境还有旧版本创建的容器时沿用旧的项目名称，升级后仍然可以停止、查看和销毁这些容器
+    ///
+    /// 旧版本的所有检出目录共用同一个项目名称，按 compose 记录的项目目录（环境目录）区分；查询失败时使用新的名称。
+    pub fn with_legacy_fallback(mut self, backend: &dyn ContainerBackend) -> Self {
+        if self.environment == project::DEFAULT_ENV {
+            let env_dir = self.compose_file.parent().unwrap_or(&self.root);
+            self.legacy_name = backend
+                .compose_project_exists(LEGACY_PROJECT_NAME, env_dir)
+                .unwrap_or(false);
+        }
+        self
+    }
+
+    /// 传给 compose 的文件，按合并顺序排列：compose 文件和已存在的覆盖文件
+    pub fn files(&self) -> Vec<&Path> {
+        let overrides = [&self.override_file, &self.labels_file, &self.ports_file]
+            .into_iter()
+            .filter(|file| file.exists());
+        std::iter::once(&self.compose_file)
+            .chain(overrides)
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    /// 检查 compose 文件和环境变量文件是否存在
+    pub fn ensure_exists(&self) -> Result<(), MddeError> {
+        if !self.compose_file.exists() {
+            return Err(MddeError::FileOperation(
+                i18n::t("docker_compose_not_exists").to_string(),
+            ));
+        }
+
+        if !self.env_file.exists() {
+            return Err(MddeError::FileOperation(
+                i18n::t("mdde_cfg_env_not_exists").to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 根据配置的端口映射生成端口覆盖文件，没有需要发布的端口时删除该文件
+    pub fn write_ports_override(&self, config: &Config) -> Result<(), MddeError> {
+        match self.ports_override_content(config)? {
+            Some(content) => std::fs::write(&self.ports_file, content)?,
+            None => {
+                if self.ports_file.exists() {
+                    std::fs::remove_file(&self.ports_file)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 生成标签覆盖文件
+    pub fn write_labels_override(&self) -> Result<(), MddeError> {
+        std::fs::write(&self.labels_file, self.labels_override_content()?)?;
+        Ok(())
+    }
+
+    /// 资源上的 mdde 标签，模板记录在 cfg.env 的 `template` 中
+    pub fn labels(&self) -> Mapping {
+        let template = std::fs::read_to_string(&self.env_file)
+            .ok()
+            .and_then(|content| EnvFile::parse(&content).ok())
+            .and_then(|env| env.get("template").map(str::to_string));
+
+        let mut labels = Mapping::new();
+        labels.insert(MANAGED_LABEL.into(), "true".into());
+        labels.insert(
+            PROJECT_LABEL.into(),
+            self.root.to_string_lossy().into_owned().into(),
+        );
+        labels.insert(ENVIRONMENT_LABEL.into(), self.environment.as_str().into());
+        if let Some(template) = template {
+            labels.insert(TEMPLATE_LABEL.into(), template.into());
+        }
+        labels
+    }
+
+    /// 资源的标签是否表明它属于这个环境
+    pub fn owns(&self, labels: &HashMap<String, String>) -> bool {
+        labels.get(PROJECT_LABEL).map(String::as_str) == Some(&*self.root.to_string_lossy())
+            && labels.get(ENVIRONMENT_LABEL) == Some(&self.environment)
+    }
+
+    /// 标签覆盖文件的内容
+    ///
+    /// 标签添加到所有服务的容器、有 `build` 的服务构建的镜像，以及非外部的命名卷和网络（包括默认网络）。
+    pub fn labels_override_content(&self) -> Result<String, MddeError> {
+        let compose: Value = serde_yaml::from_str(&std::fs::read_to_string(&self.compose_file)?)?;
+        let labels = Value::Mapping(self.labels());
+        let with_labels = || {
+            let mut config = Mapping::new();
+            config.insert("labels".into(), labels.clone());
+            Value::Mapping(config)
+        };
+        let keys = |section: &str| -> Vec<Value> {
+            compose
+                .get(section)
+                .and_then(Value::as_mapping)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter(|(_, item)| {
+                            item.get("external").and_then(Value::as_bool) != Some(true)
+                        })
+                        .map(|(key, _)| key.clone())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut services = Mapping::new();
+        if let Some(items) = compose.get("services").and_then(Value::as_mapping) {
+            for (name, service) in items {
+                let mut config = Mapping::new();
+                config.insert("labels".into(), labels.clone());
+                if service.get("build").is_some() {
+                    config.insert("build".into(), with_labels());
+                }
+                services.insert(name.clone(), Value::Mapping(config));
+            }
+        }
+
+        let mut root = Mapping::new();
+        root.insert("services".into(), Value::Mapping(services));
+        let volumes: Mapping = keys("volumes")
+            .into_iter()
+            .map(|key| (key, with_labels()))
+            .collect();
+        if !volumes.is_empty() {
+            root.insert("volumes".into(), Value::Mapping(volumes));
+        }
+        let mut networks: Mapping = keys("networks")
+            .into_iter()
+            .map(|key| (key, with_labels()))
+            .collect();
+        // 默认网络没有声明时也会创建，声明过的已经在上面处理（外部网络不能添加标签）
+        let declares_default = compose
+            .get("networks")
+            .and_then(|networks| networks.get("default"))
+            .is_some();
+        if !declares_default {
+            networks.insert("default".into(), with_labels());
+        }
+        root.insert("networks".into(), Value::Mapping(networks));
+
+        Ok(format!(
+            "# 由 mdde 生成，为环境的资源添加标签，请勿手动修改\n{}",
+            serde_yaml::to_string(&root)?
+        ))
+    }
+
+    /// 端口覆盖文件的内容，没有需要发布的端口时为 `None`
+    ///
+    /// 端口发布到主服务上：`container_name` 引用了 `${container_name}` 或与配置相同的服务，
+    /// 找不到时使用第一个服务。模板已经引用 `${app_port}` 时应用端口不再重复发布。
+    pub fn ports_override_content(&self, config: &Config) -> Result<Option<String>, MddeError> {
+        let content = std::fs::read_to_string(&self.compose_file)?;
+        let mappings = if content.contains("app_port}") || content.contains("$app_port") {
+            config.ports.clone()
+        } else {
+            config.port_mappings()
+        };
+
+        match primary_service(&content, config.container_name.as_deref()) {
+            Some(service) if !mappings.is_empty() => Ok(Some(ports_override(&service, &mappings)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// compose 文件中的主服务，应用端口发布在该服务上
+    pub fn primary_service(
+        &self,
+        container_name: Option<&str>,
+    ) -> Result<Option<String>, MddeError> {
+        let content = std::fs::read_to_string(&self.compose_file)?;
+        Ok(primary_service(&content, container_name))
+    }
+}
+
+/// 查找 compose 文件中的主服务
+fn primary_service(compose: &str, container_name: Option<&str>) -> Option<String> {
+    let compose: Value = serde_yaml::from_str(compose).ok()?;
+    let services = compose.get("services")?.as_mapping()?;
+
+    let is_primary = |service: &Value| {
+        service
+            .get("container_name")
+            .and_then(Value::as_str)
+            .is_some_and(|name| name.contains("container_name") || Some(name) == container_name)
+    };
+    services
+        .iter()
+        .find(|(_, service)| is_primary(service))
+        .or_else(|| services.iter().next())
+        .and_then(|(name, _)| name.as_str().map(str::to_string))
+}
+
+/// 生成只包含端口的 compose 覆盖文件内容
+fn ports_override(service: &str, mappings: &[PortMapping]) -> Result<String, MddeError> {
+    let ports: Vec<Value> = mappings
+        .iter()
+        .map(|mapping| Value::String(mapping.to_compose()))
+        .collect();
+    let mut service_config = Mapping::new();
+    service_config.insert("ports".into(), Value::Sequence(ports));
+    let mut services = Mapping::new();
+    services.insert(service.into(), Value::Mapping(service_config));
+    let mut root = Mapping::new();
+    root.insert("services".into(), Value::Mapping(services));
+
+    Ok(format!(
+        "# 由 mdde 根据 cfg.env 中的 app_port/ports 生成，请勿手动修改\n{}",
+        serde_yaml::to_string(&root)?
+    ))
+}
+
+/// compose 的调用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComposeFlavor {
+    /// Docker CLI 插件 `docker compose`（Compose v2）
+    Plugin,
+    /// 独立的 `docker-compose` 可执行文件（Compose v1）
+    Standalone,
+}
+
+impl ComposeFlavor {
+    /// 检测时的优先顺序
+    const PROBE_ORDER: [ComposeFlavor; 2] = [ComposeFlavor::Plugin, ComposeFlavor::Standalone];
+
+    /// 在指定引擎下的可执行文件名
+    pub fn program(self, engine: ContainerEngine) -> &'static str {
+        match self {
+            ComposeFlavor::Plugin => engine.program(),
+            ComposeFlavor::Standalone => engine.standalone_compose(),
+        }
+    }
+
+    /// 构建 compose 命令，后续参数直接追加即可
+    pub fn command(self, engine: ContainerEngine) -> Command {
+        let mut cmd = Command::new(self.program(engine));
+        if self == ComposeFlavor::Plugin {
+            cmd.arg("compose");
+        }
+        cmd
+    }
+
+    /// 获取版本信息，不可用时返回 `None`
+    pub fn version(self, engine: ContainerEngine) -> Option<String> {
+        let output = self.command(engine).arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Some(version).filter(|v| !v.is_empty())
+    }
+
+    /// 在指定引擎下的完整命令名，如 `docker compose`、`podman-compose`
+    pub fn describe(self, engine: ContainerEngine) -> String {
+        match self {
+            ComposeFlavor::Plugin => format!("{} compose", engine.program()),
+            ComposeFlavor::Standalone => engine.standalone_compose().to_string(),
+        }
+    }
+
+    /// 配置文件中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ComposeFlavor::Plugin => "plugin",
+            ComposeFlavor::Standalone => "standalone",
+        }
+    }
+}
+
+impl FromStr for ComposeFlavor {
+    type Err = MddeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "plugin" | "v2" | "docker compose" | "podman compose" => Ok(ComposeFlavor::Plugin),
+            "standalone" | "v1" | "docker-compose" | "podman-compose" => {
+                Ok(ComposeFlavor::Standalone)
+            }
+            other => Err(MddeError::Config(i18n::tf(
+                "invalid_compose_flavor",
+                &[&other],
+            ))),
+        }
+    }
+}
+
+/// 配置中指定的 compose 调用方式
+static PREFERRED_FLAVOR: RwLock<Option<ComposeFlavor>> = RwLock::new(None);
+
+/// 指定 compose 调用方式，`None` 表示自动检测
+pub fn set_preferred_flavor(flavor: Option<ComposeFlavor>) {
+    if let Ok(mut preferred) = PREFERRED_FLAVOR.write() {
+        *preferred = flavor;
+    }
+}
+
+/// 探测指定引擎下可用的 compose，优先使用插件形式，结果在进程内按引擎缓存
+pub fn detect_flavor(engine: ContainerEngine) -> Option<ComposeFlavor> {
+    static DOCKER: OnceLock<Option<ComposeFlavor>> = OnceLock::new();
+    static PODMAN: OnceLock<Option<ComposeFlavor>> = OnceLock::new();
+
+    let detected = match engine {
+        ContainerEngine::Docker => &DOCKER,
+        ContainerEngine::Podman => &PODMAN,
+    };
+    *detected.get_or_init(|| {
+        ComposeFlavor::PROBE_ORDER
+            .into_iter()
+            .find(|flavor| flavor.version(engine).is_some())
+    })
+}
+
+/// 获取当前引擎下要使用的 compose 调用方式：优先使用配置，否则自动检测
+pub fn flavor() -> Result<ComposeFlavor, DockerError> {
+    let engine = container_engine::current();
+    let preferred = PREFERRED_FLAVOR.read().ok().and_then(|p| *p);
+    preferred.or_else(|| detect_flavor(engine)).ok_or_else(|| {
+        DockerError::CommandFailed(i18n::tf(
+            "compose_not_found",
+            &[
+                &ComposeFlavor::Plugin.describe(engine),
+                &ComposeFlavor::Standalone.describe(engine),
+            ],
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_ports_override() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(".mdde")).unwrap();
+        let project = ComposeProject::new(dir.path());
+        std::fs::write(
+            &project.compose_file,
+            "services:\n  db:\n    image: postgres\n  app:\n    image: node\n    container_name: ${container_name}\n    ports:\n      - \"${app_port}\"\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.set_port_mappings(vec![
+            "8080:80".parse().unwrap(),
+            "127.0.0.1:5000:5000".parse().unwrap(),
+            "5353:53/udp".parse().unwrap(),
+        ]);
+        project.write_ports_override(&config).unwrap();
+
+        // 模板已经引用 app_port，只发布其他端口
+        let content = std::fs::read_to_string(&project.ports_file).unwrap();
+        let value: Value = serde_yaml::from_str(&content).unwrap();
+        let ports: Vec<&str> = value["services"]["app"]["ports"]
+            .as_sequence()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert_eq!(ports, vec!["127.0.0.1:5000:5000/tcp", "5353:53/udp"]);
+
+        // 没有其他端口时删除覆盖文件
+        config.set_port_mappings(vec!["8080:80".parse().unwrap()]);
+        project.write_ports_override(&config).unwrap();
+        assert!(!project.ports_file.exists());
+    }
+
+    #[test]
+    fn test_write_labels_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = ComposeProject::in_environment(dir.path(), "test");
+        std::fs::create_dir_all(project.compose_file.parent().unwrap()).unwrap();
+        std::fs::write(
+            &project.compose_file,
+            "services:\n  app:\n    build: .\n  db:\n    image: postgres\nvolumes:\n  data:\n  shared:\n    external: true\nnetworks:\n  backend:\n",
+        )
+        .unwrap();
+        std::fs::write(&project.env_file, "template=node22\n").unwrap();
+
+        project.write_labels_override().unwrap();
+        assert!(project.files().contains(&project.labels_file.as_path()));
+
+        let content = std::fs::read_to_string(&project.labels_file).unwrap();
+        let value: Value = serde_yaml::from_str(&content).unwrap();
+        let app = &value["services"]["app"];
+        assert_eq!(app["labels"][MANAGED_LABEL], "true");
+        assert_eq!(app["labels"][ENVIRONMENT_LABEL], "test");
+        assert_eq!(app["labels"][TEMPLATE_LABEL], "node22");
+        assert_eq!(
+            app["labels"][PROJECT_LABEL],
+            dir.path().to_string_lossy().as_ref()
+        );
+        assert_eq!(app["build"]["labels"][MANAGED_LABEL], "true");
+        assert!(value["services"]["db"].get("build").is_none());
+
+        // 外部卷不能添加标签，默认网络没有声明时也添加
+        assert_eq!(value["volumes"]["data"]["labels"][MANAGED_LABEL], "true");
+        assert!(value["volumes"].get("shared").is_none());
+        assert_eq!(
+            value["networks"]["backend"]["labels"][MANAGED_LABEL],
+            "true"
+        );
+        assert_eq!(
+            value["networks"]["default"]["labels"][MANAGED_LABEL],
+            "true"
+        );
+    }
+
+    #[test]
+    fn test_parse_compose_flavor() {
+        assert_eq!(
+            "plugin".parse::<ComposeFlavor>().unwrap(),
+            ComposeFlavor::Plugin
+        );
+        assert_eq!(
+            "V2".parse::<ComposeFlavor>().unwrap(),
+            ComposeFlavor::Plugin
+        );
+        assert_eq!(
+            "docker-compose".parse::<ComposeFlavor>().unwrap(),
+            ComposeFlavor::Standalone
+        );
+        assert_eq!(
+            "podman-compose".parse::<ComposeFlavor>().unwrap(),
+            ComposeFlavor::Standalone
+        );
+        assert!("podman".parse::<ComposeFlavor>().is_err());
+
+        assert_eq!(
+            ComposeFlavor::Plugin.describe(ContainerEngine::Docker),
+            "docker compose"
+        );
+        assert_eq!(
+            ComposeFlavor::Standalone.describe(ContainerEngine::Podman),
+            "podman-compose"
+        );
+        assert_eq!(
+            ComposeFlavor::Standalone
+                .as_str()
+                .parse::<ComposeFlavor>()
+                .unwrap(),
+            ComposeFlavor::Standalone
+        );
+    }
+
+    #[test]
+    fn test_compose_command_args() {
+        let cmd = ComposeFlavor::Plugin.command(ContainerEngine::Docker);
+        assert_eq!(cmd.get_program(), "docker");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), vec!["compose"]);
+
+        let cmd = ComposeFlavor::Plugin.command(ContainerEngine::Podman);
+        assert_eq!(cmd.get_program(), "podman");
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), vec!["compose"]);
+
+        let cmd = ComposeFlavor::Standalone.command(ContainerEngine::Docker);
+        assert_eq!(cmd.get_program(), "docker-compose");
+        assert_eq!(cmd.get_args().count(), 0);
+    }
+
+    #[test]
+    fn test_project_name_is_unique_per_checkout() {
+        let api = ComposeProject::in_environment(Path::new("/work/a/api"), "dev");
+        let other = ComposeProject::in_environment(Path::new("/work/b/api"), "dev");
+        assert_ne!(api.name(), other.name());
+        assert_eq!(api.name(), api.clone().name());
+        assert!(api.name().starts_with("mdde-"));
+        assert!(api.name().ends_with("-dev"));
+        assert!(api
+            .name()
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+
+        // 旧版本创建的 default 环境沿用 compose 默认的项目名称
+        let legacy = ComposeProject {
+            legacy_name: true,
+            ..ComposeProject::new(Path::new("/work/a/api"))
+        };
+        assert_eq!(legacy.name(), LEGACY_PROJECT_NAME);
+    }
+}
@@ -1,25 +1,148 @@
+use crate::compose::ComposeFlavor;
+use crate::container_engine::ContainerEngine;
+use crate::env_file::EnvFile;
 use crate::error::MddeError;
+use crate::i18n;
+use crate::port_mapping::PortMapping;
+use crate::project;
+use crate::template_integrity::TrustedKey;
+use crate::template_source::TemplateSource;
 use crate::utils::DEFAULT_HOST;
+use colored::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub host: String,
+    /// 其他模板来源，查找模板时排在 `host` 之前，保存时以逗号分隔
+    pub sources: Vec<TemplateSource>,
+    /// 信任的模板签名公钥，设置后只使用经其中一个公钥签名的模板
+    pub trusted_keys: Vec<TrustedKey>,
     pub container_name: Option<String>,
-    pub app_port: Option<u16>,
+    /// 应用端口映射，模板中以 `${app_port}` 引用
+    pub app_port: Option<PortMapping>,
+    /// 其他端口映射，保存在 cfg.env 的 `ports` 中，以逗号分隔
+    pub ports: Vec<PortMapping>,
     pub workspace: Option<PathBuf>,
+    /// 强制使用的 compose 调用方式，未设置时自动检测
+    pub compose: Option<ComposeFlavor>,
+    /// 容器引擎，未设置时自动检测
+    pub engine: Option<ContainerEngine>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             host: DEFAULT_HOST.to_string(),
+            sources: Vec::new(),
+            trusted_keys: Vec::new(),
             container_name: None,
             app_port: None,
+            ports: Vec::new(),
             workspace: None,
+            compose: None,
+            engine: None,
+        }
+    }
+}
+
+/// 所有配置项
+pub const CONFIG_KEYS: [&str; 9] = [
+    "host",
+    "sources",
+    "trusted_keys",
+    "container_name",
+    "app_port",
+    "ports",
+    "workspace",
+    "compose",
+    "engine",
+];
+
+/// 只能在用户配置文件或命令行中设置的配置项，项目 `cfg.env` 和 `MDDE_*` 环境变量中的值被忽略，
+/// 避免仓库中的文件或外部环境替换、清空信任的公钥
+pub const USER_ONLY_KEYS: [&str; 1] = ["trusted_keys"];
+
+/// mdde 记录在 cfg.env 中、但不属于配置项的键，如创建环境时使用的模板
+pub const METADATA_KEYS: [&str; 1] = ["template"];
+
+/// 环境变量覆盖配置时使用的前缀，如 `MDDE_HOST`
+const ENV_PREFIX: &str = "MDDE_";
+
+/// 配置值的来源，按优先级从低到高排列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// 内置默认值
+    Default,
+    /// 用户级配置文件
+    User(PathBuf),
+    /// 项目的 `.mdde/cfg.env`
+    Project(PathBuf),
+    /// `MDDE_*` 环境变量，附带变量名
+    Environment(String),
+    /// 命令行 `--config key=value`
+    Cli,
+}
+
+impl ConfigSource {
+    /// 配置层的名称
+    pub fn layer(&self) -> &'static str {
+        match self {
+            ConfigSource::Default => "default",
+            ConfigSource::User(_) => "user",
+            ConfigSource::Project(_) => "project",
+            ConfigSource::Environment(_) => "env",
+            ConfigSource::Cli => "cli",
+        }
+    }
+
+    /// 配置所在的文件或环境变量
+    pub fn location(&self) -> Option<String> {
+        match self {
+            ConfigSource::User(path) | ConfigSource::Project(path) => {
+                Some(path.display().to_string())
+            }
+            ConfigSource::Environment(name) => Some(name.clone()),
+            ConfigSource::Default | ConfigSource::Cli => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = self.location().unwrap_or_default();
+        match self {
+            ConfigSource::Default => write!(f, "{}", i18n::t("config_origin_default")),
+            ConfigSource::User(_) => write!(f, "{}", i18n::tf("config_origin_user", &[&location])),
+            ConfigSource::Project(_) => {
+                write!(f, "{}", i18n::tf("config_origin_project", &[&location]))
+            }
+            ConfigSource::Environment(_) => {
+                write!(f, "{}", i18n::tf("config_origin_env", &[&location]))
+            }
+            ConfigSource::Cli => write!(f, "{}", i18n::t("config_origin_cli")),
+        }
+    }
+}
+
+/// 一个配置项的值及其来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigValue {
+    pub key: String,
+    pub value: String,
+    pub source: ConfigSource,
+}
+
+impl ConfigValue {
+    pub fn new(key: &str, value: &str, source: ConfigSource) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.to_string(),
+            source,
         }
     }
 }
@@ -27,47 +150,214 @@ impl Default for Config {
 impl Config {
     /// 加载配置文件
     pub async fn load() -> Result<Self, MddeError> {
-        // 从 .mdde.env 文件加载配置
-        let env_vars = Self::load_env_file().await?;
+        Self::load_with(&[]).await
+    }
+
+    /// 加载配置，并应用命令行中的覆盖项
+    pub async fn load_with(overrides: &[(String, String)]) -> Result<Self, MddeError> {
+        Ok(Self::load_layers(overrides).await?.0)
+    }
 
+    /// 加载配置，`cfg.env` 有语法错误时只打印警告并跳过出错的行，
+    /// 用于查看和编辑 `cfg.env` 的命令，使它们能修复有错误的文件
+    pub async fn load_lenient(overrides: &[(String, String)]) -> Result<Self, MddeError> {
+        let (_, errors) = Self::load_env_document_lenient().await?;
+        let env_path = Self::get_env_file_path()?;
+        for error in errors {
+            eprintln!(
+                "{}",
+                i18n::tf("env_file_warning", &[&with_path(&env_path, error)]).yellow()
+            );
+        }
+        Ok(Self::load_layers_with(overrides, true).await?.0)
+    }
+
+    /// 按优先级合并各层配置，同时返回每个生效配置项的来源
+    ///
+    /// 优先级从低到高：内置默认值、用户配置文件、项目 `.mdde/cfg.env`、`MDDE_*` 环境变量、命令行。
+    pub async fn load_layers(
+        overrides: &[(String, String)],
+    ) -> Result<(Self, Vec<ConfigValue>), MddeError> {
+        Self::load_layers_with(overrides, false).await
+    }
+
+    /// 同 [`Config::load_layers`]，`lenient` 为 true 时跳过 `cfg.env` 中有错误的行
+    pub async fn load_layers_with(
+        overrides: &[(String, String)],
+        lenient: bool,
+    ) -> Result<(Self, Vec<ConfigValue>), MddeError> {
+        let mut entries = vec![ConfigValue::new(
+            "host",
+            DEFAULT_HOST,
+            ConfigSource::Default,
+        )];
+
+        if let Some(path) = Self::user_config_path() {
+            if path.exists() {
+                let content = fs::read_to_string(&path).await?;
+                let source = ConfigSource::User(path.clone());
+                for (key, value) in
+                    Self::parse_user_config(&content).map_err(|e| with_path(&path, e))?
+                {
+                    entries.push(ConfigValue::new(&key, &value, source.clone()));
+                }
+            }
+        }
+
+        let env_path = Self::get_env_file_path()?;
+        let env_file = if lenient {
+            Self::load_env_document_lenient().await?.0
+        } else {
+            Self::load_env_document().await?
+        };
+        for (key, value) in env_file.to_map() {
+            entries.push(ConfigValue::new(
+                &key,
+                &value,
+                ConfigSource::Project(env_path.clone()),
+            ));
+        }
+
+        entries.extend(Self::env_entries(std::env::vars()));
+        entries.extend(
+            overrides
+                .iter()
+                .map(|(key, value)| ConfigValue::new(key, value, ConfigSource::Cli)),
+        );
+
+        Self::resolve(entries)
+    }
+
+    /// 按顺序合并配置项，后面的覆盖前面的，忽略未知的键和来自项目或环境变量的 [`USER_ONLY_KEYS`]
+    pub fn resolve(entries: Vec<ConfigValue>) -> Result<(Self, Vec<ConfigValue>), MddeError> {
+        let mut effective: Vec<ConfigValue> = Vec::new();
+        for entry in entries {
+            if !CONFIG_KEYS.contains(&entry.key.as_str()) {
+                continue;
+            }
+            if USER_ONLY_KEYS.contains(&entry.key.as_str())
+                && matches!(
+                    entry.source,
+                    ConfigSource::Project(_) | ConfigSource::Environment(_)
+                )
+            {
+                continue;
+            }
+            match effective.iter_mut().find(|e| e.key == entry.key) {
+                Some(existing) => *existing = entry,
+                None => effective.push(entry),
+            }
+        }
+        effective.sort_by_key(|e| CONFIG_KEYS.iter().position(|key| *key == e.key));
+
+        let values: HashMap<String, String> = effective
+            .iter()
+            .map(|e| (e.key.clone(), e.value.clone()))
+            .collect();
+        Ok((Self::from_values(&values)?, effective))
+    }
+
+    /// 从键值对构建配置
+    fn from_values(env_vars: &HashMap<String, String>) -> Result<Self, MddeError> {
         let mut config = Config::default();
 
-        // 从环境变量文件覆盖配置
         if let Some(host) = env_vars.get("host") {
             config.host = host.clone();
         }
+        if let Some(sources) = env_vars.get("sources") {
+            config.sources = TemplateSource::parse_list(sources)?;
+        }
+        if let Some(trusted_keys) = env_vars.get("trusted_keys") {
+            config.trusted_keys = TrustedKey::parse_list(trusted_keys)?;
+        }
         if let Some(container_name) = env_vars.get("container_name") {
             config.container_name = Some(container_name.clone());
         }
-        if let Some(app_port) = env_vars.get("app_port") {
-            if let Ok(port) = app_port.parse::<u16>() {
-                config.app_port = Some(port);
-            }
-        }
+        config.set_port_mappings(Self::ports_from_env(env_vars)?);
         if let Some(workspace) = env_vars.get("workspace") {
             config.workspace = Some(PathBuf::from(workspace));
         }
+        if let Some(compose) = env_vars.get("compose") {
+            config.compose = Some(compose.parse()?);
+        }
+        if let Some(engine) = env_vars.get("engine") {
+            config.engine = Some(engine.parse()?);
+        }
 
         Ok(config)
     }
 
+    /// 从 `MDDE_*` 环境变量中提取配置项，如 `MDDE_HOST` 对应 `host`
+    pub fn env_entries(vars: impl IntoIterator<Item = (String, String)>) -> Vec<ConfigValue> {
+        let mut entries: Vec<ConfigValue> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+                CONFIG_KEYS
+                    .contains(&key.as_str())
+                    .then(|| ConfigValue::new(&key, &value, ConfigSource::Environment(name)))
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        entries
+    }
+
+    /// 用户级配置文件路径，可以通过 `MDDE_CONFIG_FILE` 环境变量指定
+    pub fn user_config_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("MDDE_CONFIG_FILE") {
+            return Some(PathBuf::from(path));
+        }
+        dirs::config_dir().map(|dir| dir.join("mdde").join("config.toml"))
+    }
+
+    /// 解析 TOML 格式的用户配置，`ports` 可以写成字符串数组
+    pub fn parse_user_config(content: &str) -> Result<Vec<(String, String)>, MddeError> {
+        let table: toml::Table = toml::from_str(content)?;
+        table
+            .into_iter()
+            .map(|(key, value)| {
+                if !CONFIG_KEYS.contains(&key.as_str()) {
+                    return Err(MddeError::Config(i18n::tf("unknown_config_key", &[&key])));
+                }
+                let value = match value {
+                    toml::Value::String(value) => value,
+                    toml::Value::Integer(value) => value.to_string(),
+                    toml::Value::Array(values) => values
+                        .iter()
+                        .map(|value| match value {
+                            toml::Value::String(value) => value.clone(),
+                            other => other.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    other => other.to_string(),
+                };
+                Ok((key, value))
+            })
+            .collect()
+    }
+
     /// 保存配置文件
     pub async fn save(&self) -> Result<(), MddeError> {
-        let mut env_vars = HashMap::new();
+        let mut env_vars = Self::load_env_document().await?;
 
-        // 将配置转换为环境变量格式
-        env_vars.insert("host".to_string(), self.host.clone());
-        if let Some(container_name) = &self.container_name {
-            env_vars.insert("container_name".to_string(), container_name.clone());
+        // 将配置写入环境变量文件，文件中的其他内容保持不变
+        env_vars.set("host", &self.host);
+        if !self.sources.is_empty() {
+            env_vars.set("sources", &TemplateSource::format_list(&self.sources));
         }
-        if let Some(app_port) = self.app_port {
-            env_vars.insert("app_port".to_string(), app_port.to_string());
+        if let Some(container_name) = &self.container_name {
+            env_vars.set("container_name", container_name);
         }
+        Self::ports_into_env(&mut env_vars, &self.port_mappings());
         if let Some(workspace) = &self.workspace {
-            env_vars.insert(
-                "workspace".to_string(),
-                workspace.to_string_lossy().to_string(),
-            );
+            env_vars.set("workspace", &workspace.to_string_lossy());
+        }
+        if let Some(compose) = self.compose {
+            env_vars.set("compose", compose.as_str());
+        }
+        if let Some(engine) = self.engine {
+            env_vars.set("engine", engine.as_str());
         }
 
         // 保存到 cfg.env 文件
@@ -76,73 +366,121 @@ impl Config {
 
     /// 更新配置
     pub async fn update(&mut self, updates: HashMap<String, String>) -> Result<(), MddeError> {
+        let mut env_vars = Self::load_env_document().await?;
         for (key, value) in updates {
             match key.as_str() {
-                "host" => self.host = value,
-                "container_name" => self.container_name = Some(value),
-                "app_port" => {
-                    self.app_port = Some(
-                        value
-                            .parse()
-                            .map_err(|_| MddeError::InvalidArgument("无效的端口号".to_string()))?,
-                    );
+                "host" => self.host = value.clone(),
+                "sources" => self.sources = TemplateSource::parse_list(&value)?,
+                "trusted_keys" => {
+                    return Err(MddeError::InvalidArgument(i18n::tf(
+                        "user_only_config_key",
+                        &[&key],
+                    )))
                 }
-                "workspace" => self.workspace = Some(PathBuf::from(value)),
+                "container_name" => self.container_name = Some(value.clone()),
+                "app_port" => self.app_port = Some(value.parse()?),
+                "ports" => self.ports = PortMapping::parse_list(&value)?,
+                "workspace" => self.workspace = Some(PathBuf::from(&value)),
+                "compose" => self.compose = Some(value.parse()?),
+                "engine" => self.engine = Some(value.parse()?),
                 _ => return Err(MddeError::InvalidArgument(format!("未知配置项: {}", key))),
             }
+            env_vars.set(&key, &value);
         }
 
-        self.save().await
+        // 只写入更新的配置项，其他层的配置不会被复制到项目文件中
+        Self::save_env_file(&env_vars).await
+    }
+
+    /// 按查找顺序排列的所有模板来源，`sources` 在前，`host` 作为 `default` 来源排在最后
+    pub fn template_sources(&self) -> Result<Vec<TemplateSource>, MddeError> {
+        let mut sources = self.sources.clone();
+        sources.push(TemplateSource::from_host(&self.host)?);
+        Ok(sources)
+    }
+
+    /// 所有端口映射，第一个为应用端口
+    pub fn port_mappings(&self) -> Vec<PortMapping> {
+        self.app_port.iter().chain(&self.ports).cloned().collect()
     }
 
-    // /// 获取配置文件路径
-    // pub fn get_config_path() -> Result<PathBuf, MddeError> {
-    //     #[cfg(target_os = "windows")]
-    //     {
-    //         let app_data = std::env::var("APPDATA")
-    //             .map_err(|_| MddeError::Config("无法获取 APPDATA 环境变量".to_string()))?;
-    //         Ok(PathBuf::from(app_data).join("mdde").join("config.toml"))
-    //     }
+    /// 设置所有端口映射，第一个作为应用端口
+    pub fn set_port_mappings(&mut self, mappings: Vec<PortMapping>) {
+        let mut mappings = mappings.into_iter();
+        self.app_port = mappings.next();
+        self.ports = mappings.collect();
+    }
 
-    //     #[cfg(not(target_os = "windows"))]
-    //     {
-    //         let config_dir = dirs::config_dir()
-    //             .ok_or_else(|| MddeError::Config("无法获取配置目录".to_string()))?;
-    //         Ok(config_dir.join("mdde").join("config.toml"))
-    //     }
-    // }
+    /// 从环境变量中读取端口映射：`app_port` 为应用端口，`ports` 为其他端口
+    pub fn ports_from_env(
+        env_vars: &HashMap<String, String>,
+    ) -> Result<Vec<PortMapping>, MddeError> {
+        let mut mappings = Vec::new();
+        if let Some(app_port) = env_vars.get("app_port").filter(|v| !v.trim().is_empty()) {
+            mappings.push(app_port.parse()?);
+        }
+        if let Some(ports) = env_vars.get("ports") {
+            mappings.extend(PortMapping::parse_list(ports)?);
+        }
+        Ok(mappings)
+    }
 
-    /// 获取环境变量文件路径
+    /// 将端口映射写入环境变量文件，没有的项会被删除
+    pub fn ports_into_env(env_vars: &mut EnvFile, mappings: &[PortMapping]) {
+        match mappings.split_first() {
+            Some((app_port, ports)) => {
+                env_vars.set("app_port", &app_port.to_string());
+                if ports.is_empty() {
+                    env_vars.remove("ports");
+                } else {
+                    env_vars.set("ports", &PortMapping::format_list(ports));
+                }
+            }
+            None => {
+                env_vars.remove("app_port");
+                env_vars.remove("ports");
+            }
+        }
+    }
+
+    /// 获取环境变量文件路径，位于当前环境的目录下（见 [`project::env_dir`]）
     pub fn get_env_file_path() -> Result<PathBuf, MddeError> {
-        let path = std::env::current_dir()?.join(".mdde").join("cfg.env");
+        let path = project::env_dir()?.join("cfg.env");
         Ok(path)
     }
 
     /// 加载环境变量文件
     pub async fn load_env_file() -> Result<HashMap<String, String>, MddeError> {
+        Ok(Self::load_env_document().await?.to_map())
+    }
+
+    /// 加载环境变量文件的文档模型，文件不存在时为空文档
+    pub async fn load_env_document() -> Result<EnvFile, MddeError> {
         let env_path = Self::get_env_file_path()?;
 
         if !env_path.exists() {
-            return Ok(HashMap::new());
+            return Ok(EnvFile::default());
         }
 
         let content = fs::read_to_string(&env_path).await?;
-        let mut env_vars = HashMap::new();
+        EnvFile::parse(&content).map_err(|e| with_path(&env_path, e))
+    }
 
-        for line in content.lines() {
-            let line = line.trim();
-            if !line.is_empty() && !line.starts_with('#') {
-                if let Some((key, value)) = line.split_once('=') {
-                    env_vars.insert(key.trim().to_string(), value.trim().to_string());
-                }
-            }
+    /// 宽松加载环境变量文件：有语法错误的行原样保留，同时返回语法错误，
+    /// 见 [`EnvFile::parse_lenient`]
+    pub async fn load_env_document_lenient() -> Result<(EnvFile, Vec<MddeError>), MddeError> {
+        let env_path = Self::get_env_file_path()?;
+
+        if !env_path.exists() {
+            return Ok((EnvFile::default(), Vec::new()));
         }
 
-        Ok(env_vars)
+        let content = fs::read_to_string(&env_path).await?;
+        Ok(EnvFile::parse_lenient(&content))
     }
 
-    /// 保存环境变量文件
-    pub async fn save_env_file(env_vars: &HashMap<String, String>) -> Result<(), MddeError> {
+    /// 保存环境变量文件，注释、空行和键的顺序保持不变
+    pub async fn save_env_file(env_file: &EnvFile) -> Result<(), MddeError> {
         let env_path = Self::get_env_file_path()?;
 
         // 确保 .mdde 目录存在
@@ -150,12 +488,7 @@ impl Config {
             fs::create_dir_all(parent).await?;
         }
 
-        let mut content = String::new();
-        for (key, value) in env_vars {
-            content.push_str(&format!("{}={}\n", key, value));
-        }
-
-        fs::write(&env_path, content).await?;
+        fs::write(&env_path, env_file.to_string()).await?;
 
         // 更新 .gitignore 文件
         Self::update_gitignore().await?;
@@ -165,7 +498,7 @@ impl Config {
 
     /// 更新 .gitignore 文件
     pub async fn update_gitignore() -> Result<(), MddeError> {
-        let gitignore_path = std::env::current_dir()?.join(".gitignore");
+        let gitignore_path = project::root()?.join(".gitignore");
 
         // 检查 .gitignore 是否存在
         if !gitignore_path.exists() {
@@ -184,10 +517,180 @@ impl Config {
         if !new_content.ends_with('\n') {
             new_content.push('\n');
         }
-        new_content.push_str("\n# MDDE 配置目录\n.mdde/\n");
+        new_content.push_str(GITIGNORE_BLOCK);
 
         fs::write(&gitignore_path, new_content).await?;
 
         Ok(())
     }
+
+    /// 移除 [`Config::update_gitignore`] 添加的 `.mdde/` 条目，返回是否有改动
+    pub async fn remove_gitignore_entry(root: &Path) -> Result<bool, MddeError> {
+        let gitignore_path = root.join(".gitignore");
+        if !gitignore_path.exists() {
+            return Ok(false);
+        }
+
+        let content = fs::read_to_string(&gitignore_path).await?;
+        match without_mdde_entry(&content) {
+            Some(new_content) => {
+                fs::write(&gitignore_path, new_content).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// [`Config::update_gitignore`] 追加到 `.gitignore` 末尾的内容
+const GITIGNORE_BLOCK: &str = "\n# MDDE 配置目录\n.mdde/\n";
+
+/// 去掉 `.gitignore` 中 mdde 追加的 [`GITIGNORE_BLOCK`]，用户自己写的条目保持不变，没有该内容时返回 `None`
+fn without_mdde_entry(content: &str) -> Option<String> {
+    content
+        .contains(GITIGNORE_BLOCK)
+        .then(|| content.replacen(GITIGNORE_BLOCK, "", 1))
+}
+
+/// 在配置错误前加上出错的文件路径
+fn with_path(path: &Path, error: MddeError) -> MddeError {
+    match error {
+        MddeError::Config(message) => MddeError::Config(format!("{}: {}", path.display(), message)),
+        error => MddeError::Config(format!("{}: {}", path.display(), error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_without_mdde_entry() {
+        assert_eq!(
+            without_mdde_entry("target/\n\n# MDDE 配置目录\n.mdde/\n").as_deref(),
+            Some("target/\n")
+        );
+        assert_eq!(
+            without_mdde_entry("target/\r\n\n# MDDE 配置目录\n.mdde/\ndist/\r\n\n").as_deref(),
+            Some("target/\r\ndist/\r\n\n")
+        );
+        // 用户自己写的条目不是 mdde 添加的，保持不变
+        assert_eq!(without_mdde_entry("target/\n.mdde\nnode_modules/\n"), None);
+        assert_eq!(without_mdde_entry(".mdde/\n"), None);
+        assert_eq!(without_mdde_entry("target/\n.mdde-archive/\n"), None);
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let user = ConfigSource::User(PathBuf::from("/home/dev/.config/mdde/config.toml"));
+        let project = ConfigSource::Project(PathBuf::from("/work/app/.mdde/cfg.env"));
+        let entries = vec![
+            ConfigValue::new("host", DEFAULT_HOST, ConfigSource::Default),
+            ConfigValue::new("host", "http://user", user.clone()),
+            ConfigValue::new("engine", "podman", user.clone()),
+            ConfigValue::new("host", "http://project", project.clone()),
+            ConfigValue::new("container_name", "app", project.clone()),
+            ConfigValue::new("CUSTOM_VAR", "ignored", project.clone()),
+            ConfigValue::new(
+                "engine",
+                "docker",
+                ConfigSource::Environment("MDDE_ENGINE".to_string()),
+            ),
+            ConfigValue::new("host", "http://cli", ConfigSource::Cli),
+        ];
+
+        let (config, values) = Config::resolve(entries).unwrap();
+        assert_eq!(config.host, "http://cli");
+        assert_eq!(config.container_name.as_deref(), Some("app"));
+        assert_eq!(config.engine, Some(ContainerEngine::Docker));
+
+        let keys: Vec<&str> = values.iter().map(|v| v.key.as_str()).collect();
+        assert_eq!(keys, vec!["host", "container_name", "engine"]);
+        assert_eq!(values[0].source, ConfigSource::Cli);
+        assert_eq!(values[1].source, project);
+        assert_eq!(
+            values[2].source,
+            ConfigSource::Environment("MDDE_ENGINE".to_string())
+        );
+
+        // 项目文件和环境变量不能替换或清空用户配置的信任公钥
+        let key = "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=";
+        let entries = vec![
+            ConfigValue::new("trusted_keys", key, user.clone()),
+            ConfigValue::new("trusted_keys", "", project.clone()),
+            ConfigValue::new(
+                "trusted_keys",
+                "",
+                ConfigSource::Environment("MDDE_TRUSTED_KEYS".to_string()),
+            ),
+        ];
+        let (config, values) = Config::resolve(entries).unwrap();
+        assert_eq!(config.trusted_keys.len(), 1);
+        assert_eq!(values[0].source, user);
+
+        let entries = vec![ConfigValue::new("trusted_keys", key, project.clone())];
+        assert!(Config::resolve(entries).unwrap().0.trusted_keys.is_empty());
+    }
+
+    #[test]
+    fn test_env_entries() {
+        let entries = Config::env_entries([
+            ("MDDE_HOST".to_string(), "http://env".to_string()),
+            ("MDDE_APP_PORT".to_string(), "8080:80".to_string()),
+            (
+                "MDDE_CONFIG_FILE".to_string(),
+                "/tmp/config.toml".to_string(),
+            ),
+            ("HOST".to_string(), "ignored".to_string()),
+        ]);
+
+        assert_eq!(
+            entries,
+            vec![
+                ConfigValue::new(
+                    "app_port",
+                    "8080:80",
+                    ConfigSource::Environment("MDDE_APP_PORT".to_string())
+                ),
+                ConfigValue::new(
+                    "host",
+                    "http://env",
+                    ConfigSource::Environment("MDDE_HOST".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_user_config() {
+        let values = Config::parse_user_config(
+            "host = \"http://intranet\"\nengine = \"podman\"\nports = [\"8080:80\", \"5353:53/udp\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                ("engine".to_string(), "podman".to_string()),
+                ("host".to_string(), "http://intranet".to_string()),
+                ("ports".to_string(), "8080:80,5353:53/udp".to_string()),
+            ]
+        );
+
+        let values = Config::parse_user_config(
+            "sources = [\"team=/srv/templates\", \"intranet=https://tpl.local\"]\n",
+        )
+        .unwrap();
+        let values: HashMap<String, String> = values.into_iter().collect();
+        let config = Config::from_values(&values).unwrap();
+        let names: Vec<_> = config
+            .template_sources()
+            .unwrap()
+            .into_iter()
+            .map(|source| source.name)
+            .collect();
+        assert_eq!(names, ["team", "intranet", "default"]);
+
+        assert!(Config::parse_user_config("hots = \"typo\"\n").is_err());
+        assert!(Config::parse_user_config("host = ").is_err());
+    }
 }
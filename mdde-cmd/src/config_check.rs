@@ -0,0 +1,597 @@
+use crate::compose::{ComposeFlavor, ComposeProject};
+use crate::config::{CONFIG_KEYS, METADATA_KEYS};
+use crate::container_engine::ContainerEngine;
+use crate::env_file::EnvFile;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::port_mapping::PortMapping;
+use serde::Serialize;
+use std::path::Path;
+
+/// compose 文件中的一处变量引用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableRef {
+    pub name: String,
+    /// 所在行号，从 1 开始
+    pub line: usize,
+    pub modifier: Modifier,
+}
+
+/// `${NAME...}` 中变量名之后的部分，`colon` 为 true 时空值视为未设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Modifier {
+    None,
+    /// `:-default` 或 `-default`
+    Default {
+        colon: bool,
+    },
+    /// `:?error` 或 `?error`
+    Required {
+        colon: bool,
+        message: String,
+    },
+    /// `:+alternative` 或 `+alternative`
+    Alternative,
+}
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 检查发现的问题
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// 问题类型：missing、empty、unused、malformed 或 syntax
+    pub kind: &'static str,
+    pub variable: Option<String>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+/// 变量的取值来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueSource {
+    /// cfg.env
+    File,
+    /// 进程环境变量，优先于 cfg.env
+    Environment,
+    /// 引用中的默认值
+    Default,
+    Missing,
+}
+
+impl ValueSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValueSource::File => "file",
+            ValueSource::Environment => "environment",
+            ValueSource::Default => "default",
+            ValueSource::Missing => "missing",
+        }
+    }
+}
+
+/// compose 文件引用的一个变量及其取值
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Variable {
+    pub name: String,
+    pub lines: Vec<usize>,
+    pub value: Option<String>,
+    pub source: ValueSource,
+}
+
+/// 检查结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub variables: Vec<Variable>,
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn errors(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors() > 0
+    }
+}
+
+impl Issue {
+    fn new(
+        severity: Severity,
+        kind: &'static str,
+        variable: Option<&str>,
+        message: String,
+    ) -> Self {
+        Self {
+            severity,
+            kind,
+            variable: variable.map(str::to_string),
+            message,
+            suggestion: None,
+        }
+    }
+
+    fn suggest(mut self, suggestion: String) -> Self {
+        self.suggestion = Some(suggestion);
+        self
+    }
+}
+
+/// 检查项目的 compose 文件和 cfg.env，compose 和 mdde 都会读取进程环境变量
+pub fn check_project(project: &ComposeProject) -> Result<Report, MddeError> {
+    let compose = std::fs::read_to_string(&project.compose_file)?;
+    let content = if project.env_file.exists() {
+        std::fs::read_to_string(&project.env_file)?
+    } else {
+        String::new()
+    };
+
+    match EnvFile::parse(&content) {
+        Ok(env) => Ok(check(&compose, &env, |name| std::env::var(name).ok())),
+        Err(e) => Ok(Report {
+            variables: Vec::new(),
+            issues: vec![Issue::new(
+                Severity::Error,
+                "syntax",
+                None,
+                format!("{}: {}", project.env_file.display(), e),
+            )],
+        }),
+    }
+}
+
+/// 对照 compose 文件中的变量引用检查 cfg.env：未定义、空值、未使用和格式错误的值
+pub fn check(compose: &str, env: &EnvFile, lookup: impl Fn(&str) -> Option<String>) -> Report {
+    let mut report = Report::default();
+    let (references, invalid) = references(compose);
+
+    for (line, reference) in invalid {
+        report.issues.push(Issue::new(
+            Severity::Error,
+            "syntax",
+            None,
+            i18n::tf("check_invalid_reference", &[&line, &reference]),
+        ));
+    }
+
+    let defined: Vec<&str> = env.entries().iter().map(|(key, _)| *key).collect();
+    let mut names: Vec<&str> = Vec::new();
+    for reference in &references {
+        if !names.contains(&reference.name.as_str()) {
+            names.push(&reference.name);
+        }
+    }
+
+    for name in &names {
+        let uses: Vec<&VariableRef> = references.iter().filter(|r| r.name == *name).collect();
+        let lines: Vec<usize> = uses.iter().map(|r| r.line).collect();
+
+        let (value, source) = match (lookup(name), env.get(name)) {
+            (Some(value), _) => (Some(value), ValueSource::Environment),
+            (None, Some(value)) => (Some(value.to_string()), ValueSource::File),
+            (None, None)
+                if uses
+                    .iter()
+                    .any(|r| matches!(r.modifier, Modifier::Default { .. })) =>
+            {
+                (None, ValueSource::Default)
+            }
+            (None, None) => (None, ValueSource::Missing),
+        };
+
+        let empty = value.as_deref().is_some_and(str::is_empty);
+        for reference in &uses {
+            let issue = match (&reference.modifier, &value) {
+                (Modifier::Required { colon, message }, value)
+                    if value.is_none() || (*colon && empty) =>
+                {
+                    Some(Issue::new(
+                        Severity::Error,
+                        "missing",
+                        Some(name),
+                        i18n::tf("check_required_variable", &[name, &reference.line, message]),
+                    ))
+                }
+                (Modifier::None, None) => Some(Issue::new(
+                    Severity::Error,
+                    "missing",
+                    Some(name),
+                    i18n::tf("check_missing_variable", &[name, &reference.line]),
+                )),
+                (Modifier::None, Some(_)) if empty => Some(Issue::new(
+                    Severity::Warning,
+                    "empty",
+                    Some(name),
+                    i18n::tf("check_empty_variable", &[name, &reference.line]),
+                )),
+                _ => None,
+            };
+
+            if let Some(issue) = issue {
+                let suggestion = match similar(name, &defined) {
+                    Some(candidate) if issue.kind == "missing" => {
+                        i18n::tf("check_suggest_rename", &[&candidate, name])
+                    }
+                    _ => i18n::tf("check_suggest_set", &[name]),
+                };
+                report.issues.push(issue.suggest(suggestion));
+                // 同一个变量只报告一次
+                break;
+            }
+        }
+
+        report.variables.push(Variable {
+            name: name.to_string(),
+            lines,
+            value,
+            source,
+        });
+    }
+
+    for (key, value) in env.entries() {
+        if let Some(issue) = malformed(key, value) {
+            report.issues.push(issue);
+        }
+    }
+
+    for key in &defined {
+        if names.contains(key)
+            || CONFIG_KEYS.contains(key)
+            || METADATA_KEYS.contains(key)
+            || is_compose_setting(key)
+        {
+            continue;
+        }
+        let issue = Issue::new(
+            Severity::Warning,
+            "unused",
+            Some(key),
+            i18n::tf("check_unused_variable", &[key]),
+        );
+        let suggestion = match similar(key, &names) {
+            Some(candidate) => i18n::tf("check_suggest_rename", &[key, &candidate]),
+            None => i18n::tf("check_suggest_remove", &[key]),
+        };
+        report.issues.push(issue.suggest(suggestion));
+    }
+
+    report
+}
+
+/// 列出 compose 文件中的所有变量引用，同时返回无效的引用及其行号
+///
+/// 与 compose 的插值规则一致：`$$` 表示字面量 `$`，注释行中的引用被忽略。
+pub fn references(compose: &str) -> (Vec<VariableRef>, Vec<(usize, String)>) {
+    let mut references = Vec::new();
+    let mut invalid = Vec::new();
+
+    for (index, line) in compose.lines().enumerate() {
+        let line_no = index + 1;
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(dollar) = rest.find('$') {
+            let after = &rest[dollar + 1..];
+            if let Some(after) = after.strip_prefix('$') {
+                rest = after;
+            } else if let Some(inner) = after.strip_prefix('{') {
+                let Some(end) = closing_brace(inner) else {
+                    invalid.push((line_no, rest[dollar..].to_string()));
+                    break;
+                };
+                let expression = &rest[dollar..dollar + end + 3];
+                match parse_braced(&inner[..end]) {
+                    Some((name, modifier)) => {
+                        references.push(VariableRef {
+                            name: name.to_string(),
+                            line: line_no,
+                            modifier: modifier.clone(),
+                        });
+                        // 默认值中也可以引用变量
+                        if let Modifier::Default { .. } = modifier {
+                            let (nested, _) = references_in(&inner[..end], line_no);
+                            references.extend(nested);
+                        }
+                    }
+                    None => invalid.push((line_no, expression.to_string())),
+                }
+                rest = &inner[end + 1..];
+            } else {
+                let name_len = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                let name = &after[..name_len];
+                if !name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit()) {
+                    references.push(VariableRef {
+                        name: name.to_string(),
+                        line: line_no,
+                        modifier: Modifier::None,
+                    });
+                }
+                rest = &after[name_len..];
+            }
+        }
+    }
+
+    (references, invalid)
+}
+
+/// 查找默认值中嵌套的引用（跳过最外层的变量名）
+fn references_in(inner: &str, line_no: usize) -> (Vec<VariableRef>, Vec<(usize, String)>) {
+    let operand_start = inner.find(['-', '?', '+']).map_or(inner.len(), |i| i + 1);
+    let (nested, invalid) = references(&inner[operand_start..]);
+    let nested = nested
+        .into_iter()
+        .map(|reference| VariableRef {
+            line: line_no,
+            ..reference
+        })
+        .collect();
+    (nested, invalid)
+}
+
+/// 查找与 `${` 匹配的 `}`，允许默认值中嵌套 `${...}`
+fn closing_brace(s: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 解析 `${...}` 的内容，返回变量名和修饰符
+fn parse_braced(inner: &str) -> Option<(&str, Modifier)> {
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(inner.len());
+    let (name, modifier) = inner.split_at(name_len);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (colon, modifier) = match modifier.strip_prefix(':') {
+        Some(modifier) => (true, modifier),
+        None => (false, modifier),
+    };
+    let mut chars = modifier.chars();
+    let modifier = match chars.next() {
+        None if !colon => Modifier::None,
+        Some('-') => Modifier::Default { colon },
+        Some('?') => Modifier::Required {
+            colon,
+            message: chars.as_str().to_string(),
+        },
+        Some('+') => Modifier::Alternative,
+        _ => return None,
+    };
+    Some((name, modifier))
+}
+
+/// 检查 mdde 配置项的值是否有效
+fn malformed(key: &str, value: &str) -> Option<Issue> {
+    let invalid = |reason: String| {
+        Some(Issue::new(
+            Severity::Error,
+            "malformed",
+            Some(key),
+            i18n::tf("check_malformed_value", &[&key, &value, &reason]),
+        ))
+    };
+
+    match key {
+        "host" if !value.starts_with("http://") && !value.starts_with("https://") => {
+            invalid(i18n::t("url_must_start_with").to_string())
+        }
+        "container_name" if !is_valid_container_name(value) => {
+            invalid(i18n::t("check_invalid_container_name").to_string())
+        }
+        "app_port" if !value.trim().is_empty() => value
+            .parse::<PortMapping>()
+            .err()
+            .and_then(|e| invalid(e.to_string())),
+        "ports" => PortMapping::parse_list(value)
+            .err()
+            .and_then(|e| invalid(e.to_string())),
+        "compose" => value
+            .parse::<ComposeFlavor>()
+            .err()
+            .and_then(|e| invalid(e.to_string())),
+        "engine" => value
+            .parse::<ContainerEngine>()
+            .err()
+            .and_then(|e| invalid(e.to_string())),
+        "workspace" if !value.is_empty() && !Path::new(value).is_dir() => Some(Issue::new(
+            Severity::Warning,
+            "malformed",
+            Some(key),
+            i18n::tf("check_workspace_missing", &[&value]),
+        )),
+        _ => None,
+    }
+}
+
+/// 容器名称的格式与 Docker 一致：`[a-zA-Z0-9][a-zA-Z0-9_.-]*`
+fn is_valid_container_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// compose 自身读取的 `COMPOSE_*` 和 `DOCKER_*` 变量不算未使用
+fn is_compose_setting(key: &str) -> bool {
+    key.starts_with("COMPOSE_") || key.starts_with("DOCKER_")
+}
+
+/// 查找拼写相近的名称（忽略大小写，编辑距离不超过 2）
+fn similar<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let lowercase = name.to_lowercase();
+    candidates
+        .iter()
+        .filter(|candidate| **candidate != name)
+        .map(|candidate| {
+            let distance = edit_distance(&lowercase, &candidate.to_lowercase());
+            (*candidate, distance)
+        })
+        .filter(|&(_, distance)| distance <= 2 && distance < lowercase.len())
+        .min_by_key(|&(_, distance)| distance)
+        .map(|(candidate, _)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSE: &str = "services:\n  app:\n    image: node:${NODE_VERSION:-22}\n    container_name: ${container_name}\n    # ports: ${commented}\n    ports:\n      - \"${app_port}\"\n    volumes:\n      - ${workspace}:/app\n    environment:\n      - DB_URL=${DB_URL:?set DB_URL in cfg.env}\n      - PRICE=$$5\n      - TOKEN=$API_TOKEN\n";
+
+    fn check_env(env: &str) -> Report {
+        check(
+            COMPOSE,
+            &EnvFile::parse_with(env, |_| None).unwrap(),
+            |name| (name == "API_TOKEN").then(|| "from-shell".to_string()),
+        )
+    }
+
+    fn kinds(report: &Report) -> Vec<(&str, Option<&str>)> {
+        report
+            .issues
+            .iter()
+            .map(|issue| (issue.kind, issue.variable.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_references() {
+        let (found, invalid) = references(COMPOSE);
+        let names: Vec<&str> = found.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "NODE_VERSION",
+                "container_name",
+                "app_port",
+                "workspace",
+                "DB_URL",
+                "API_TOKEN"
+            ]
+        );
+        assert_eq!(found[1].line, 4);
+        assert_eq!(found[0].modifier, Modifier::Default { colon: true });
+        assert!(invalid.is_empty());
+
+        let (found, _) = references("image: ${REGISTRY:-${MIRROR}/library}/node\n");
+        let names: Vec<&str> = found.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["REGISTRY", "MIRROR"]);
+
+        let (_, invalid) = references("a: ${1BAD}\nb: ${NAME:x}\nc: ${UNCLOSED\n");
+        assert_eq!(
+            invalid.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_clean_config_has_no_issues() {
+        let workspace = std::env::temp_dir();
+        let report = check_env(&format!(
+            "container_name=demo\napp_port=8080:80\nworkspace={}\nDB_URL=postgres://db\nCOMPOSE_PROJECT_NAME=demo\n",
+            workspace.display()
+        ));
+        assert_eq!(report.issues, Vec::new());
+
+        let sources: Vec<(&str, ValueSource)> = report
+            .variables
+            .iter()
+            .map(|v| (v.name.as_str(), v.source))
+            .collect();
+        assert_eq!(sources[0], ("NODE_VERSION", ValueSource::Default));
+        assert_eq!(sources[1], ("container_name", ValueSource::File));
+        assert_eq!(sources[5], ("API_TOKEN", ValueSource::Environment));
+    }
+
+    #[test]
+    fn test_reports_missing_unused_and_malformed() {
+        let report = check_env(
+            "container_name=my app\napp_port=80:abc\nworkspace=\ndb_ur=postgres://db\nEXTRA=1\n",
+        );
+
+        assert_eq!(
+            kinds(&report),
+            vec![
+                ("empty", Some("workspace")),
+                ("missing", Some("DB_URL")),
+                ("malformed", Some("container_name")),
+                ("malformed", Some("app_port")),
+                ("unused", Some("db_ur")),
+                ("unused", Some("EXTRA")),
+            ]
+        );
+        assert_eq!(report.errors(), 3);
+
+        // 拼写相近时建议改名
+        let missing = &report.issues[1];
+        assert!(missing.message.contains("set DB_URL in cfg.env"));
+        assert!(missing.suggestion.as_deref().unwrap().contains("db_ur"));
+        assert!(report.issues[4]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("DB_URL"));
+        assert!(report.issues[5]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("--del"));
+    }
+
+    #[test]
+    fn test_missing_without_default() {
+        let report = check("image: ${IMAGE}\n", &EnvFile::default(), |_| None);
+        assert_eq!(kinds(&report), vec![("missing", Some("IMAGE"))]);
+        assert_eq!(report.variables[0].source, ValueSource::Missing);
+        assert!(report.issues[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("mdde env --set IMAGE="));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::MddeError;
+use crate::i18n;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+/// 容器引擎
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerEngine {
+    Docker,
+    Podman,
+}
+
+impl ContainerEngine {
+    /// 检测时的优先顺序
+    const PROBE_ORDER: [ContainerEngine; 2] = [ContainerEngine::Docker, ContainerEngine::Podman];
+
+    /// 命令行可执行文件名
+    pub fn program(self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker",
+            ContainerEngine::Podman => "podman",
+        }
+    }
+
+    /// 独立 compose 工具的可执行文件名
+    pub fn standalone_compose(self) -> &'static str {
+        match self {
+            ContainerEngine::Docker => "docker-compose",
+            ContainerEngine::Podman => "podman-compose",
+        }
+    }
+
+    /// 构建引擎命令
+    pub fn command(self) -> Command {
+        Command::new(self.program())
+    }
+
+    /// 获取版本信息，未安装时返回 `None`
+    pub fn version(self) -> Option<String> {
+        let output = self.command().arg("--version").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Some(version).filter(|v| !v.is_empty())
+    }
+
+    /// 本地 API 套接字的候选路径，按优先级排列
+    pub fn socket_candidates(self) -> Vec<PathBuf> {
+        match self {
+            ContainerEngine::Docker => vec![PathBuf::from("/var/run/docker.sock")],
+            ContainerEngine::Podman => {
+                let mut sockets = Vec::new();
+                // rootless 模式的套接字位于用户运行时目录
+                if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+                    sockets.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+                }
+                sockets.push(PathBuf::from("/run/podman/podman.sock"));
+                sockets
+            }
+        }
+    }
+
+    /// 配置文件中使用的名称
+    pub fn as_str(self) -> &'static str {
+        self.program()
+    }
+}
+
+impl fmt::Display for ContainerEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerEngine::Docker => write!(f, "Docker"),
+            ContainerEngine::Podman => write!(f, "Podman"),
+        }
+    }
+}
+
+impl FromStr for ContainerEngine {
+    type Err = MddeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "docker" => Ok(ContainerEngine::Docker),
+            "podman" => Ok(ContainerEngine::Podman),
+            other => Err(MddeError::Config(i18n::tf("invalid_engine", &[&other]))),
+        }
+    }
+}
+
+/// 配置中指定的容器引擎
+static PREFERRED_ENGINE: RwLock<Option<ContainerEngine>> = RwLock::new(None);
+
+/// 指定容器引擎，`None` 表示自动检测
+pub fn set_preferred_engine(engine: Option<ContainerEngine>) {
+    if let Ok(mut preferred) = PREFERRED_ENGINE.write() {
+        *preferred = engine;
+    }
+}
+
+/// 探测已安装的引擎，优先使用 Docker，结果在进程内缓存
+pub fn detect_engine() -> Option<ContainerEngine> {
+    static DETECTED: OnceLock<Option<ContainerEngine>> = OnceLock::new();
+    *DETECTED.get_or_init(|| {
+        ContainerEngine::PROBE_ORDER
+            .into_iter()
+            .find(|engine| engine.version().is_some())
+    })
+}
+
+/// 是否由配置指定了引擎
+pub fn is_configured() -> bool {
+    PREFERRED_ENGINE.read().is_ok_and(|p| p.is_some())
+}
+
+/// 获取要使用的容器引擎：优先使用配置，否则自动检测，都不可用时默认为 Docker
+pub fn current() -> ContainerEngine {
+    let preferred = PREFERRED_ENGINE.read().ok().and_then(|p| *p);
+    preferred
+        .or_else(detect_engine)
+        .unwrap_or(ContainerEngine::Docker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_engine() {
+        assert_eq!(
+            "podman".parse::<ContainerEngine>().unwrap(),
+            ContainerEngine::Podman
+        );
+        assert_eq!(
+            " Docker ".parse::<ContainerEngine>().unwrap(),
+            ContainerEngine::Docker
+        );
+        assert!("containerd".parse::<ContainerEngine>().is_err());
+        assert_eq!(
+            ContainerEngine::Podman
+                .as_str()
+                .parse::<ContainerEngine>()
+                .unwrap(),
+            ContainerEngine::Podman
+        );
+    }
+
+    #[test]
+    fn test_podman_socket_candidates() {
+        let sockets = ContainerEngine::Podman.socket_candidates();
+        assert_eq!(
+            sockets.last().unwrap(),
+            &PathBuf::from("/run/podman/podman.sock")
+        );
+        assert!(sockets.iter().all(|s| s.ends_with("podman.sock")));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::compose::{self, ComposeFlavor, ComposeProject};
+use crate::container_engine;
 use std::error::Error;
 use std::fmt;
 use std::process::Command;
@@ -13,6 +15,7 @@ pub enum DockerError {
     DockerNotInstalled,
     ContainerNotFound(String),
     ContainerNotRunning(String),
+    ApiError(u16, String),
 }
 
 impl fmt::Display for DockerError {
@@ -23,6 +26,9 @@ impl fmt::Display for DockerError {
             DockerError::DockerNotInstalled => write!(f, "Docker未安装或不在PATH中"),
             DockerError::ContainerNotFound(name) => write!(f, "容器不存在: {}", name),
             DockerError::ContainerNotRunning(name) => write!(f, "容器未运行: {}", name),
+            DockerError::ApiError(status, msg) => {
+                write!(f, "Docker API 请求失败 ({}): {}", status, msg)
+            }
         }
     }
 }
@@ -32,7 +38,7 @@ impl Error for DockerError {}
 impl DockerCommand {
     /// 检查Docker是否已安装
     pub fn check_installed() -> Result<bool, DockerError> {
-        match Command::new("docker").arg("--version").output() {
+        match Self::command().arg("--version").output() {
             Ok(output) => Ok(output.status.success()),
             Err(_) => Err(DockerError::DockerNotInstalled),
         }
@@ -40,7 +46,7 @@ impl DockerCommand {
 
     /// 获取Docker版本信息
     pub fn version() -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("--version")
             .output()
             .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
@@ -57,7 +63,7 @@ impl DockerCommand {
 
     /// 获取Docker系统信息
     pub fn info() -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("info")
             .output()
             .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
@@ -74,7 +80,7 @@ impl DockerCommand {
 
     /// 列出所有容器
     pub fn ps_all() -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("ps")
             .arg("-a")
             .arg("--format")
@@ -94,7 +100,7 @@ impl DockerCommand {
 
     /// 列出运行中的容器
     pub fn ps_running() -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("ps")
             .arg("--format")
             .arg("table {{.Names}}\t{{.Status}}\t{{.Image}}")
@@ -113,7 +119,7 @@ impl DockerCommand {
 
     /// 检查容器是否存在
     pub fn container_exists(name: &str) -> Result<bool, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("ps")
             .arg("-a")
             .arg("--filter")
@@ -134,7 +140,7 @@ impl DockerCommand {
 
     /// 检查容器是否正在运行
     pub fn container_running(name: &str) -> Result<bool, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("ps")
             .arg("--filter")
             .arg(format!("name=^{}$", name))
@@ -156,7 +162,7 @@ impl DockerCommand {
 
     /// 启动容器
     pub fn start_container(name: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("start")
             .arg(name)
             .output()
@@ -175,7 +181,7 @@ impl DockerCommand {
 
     /// 停止容器
     pub fn stop_container(name: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("stop")
             .arg(name)
             .output()
@@ -194,7 +200,7 @@ impl DockerCommand {
 
     /// 重启容器
     pub fn restart_container(name: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("restart")
             .arg(name)
             .output()
@@ -213,7 +219,7 @@ impl DockerCommand {
 
     /// 在容器中执行命令
     pub fn exec_command(container: &str, command: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("exec")
             .arg(container)
             .arg("sh")
@@ -238,7 +244,7 @@ impl DockerCommand {
     pub fn exec_command_stream(container: &str, command: &str) -> Result<(), DockerError> {
         use std::process::Stdio;
 
-        let mut cmd = Command::new("docker");
+        let mut cmd = Self::command();
         cmd.arg("exec")
             .arg(container)
             .arg("sh")
@@ -265,7 +271,7 @@ impl DockerCommand {
     pub fn exec_interactive(container: &str, shell: &str) -> Result<(), DockerError> {
         use std::process::Stdio;
 
-        let mut cmd = Command::new("docker");
+        let mut cmd = Self::command();
         cmd.arg("exec")
             .arg("-it")
             .arg(container)
@@ -290,7 +296,7 @@ impl DockerCommand {
 
     /// 获取容器日志
     pub fn logs(container: &str, tail: Option<u32>) -> Result<String, DockerError> {
-        let mut cmd = Command::new("docker");
+        let mut cmd = Self::command();
         cmd.arg("logs");
 
         if let Some(lines) = tail {
@@ -317,7 +323,7 @@ impl DockerCommand {
 
     /// 获取容器状态信息
     pub fn inspect(container: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("inspect")
             .arg(container)
             .output()
@@ -337,7 +343,7 @@ impl DockerCommand {
 
     /// 删除容器
     pub fn rm_container(name: &str, force: bool) -> Result<String, DockerError> {
-        let mut cmd = Command::new("docker");
+        let mut cmd = Self::command();
         cmd.arg("rm");
 
         if force {
@@ -363,7 +369,7 @@ impl DockerCommand {
 
     /// 构建镜像
     pub fn build_image(path: &str, tag: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("build")
             .arg("-t")
             .arg(tag)
@@ -384,7 +390,7 @@ impl DockerCommand {
 
     /// 拉取镜像
     pub fn pull_image(image: &str) -> Result<String, DockerError> {
-        let output = Command::new("docker")
+        let output = Self::command()
             .arg("pull")
             .arg(image)
             .output()
@@ -410,7 +416,7 @@ impl DockerCommand {
         env: Option<&str>,
         detach: bool,
     ) -> Result<String, DockerError> {
-        let mut cmd = Command::new("docker");
+        let mut cmd = Self::command();
         cmd.arg("run");
 
         if detach {
@@ -449,45 +455,58 @@ impl DockerCommand {
 
     /// 启动开发环境，实时输出结果
     pub fn start_environment_stream(detach: bool) -> Result<(), DockerError> {
-        use std::process::Stdio;
+        let project =
+            ComposeProject::active().map_err(|e| DockerError::CommandFailed(e.to_string()))?;
 
         // 检查 docker-compose.yml 文件是否存在
-        let compose_file = std::env::current_dir()
-            .map_err(|e| DockerError::CommandFailed(e.to_string()))?
-            .join(".mdde")
-            .join("docker-compose.yml");
-        if !compose_file.exists() {
+        if !project.compose_file.exists() {
             return Err(DockerError::CommandFailed(
                 "docker-compose.yml 文件不存在".to_string(),
             ));
         }
 
         // 检查 .mdde/cfg.env 文件是否存在
-        let env_file = std::env::current_dir()
-            .map_err(|e| DockerError::CommandFailed(e.to_string()))?
-            .join(".mdde")
-            .join("cfg.env");
-        if !env_file.exists() {
+        if !project.env_file.exists() {
             return Err(DockerError::CommandFailed(
                 ".mdde/cfg.env 文件不存在".to_string(),
             ));
         }
 
-        // 构建 docker-compose 命令
-        let mut cmd = Command::new("docker-compose");
-        cmd.arg("--env-file")
-            .arg(".mdde/cfg.env")
-            .arg("--file")
-            .arg(".mdde/docker-compose.yml");
-
         if detach {
-            cmd.arg("up").arg("-d");
+            Self::compose_stream(&project, &["up", "-d"])
         } else {
-            cmd.arg("up");
+            Self::compose_stream(&project, &["up"])
         }
+    }
 
-        // 设置实时输出
-        cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    /// 构建当前容器引擎的命令
+    pub fn command() -> Command {
+        container_engine::current().command()
+    }
+
+    /// 构建 compose 命令，在项目根目录下执行并附带项目名称、环境变量文件、compose 文件和覆盖文件
+    fn compose_command(flavor: ComposeFlavor, project: &ComposeProject) -> Command {
+        let mut cmd = flavor.command(container_engine::current());
+        cmd.current_dir(&project.root)
+            .arg("--project-name")
+            .arg(project.name())
+            .arg("--env-file")
+            .arg(&project.env_file);
+        for file in project.files() {
+            cmd.arg("--file").arg(file);
+        }
+        cmd
+    }
+
+    /// 执行 compose 子命令，实时输出结果
+    pub fn compose_stream(project: &ComposeProject, args: &[&str]) -> Result<(), DockerError> {
+        use std::process::Stdio;
+
+        let flavor = compose::flavor()?;
+        let mut cmd = Self::compose_command(flavor, project);
+        cmd.args(args)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
 
         let status = cmd
             .status()
@@ -497,11 +516,29 @@ impl DockerCommand {
             Ok(())
         } else {
             Err(DockerError::CommandFailed(format!(
-                "启动环境失败，退出代码: {}",
+                "{} {} 执行失败，退出代码: {}",
+                flavor.describe(container_engine::current()),
+                args.join(" "),
                 status.code().unwrap_or(-1)
             )))
         }
     }
+
+    /// 执行 compose 子命令并返回标准输出
+    pub fn compose_output(project: &ComposeProject, args: &[&str]) -> Result<String, DockerError> {
+        let output = Self::compose_command(compose::flavor()?, project)
+            .args(args)
+            .output()
+            .map_err(|e| DockerError::CommandFailed(e.to_string()))?;
+
+        if output.status.success() {
+            String::from_utf8(output.stdout)
+                .map_err(|e| DockerError::OutputParseFailed(e.to_string()))
+        } else {
+            let error_msg = String::from_utf8_lossy(&output.stderr);
+            Err(DockerError::CommandFailed(error_msg.trim().to_string()))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -516,6 +553,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_unwrap)]
     fn test_docker_version() {
         let result = DockerCommand::version();
         // 这个测试可能失败，取决于系统是否安装了Docker
@@ -524,4 +562,20 @@ mod tests {
             assert!(version.contains("Docker"));
         }
     }
+
+    #[test]
+    fn test_compose_command_passes_project_name() {
+        let project = ComposeProject::in_environment(std::path::Path::new("/work/api"), "dev");
+        let cmd = DockerCommand::compose_command(ComposeFlavor::Plugin, &project);
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            args.windows(2)
+                .any(|pair| pair[0] == "--project-name" && pair[1] == project.name()),
+            "{:?}",
+            args
+        );
+    }
 }
@@ -0,0 +1,741 @@
+use crate::error::MddeError;
+use crate::i18n;
+use std::collections::HashMap;
+use std::fmt;
+
+/// 环境变量文件（`cfg.env`）的文档模型
+///
+/// 语法与 compose 的 `--env-file` 一致：支持 `export` 前缀、单引号和双引号、
+/// 双引号中的转义、跨行的引号值、行内注释以及 `${VAR:-default}` 形式的变量插值。
+/// 保留注释、空行、键的顺序和未知的键，只修改被设置或删除的行，
+/// 未修改时输出与原文件逐字节相同。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvFile {
+    lines: Vec<Line>,
+}
+
+/// 文件中的一个变量定义或一行其他内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// `prefix` 为值之前的部分（含 `export`、键和等号），`literal` 为值在文件中的原文，
+    /// `suffix` 为值之后的空白、行内注释和换行符。引号值可以跨越多行。
+    Entry {
+        key: String,
+        value: String,
+        prefix: String,
+        literal: String,
+        suffix: String,
+    },
+    /// 空行或注释，包含换行符
+    Other(String),
+    /// 宽松解析时有语法错误的一行，`key` 为能识别出的变量名
+    Invalid { key: Option<String>, raw: String },
+}
+
+impl Line {
+    fn key(&self) -> Option<&str> {
+        match self {
+            Line::Entry { key, .. } => Some(key),
+            Line::Invalid { key, .. } => key.as_deref(),
+            Line::Other(_) => None,
+        }
+    }
+
+    fn ends_with_newline(&self) -> bool {
+        match self {
+            Line::Entry { suffix, .. } => suffix.ends_with('\n'),
+            Line::Other(raw) | Line::Invalid { raw, .. } => raw.ends_with('\n'),
+        }
+    }
+
+    fn push_newline(&mut self) {
+        match self {
+            Line::Entry { suffix, .. } => suffix.push('\n'),
+            Line::Other(raw) | Line::Invalid { raw, .. } => raw.push('\n'),
+        }
+    }
+}
+
+impl EnvFile {
+    /// 解析文件内容，插值时先查找文件中前面定义的变量，再查找进程环境变量
+    pub fn parse(content: &str) -> Result<Self, MddeError> {
+        Self::parse_with(content, |name| std::env::var(name).ok())
+    }
+
+    /// 解析文件内容，`lookup` 用于查找文件中没有定义的变量
+    pub fn parse_with(
+        content: &str,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, MddeError> {
+        let (env, mut errors) = Self::parse_lines(content, &lookup, false);
+        match errors.pop() {
+            Some(error) => Err(error),
+            None => Ok(env),
+        }
+    }
+
+    /// 宽松解析：有语法错误的行原样保留，不参与取值，仍然可以按键删除或覆盖，
+    /// 同时返回所有语法错误。用于查看和编辑 `cfg.env` 的命令，使它们能修复有错误的文件。
+    pub fn parse_lenient(content: &str) -> (Self, Vec<MddeError>) {
+        Self::parse_lines(content, &|name| std::env::var(name).ok(), true)
+    }
+
+    /// 逐行解析，`lenient` 为 false 时遇到第一个错误即停止
+    fn parse_lines(
+        content: &str,
+        lookup: &dyn Fn(&str) -> Option<String>,
+        lenient: bool,
+    ) -> (Self, Vec<MddeError>) {
+        let mut lines = Vec::new();
+        let mut errors = Vec::new();
+        let mut defined: HashMap<String, String> = HashMap::new();
+        let mut pos = 0;
+        let mut line_no = 1;
+
+        while pos < content.len() {
+            let end = line_end(content, pos);
+            let line = &content[pos..end];
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                lines.push(Line::Other(line.to_string()));
+                pos = end;
+                line_no += 1;
+                continue;
+            }
+
+            let lookup = |name: &str| defined.get(name).cloned().or_else(|| lookup(name));
+            match parse_entry(content, pos, &lookup) {
+                Ok(entry) => {
+                    let Line::Entry {
+                        key,
+                        value,
+                        prefix,
+                        literal,
+                        suffix,
+                    } = &entry
+                    else {
+                        unreachable!("parse_entry 只返回变量定义");
+                    };
+                    let next = pos + prefix.len() + literal.len() + suffix.len();
+                    line_no += content[pos..next].matches('\n').count();
+                    pos = next;
+                    defined.insert(key.clone(), value.clone());
+                    lines.push(entry);
+                }
+                Err((key, detail)) => {
+                    errors.push(MddeError::Config(i18n::tf(
+                        "env_syntax_error",
+                        &[&line_no, &detail],
+                    )));
+                    if !lenient {
+                        break;
+                    }
+                    lines.push(Line::Invalid {
+                        key,
+                        raw: line.to_string(),
+                    });
+                    pos = end;
+                    line_no += 1;
+                }
+            }
+        }
+
+        (Self { lines }, errors)
+    }
+
+    /// 获取变量插值后的值，重复定义时以最后一个为准
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().rev().find_map(|line| match line {
+            Line::Entry { key: k, value, .. } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// 设置变量：已存在时只替换值，保留 `export`、等号两侧的空白和行内注释；
+    /// 最后一个定义有语法错误时整行替换；不存在时追加到文件末尾。
+    /// 值中包含特殊字符时写为双引号字符串。
+    pub fn set(&mut self, key: &str, value: &str) {
+        let existing = self
+            .lines
+            .iter_mut()
+            .rev()
+            .find(|line| line.key() == Some(key));
+        match existing {
+            Some(Line::Entry {
+                value: old,
+                literal,
+                ..
+            }) => {
+                if old != value {
+                    *literal = quote(value);
+                    *old = value.to_string();
+                }
+            }
+            Some(line) => {
+                let newline = if line.ends_with_newline() { "\n" } else { "" };
+                *line = Line::Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    prefix: format!("{}=", key),
+                    literal: quote(value),
+                    suffix: newline.to_string(),
+                };
+            }
+            None => {
+                if let Some(last) = self.lines.last_mut() {
+                    if !last.ends_with_newline() {
+                        last.push_newline();
+                    }
+                }
+                self.lines.push(Line::Entry {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                    prefix: format!("{}=", key),
+                    literal: quote(value),
+                    suffix: "\n".to_string(),
+                });
+            }
+        }
+    }
+
+    /// 删除变量的所有定义（包括有语法错误的行），返回删除前生效的值；
+    /// 只有错误的定义时返回该行的原文
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let value = self.get(key).map(str::to_string).or_else(|| {
+            self.lines.iter().rev().find_map(|line| match line {
+                Line::Invalid { key: Some(k), raw } if k == key => Some(raw.trim().to_string()),
+                _ => None,
+            })
+        });
+        self.lines.retain(|line| line.key() != Some(key));
+        value
+    }
+
+    /// 按文件中的顺序遍历变量，重复定义的变量只返回生效的一个
+    pub fn entries(&self) -> Vec<(&str, &str)> {
+        let mut entries: Vec<(&str, &str)> = Vec::new();
+        for line in &self.lines {
+            if let Line::Entry { key, value, .. } = line {
+                match entries.iter_mut().find(|(k, _)| k == key) {
+                    Some(entry) => entry.1 = value,
+                    None => entries.push((key, value)),
+                }
+            }
+        }
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(|line| line.key().is_none())
+    }
+
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.entries()
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+}
+
+impl fmt::Display for EnvFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Entry {
+                    prefix,
+                    literal,
+                    suffix,
+                    ..
+                } => write!(f, "{}{}{}", prefix, literal, suffix)?,
+                Line::Other(raw) | Line::Invalid { raw, .. } => f.write_str(raw)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 解析从 `pos` 开始的一个变量定义，出错时返回已识别出的键和错误说明
+fn parse_entry(
+    content: &str,
+    pos: usize,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<Line, (Option<String>, String)> {
+    let end = line_end(content, pos);
+    let line = &content[pos..end];
+
+    let Some(eq) = line.find('=') else {
+        return Err((None, i18n::tf("env_expected_assignment", &[&line.trim()])));
+    };
+    let key = line[..eq].trim();
+    let key = key
+        .strip_prefix("export")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .map_or(key, str::trim_start);
+    if !is_valid_key(key) {
+        return Err((None, i18n::tf("env_invalid_key", &[&key])));
+    }
+    let error = |detail: String| (Some(key.to_string()), detail);
+
+    let after = &line[eq + 1..];
+    let spaced = after.starts_with([' ', '\t']);
+    let value_start = end - after.trim_start_matches([' ', '\t']).len();
+    let prefix = &content[pos..value_start];
+
+    let (value, value_end) = match content[value_start..].chars().next() {
+        Some(quote @ ('\'' | '"')) => {
+            let body_start = value_start + 1;
+            let close = find_closing_quote(&content[body_start..], quote)
+                .map(|i| body_start + i)
+                .ok_or_else(|| error(i18n::t("env_unterminated_quote").to_string()))?;
+            let body = &content[body_start..close];
+            let value = if quote == '\'' {
+                body.to_string()
+            } else {
+                expand(body, true, lookup).map_err(error)?
+            };
+            (value, close + 1)
+        }
+        _ => {
+            let text = content[value_start..end].trim_end_matches(['\n', '\r']);
+            let comment = text
+                .char_indices()
+                .find(|&(i, c)| {
+                    c == '#'
+                        && if i == 0 {
+                            spaced
+                        } else {
+                            text[..i].ends_with([' ', '\t'])
+                        }
+                })
+                .map_or(text.len(), |(i, _)| i);
+            let literal = text[..comment].trim_end();
+            let value = expand(literal, false, lookup).map_err(error)?;
+            (value, value_start + literal.len())
+        }
+    };
+
+    let suffix_end = line_end(content, value_end);
+    let suffix = &content[value_end..suffix_end];
+    let rest = suffix.trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(error(i18n::tf("env_unexpected_characters", &[&rest])));
+    }
+
+    Ok(Line::Entry {
+        key: key.to_string(),
+        value,
+        prefix: prefix.to_string(),
+        literal: content[value_start..value_end].to_string(),
+        suffix: suffix.to_string(),
+    })
+}
+
+/// 从 `pos` 开始的这一行的结束位置（包含换行符）
+fn line_end(content: &str, pos: usize) -> usize {
+    content[pos..]
+        .find('\n')
+        .map_or(content.len(), |i| pos + i + 1)
+}
+
+/// 变量名只能包含字母、数字、`_`、`.` 和 `-`
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// 查找未转义的结束引号，单引号中没有转义
+fn find_closing_quote(body: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quote == '"' && c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// 按 compose 文件的规则展开变量插值：`$$` 表示字面量 `$`，没有转义字符
+pub fn interpolate(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, MddeError> {
+    expand(s, false, lookup).map_err(MddeError::Config)
+}
+
+/// 展开变量插值，`$$` 表示字面量 `$`；`escapes` 为 true 时（双引号中）同时处理转义字符
+fn expand(
+    s: &str,
+    escapes: bool,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next() {
+                Some((_, 'n')) => output.push('\n'),
+                Some((_, 'r')) => output.push('\r'),
+                Some((_, 't')) => output.push('\t'),
+                Some((_, c @ ('\\' | '"' | '$'))) => output.push(c),
+                Some((_, c)) => {
+                    output.push('\\');
+                    output.push(c);
+                }
+                None => output.push('\\'),
+            },
+            '$' => match chars.peek() {
+                Some(&(_, '$')) => {
+                    chars.next();
+                    output.push('$');
+                }
+                Some(&(start, '{')) => {
+                    let end = find_closing_brace(&s[start..])
+                        .map(|offset| start + offset)
+                        .ok_or_else(|| i18n::tf("env_bad_substitution", &[&&s[i..]]))?;
+                    output.push_str(&substitute(&s[i..=end], &s[start + 1..end], lookup)?);
+                    while chars.peek().is_some_and(|&(j, _)| j <= end) {
+                        chars.next();
+                    }
+                }
+                Some(&(start, c)) if c.is_ascii_alphabetic() || c == '_' => {
+                    let name_len = s[start..]
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(s.len() - start);
+                    output.push_str(&lookup(&s[start..start + name_len]).unwrap_or_default());
+                    while chars.peek().is_some_and(|&(j, _)| j < start + name_len) {
+                        chars.next();
+                    }
+                }
+                _ => output.push('$'),
+            },
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+/// 查找与 `{` 匹配的 `}`，允许默认值中嵌套 `${...}`
+fn find_closing_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 展开 `${...}` 的内容：`NAME`、`NAME:-default`、`NAME-default`、`NAME:?error`、
+/// `NAME?error`、`NAME:+alternative` 和 `NAME+alternative`
+fn substitute(
+    expression: &str,
+    inner: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let name_len = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(inner.len());
+    let (name, modifier) = inner.split_at(name_len);
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(i18n::tf("env_bad_substitution", &[&expression]));
+    }
+
+    let value = lookup(name);
+    let (colon, modifier) = match modifier.strip_prefix(':') {
+        Some(modifier) => (true, modifier),
+        None => (false, modifier),
+    };
+    // 带冒号时空值视为未设置
+    let set = value.as_deref().is_some_and(|v| !colon || !v.is_empty());
+
+    let operator = modifier.chars().next();
+    let operand = || {
+        expand(
+            &modifier[operator.map_or(0, char::len_utf8)..],
+            false,
+            lookup,
+        )
+    };
+    match operator {
+        None if !colon => Ok(value.unwrap_or_default()),
+        Some('-') if set => Ok(value.unwrap_or_default()),
+        Some('-') => operand(),
+        Some('?') if set => Ok(value.unwrap_or_default()),
+        Some('?') => {
+            let message = operand()?;
+            Err(i18n::tf("env_required_variable", &[&name, &message]))
+        }
+        Some('+') if set => operand(),
+        Some('+') => Ok(String::new()),
+        _ => Err(i18n::tf("env_bad_substitution", &[&expression])),
+    }
+}
+
+/// 将值写为文件中的原文，包含特殊字符时使用双引号并转义
+fn quote(value: &str) -> String {
+    let plain = value.chars().all(|c| {
+        !c.is_whitespace() && !c.is_control() && !matches!(c, '#' | '\'' | '"' | '$' | '\\')
+    });
+    if plain {
+        return value.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\\' | '"' | '$' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "# 服务器地址\nhost = http://intranet:3000\n\n# 自定义变量\nDB_URL=postgres://db\r\ncontainer_name=demo # 容器名\n  # 缩进的注释\nexport TOKEN='a b'\nworkspace=/work";
+
+    fn parse(content: &str) -> EnvFile {
+        EnvFile::parse_with(content, |name| {
+            (name == "HOME").then(|| "/home/dev".to_string())
+        })
+        .unwrap()
+    }
+
+    fn syntax_error(content: &str) -> String {
+        match EnvFile::parse_with(content, |_| None) {
+            Err(MddeError::Config(message)) => message,
+            other => panic!("{:?} should be a syntax error, got {:?}", content, other),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_is_byte_identical() {
+        for content in [
+            CONTENT,
+            "",
+            "\n\n",
+            "a=1",
+            "a=1\n",
+            "# only comment",
+            "a=\"multi\nline\"\nb=2\n",
+        ] {
+            assert_eq!(parse(content).to_string(), content);
+        }
+
+        // 设置为相同的值不会改变文件
+        let mut env = parse(CONTENT);
+        env.set("host", "http://intranet:3000");
+        env.set("TOKEN", "a b");
+        assert_eq!(env.to_string(), CONTENT);
+    }
+
+    #[test]
+    fn test_set_preserves_layout() {
+        let mut env = parse(CONTENT);
+        env.set("host", "http://other");
+        env.set("DB_URL", "postgres://other");
+        env.set("container_name", "api");
+        env.set("TOKEN", "it's $secret");
+        env.set("app_port", "8080");
+
+        assert_eq!(
+            env.to_string(),
+            "# 服务器地址\nhost = http://other\n\n# 自定义变量\nDB_URL=postgres://other\r\ncontainer_name=api # 容器名\n  # 缩进的注释\nexport TOKEN=\"it's \\$secret\"\nworkspace=/work\napp_port=8080\n"
+        );
+        assert_eq!(env.get("host"), Some("http://other"));
+        assert_eq!(
+            env.entries()
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>(),
+            vec![
+                "host",
+                "DB_URL",
+                "container_name",
+                "TOKEN",
+                "workspace",
+                "app_port"
+            ]
+        );
+
+        // 写入的值重新解析后保持不变
+        for value in [
+            "it's $secret",
+            "line 1\nline 2",
+            "tab\there",
+            "back\\slash",
+            "#tag",
+            "",
+        ] {
+            env.set("TOKEN", value);
+            assert_eq!(parse(&env.to_string()).get("TOKEN"), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_remove_and_duplicates() {
+        let mut env = parse("a=1\n# keep\nb=2\na=3\n");
+        assert_eq!(env.get("a"), Some("3"));
+        assert_eq!(env.len(), 2);
+
+        env.set("a", "4");
+        assert_eq!(env.to_string(), "a=1\n# keep\nb=2\na=4\n");
+
+        assert_eq!(env.remove("a"), Some("4".to_string()));
+        assert_eq!(env.remove("missing"), None);
+        assert_eq!(env.to_string(), "# keep\nb=2\n");
+        assert!(!env.is_empty());
+
+        env.remove("b");
+        assert!(env.is_empty());
+        assert_eq!(env.to_string(), "# keep\n");
+    }
+
+    #[test]
+    fn test_quotes_escapes_and_comments() {
+        let env = parse(concat!(
+            "export EXPORTED=1\n",
+            "exporter=2\n",
+            "SINGLE='literal $HOME \\n # not a comment'\n",
+            "DOUBLE=\"tab\\there\\nnew \\\"quoted\\\" \\$HOME\" # comment\n",
+            "MULTI=\"first\n",
+            "second\"\n",
+            "UNQUOTED = value with spaces   # comment\n",
+            "HASH=a#b\n",
+            "EMPTY=\n",
+            "SPACED= # only a comment\n",
+            "dotted.key-name=ok\n",
+        ));
+
+        assert_eq!(env.get("EXPORTED"), Some("1"));
+        assert_eq!(env.get("exporter"), Some("2"));
+        assert_eq!(env.get("SINGLE"), Some("literal $HOME \\n # not a comment"));
+        assert_eq!(env.get("DOUBLE"), Some("tab\there\nnew \"quoted\" $HOME"));
+        assert_eq!(env.get("MULTI"), Some("first\nsecond"));
+        assert_eq!(env.get("UNQUOTED"), Some("value with spaces"));
+        assert_eq!(env.get("HASH"), Some("a#b"));
+        assert_eq!(env.get("EMPTY"), Some(""));
+        assert_eq!(env.get("SPACED"), Some(""));
+        assert_eq!(env.get("dotted.key-name"), Some("ok"));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let env = parse(concat!(
+            "BASE=/srv\n",
+            "EMPTY=\n",
+            "PLAIN=$BASE/app\n",
+            "BRACED=${BASE}_data\n",
+            "FROM_ENV=\"${HOME}/.cache\"\n",
+            "UNSET=[$MISSING]\n",
+            "DEFAULT=${MISSING:-fallback}\n",
+            "EMPTY_DEFAULT=${EMPTY:-fallback}\n",
+            "EMPTY_KEEP=${EMPTY-fallback}\n",
+            "NESTED=${MISSING:-${BASE}/nested}\n",
+            "ALT=${BASE:+alternative}\n",
+            "NO_ALT=${MISSING:+alternative}\n",
+            "DOLLAR=price$ 5\n",
+        ));
+
+        assert_eq!(env.get("PLAIN"), Some("/srv/app"));
+        assert_eq!(env.get("BRACED"), Some("/srv_data"));
+        assert_eq!(env.get("FROM_ENV"), Some("/home/dev/.cache"));
+        assert_eq!(env.get("UNSET"), Some("[]"));
+        assert_eq!(env.get("DEFAULT"), Some("fallback"));
+        assert_eq!(env.get("EMPTY_DEFAULT"), Some("fallback"));
+        assert_eq!(env.get("EMPTY_KEEP"), Some(""));
+        assert_eq!(env.get("NESTED"), Some("/srv/nested"));
+        assert_eq!(env.get("ALT"), Some("alternative"));
+        assert_eq!(env.get("NO_ALT"), Some(""));
+        assert_eq!(env.get("DOLLAR"), Some("price$ 5"));
+    }
+
+    #[test]
+    fn test_syntax_errors_report_line_numbers() {
+        let error = syntax_error("a=1\n\nnot a variable\n");
+        assert!(error.contains('3'), "{}", error);
+
+        let error = syntax_error("a=1\nb=\"unterminated\nc=3\n");
+        assert!(error.contains('2'), "{}", error);
+
+        let error = syntax_error("a=\"multi\nline\"\nbad key=1\n");
+        assert!(error.contains('3'), "{}", error);
+
+        for content in [
+            "=value",
+            "a='quoted' trailing",
+            "a=${UNCLOSED",
+            "a=${1BAD}",
+            "a=${NAME:x}",
+            "a=${REQUIRED:?must be set}",
+        ] {
+            syntax_error(content);
+        }
+        assert!(syntax_error("a=${REQUIRED:?must be set}").contains("must be set"));
+    }
+
+    #[test]
+    fn test_lenient_parse_keeps_invalid_lines_editable() {
+        let content = "a=1\nb=\"unterminated\nnot a variable\nc=3\n";
+        let (mut env, errors) = EnvFile::parse_lenient(content);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].to_string().contains('2'), "{}", errors[0]);
+        assert!(errors[1].to_string().contains('3'), "{}", errors[1]);
+        assert_eq!(env.to_string(), content);
+        assert_eq!(env.entries(), vec![("a", "1"), ("c", "3")]);
+
+        // 有错误的行可以按键删除或覆盖
+        assert_eq!(env.remove("b"), Some("b=\"unterminated".to_string()));
+        assert_eq!(env.to_string(), "a=1\nnot a variable\nc=3\n");
+
+        let (mut env, _) = EnvFile::parse_lenient("b=${UNCLOSED\na=1\n");
+        env.set("b", "fixed");
+        assert_eq!(env.to_string(), "b=fixed\na=1\n");
+        assert_eq!(
+            EnvFile::parse(&env.to_string()).unwrap().get("b"),
+            Some("fixed")
+        );
+    }
+
+    #[test]
+    fn test_double_dollar_is_literal() {
+        let env = parse(
+            "BASE=/srv\nESCAPED=$$BASE\nQUOTED=\"cost $$5 ${BASE}\"\nDEFAULT=${MISSING:-$$x}\n",
+        );
+        assert_eq!(env.get("ESCAPED"), Some("$BASE"));
+        assert_eq!(env.get("QUOTED"), Some("cost $5 /srv"));
+        assert_eq!(env.get("DEFAULT"), Some("$x"));
+
+        let lookup = |name: &str| (name == "BASE").then(|| "/srv".to_string());
+        assert_eq!(interpolate("$$BASE $BASE", &lookup).unwrap(), "$BASE /srv");
+    }
+}
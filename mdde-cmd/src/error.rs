@@ -44,6 +44,18 @@ pub enum MddeError {
     #[error("容器未运行: {0}")]
     ContainerNotRunning(String),
 
+    #[error("服务未就绪: {}", .0.join(", "))]
+    ServicesNotReady(Vec<String>),
+
+    #[error("端口已被占用: {}", .0.join(", "))]
+    PortConflict(Vec<String>),
+
+    #[error("模板文件不存在: {0}")]
+    TemplateNotFound(String),
+
+    #[error("完整性校验失败: {0}")]
+    IntegrityCheck(String),
+
     #[error("网络错误: {0}")]
     Network(String),
 
@@ -54,6 +66,16 @@ pub enum MddeError {
     Unknown(String),
 }
 
+impl MddeError {
+    /// 模板文件不存在：HTTP 来源返回 404 或本地来源中没有该文件
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            MddeError::HttpStatus(404) | MddeError::TemplateNotFound(_)
+        )
+    }
+}
+
 impl From<String> for MddeError {
     fn from(err: String) -> Self {
         MddeError::Unknown(err)
@@ -1,11 +1,17 @@
 use crate::commands::create::DevEnvironment;
 use crate::error::MddeError;
-use reqwest::Client;
-use tracing::info;
+use crate::i18n;
+use crate::template_cache::{TemplateCache, Validators};
+use colored::*;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use tracing::{info, warn};
 
 pub struct MddeClient {
     base_url: String,
     client: Client,
+    cache: Option<TemplateCache>,
+    offline: bool,
 }
 
 impl MddeClient {
@@ -13,9 +19,18 @@ impl MddeClient {
         Self {
             base_url: base_url.to_string(),
             client: Client::new(),
+            cache: None,
+            offline: false,
         }
     }
 
+    /// 使用本地缓存，`offline` 时只从缓存读取，不访问服务器
+    pub fn with_cache(mut self, cache: Option<TemplateCache>, offline: bool) -> Self {
+        self.cache = cache;
+        self.offline = offline;
+        self
+    }
+
     /// 获取基础URL
     pub fn get_base_url(&self) -> &str {
         &self.base_url
@@ -27,17 +42,109 @@ impl MddeClient {
         directory: &str,
         filename: &str,
     ) -> Result<String, MddeError> {
-        let url = format!("{}/{}/{}", self.base_url, directory, filename);
-        info!("下载脚本: {}", url);
-        println!("下载脚本-print: {}", url);
+        let path = format!("{}/{}", directory, filename);
+        info!("下载脚本: {}/{}", self.base_url, path);
+        println!("下载脚本-print: {}/{}", self.base_url, path);
 
-        let response = self.client.get(&url).send().await?;
+        self.fetch(&path).await
+    }
 
-        if response.status().is_success() {
-            let content = response.text().await?;
-            Ok(content)
-        } else {
-            Err(MddeError::HttpStatus(response.status().as_u16()))
+    /// 获取服务器上的文件
+    ///
+    /// 有缓存时带上 ETag/Last-Modified 重新验证，服务器返回 304 时使用缓存；
+    /// 网络错误或服务器错误时退回到缓存。离线模式只读取缓存。
+    async fn fetch(&self, path: &str) -> Result<String, MddeError> {
+        let url = format!("{}/{}", self.base_url, path);
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.lookup(&self.base_url, path));
+
+        if self.offline {
+            return match cached {
+                Some(cached) => cached.into_content(),
+                None => Err(MddeError::Network(i18n::tf("template_not_cached", &[&url]))),
+            };
+        }
+
+        let mut request = self.client.get(&url).header("User-Agent", "mdde-cmd/1.0");
+        if let Some(entry) = cached.as_ref().map(|cached| &cached.entry) {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return match cached {
+                    Some(cached) => {
+                        println!(
+                            "{}",
+                            i18n::tf("template_cache_fallback", &[&url, &e]).yellow()
+                        );
+                        cached.into_content()
+                    }
+                    None => Err(e.into()),
+                }
+            }
+        };
+
+        let status = response.status();
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+
+        match (status, cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => {
+                info!("缓存仍然有效: {}", url);
+                // 304 响应可以不带验证信息，沿用缓存中的
+                let validators = Validators {
+                    etag: validators.etag.or(cached.entry.etag.clone()),
+                    last_modified: validators
+                        .last_modified
+                        .or(cached.entry.last_modified.clone()),
+                };
+                self.store(path, cached.content.as_deref(), validators);
+                cached.into_content()
+            }
+            (status, _) if status.is_success() => {
+                let content = response.text().await?;
+                self.store(path, Some(&content), validators);
+                Ok(content)
+            }
+            (StatusCode::NOT_FOUND, _) => {
+                self.store(path, None, validators);
+                Err(MddeError::HttpStatus(404))
+            }
+            (status, Some(cached)) if status.is_server_error() => {
+                println!(
+                    "{}",
+                    i18n::tf("template_cache_fallback", &[&url, &status]).yellow()
+                );
+                cached.into_content()
+            }
+            (status, _) => Err(MddeError::HttpStatus(status.as_u16())),
+        }
+    }
+
+    /// 写入缓存，写入失败只记录日志
+    fn store(&self, path: &str, content: Option<&str>, validators: Validators) {
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.store(&self.base_url, path, content, validators) {
+                warn!("写入模板缓存失败: {}", e);
+            }
         }
     }
 
@@ -136,34 +243,19 @@ impl MddeClient {
         println!("获取开发环境列表-print: {}", url);
         info!("获取开发环境列表: {}", url);
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Accept", "application/json")
-            .header("User-Agent", "mdde-cmd/1.0")
-            .send()
-            .await?;
+        let text = self.fetch("index.json").await?;
 
-        if response.status().is_success() {
-            // 先获取文本内容进行调试
-            let text = response.text().await?;
-            //println!("响应内容: {}", text);
-
-            // 尝试解析 JSON
-            match serde_json::from_str::<Vec<DevEnvironment>>(&text) {
-                Ok(environments) => {
-                    println!("成功解析环境列表，共 {} 个环境", environments.len());
-                    Ok(environments)
-                }
-                Err(e) => {
-                    println!("JSON 解析失败: {}", e);
-                    println!("原始响应内容: '{}'", text);
-                    Err(MddeError::HttpStatus(400)) // 返回解析错误
-                }
+        // 尝试解析 JSON
+        match serde_json::from_str::<Vec<DevEnvironment>>(&text) {
+            Ok(environments) => {
+                println!("成功解析环境列表，共 {} 个环境", environments.len());
+                Ok(environments)
+            }
+            Err(e) => {
+                println!("JSON 解析失败: {}", e);
+                println!("原始响应内容: '{}'", text);
+                Err(MddeError::HttpStatus(400)) // 返回解析错误
             }
-        } else {
-            println!("HTTP 请求失败，状态码: {}", response.status());
-            Err(MddeError::HttpStatus(response.status().as_u16()))
         }
     }
 }
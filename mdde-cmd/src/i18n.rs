@@ -108,13 +108,87 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
         messages.insert("containers_clean_completed", ("✓ Containers cleanup completed".to_string(), "✓ 容器清理完成".to_string()));
         messages.insert("clean_volumes", ("Cleaning unused volumes...".to_string(), "清理未使用的卷...".to_string()));
         messages.insert("volumes_clean_completed", ("✓ Volumes cleanup completed".to_string(), "✓ 卷清理完成".to_string()));
+        messages.insert("clean_scope_managed", ("Only resources labelled by mdde are cleaned, use --global to clean every unused resource on this machine".to_string(), "只清理带有 mdde 标签的资源，使用 --global 清理本机所有未使用的资源".to_string()));
+        messages.insert("clean_scope_global", ("⚠ Cleaning every unused resource on this machine, including ones not created by mdde".to_string(), "⚠ 将清理本机所有未使用的资源，包括不是由 mdde 创建的资源".to_string()));
         messages.insert("specify_resource_type", ("Please specify the resource type to clean".to_string(), "请指定要清理的资源类型".to_string()));
         messages.insert("use_all_flag", ("Use --all to clean all resources".to_string(), "使用 --all 清理所有资源".to_string()));
         messages.insert("use_images_flag", ("Use --images to clean images".to_string(), "使用 --images 清理镜像".to_string()));
         messages.insert("use_containers_flag", ("Use --containers to clean containers".to_string(), "使用 --containers 清理容器".to_string()));
         messages.insert("use_volumes_flag", ("Use --volumes to clean volumes".to_string(), "使用 --volumes 清理卷".to_string()));
+        messages.insert("clean_nothing", ("✓ Nothing to clean".to_string(), "✓ 没有需要清理的资源".to_string()));
+        messages.insert("clean_reclaimable_total", ("{} resources, {} reclaimable".to_string(), "共 {} 项资源，可回收 {}".to_string()));
+        messages.insert("clean_dry_run_hint", ("Dry run, nothing was removed. Run without --dry-run to clean".to_string(), "仅预览，未删除任何资源。去掉 --dry-run 以执行清理".to_string()));
+        messages.insert("clean_confirm_prompt", ("Remove the resources above? [y/N]: ".to_string(), "是否删除以上资源? [y/N]: ".to_string()));
+        messages.insert("clean_confirm_required", ("confirmation needs an interactive terminal, use --yes to clean without confirming".to_string(), "需要在交互式终端中确认，使用 --yes 跳过确认直接清理".to_string()));
+        messages.insert("clean_cancelled", ("Cleanup cancelled".to_string(), "已取消清理".to_string()));
+        messages.insert("destroy_summary", ("The following will be destroyed for environment {} ({}):".to_string(), "将销毁环境 {} ({})：".to_string()));
+        messages.insert("destroy_containers", ("  Containers: {}".to_string(), "  容器: {}".to_string()));
+        messages.insert("destroy_volumes", ("  Volumes: {}".to_string(), "  卷: {}".to_string()));
+        messages.insert("destroy_images", ("  Images: {}".to_string(), "  镜像: {}".to_string()));
+        messages.insert("destroy_networks", ("  Networks: {}".to_string(), "  网络: {}".to_string()));
+        messages.insert("destroy_foreign_resources", ("compose project {} contains resources that do not belong to this environment, refusing to destroy: {}".to_string(), "compose 项目 {} 中有不属于该环境的资源，拒绝销毁: {}".to_string()));
+        messages.insert("destroy_none", ("none".to_string(), "无".to_string()));
+        messages.insert("destroy_files_delete", ("  Files: delete {}".to_string(), "  文件: 删除 {}".to_string()));
+        messages.insert("destroy_files_archive", ("  Files: archive to {}, then delete {}".to_string(), "  文件: 打包到 {} 后删除 {}".to_string()));
+        messages.insert("destroy_files_keep", ("  Files: keep {}".to_string(), "  文件: 保留 {}".to_string()));
+        messages.insert("destroy_gitignore", ("  .gitignore: remove the .mdde/ entry".to_string(), "  .gitignore: 移除 .mdde/ 条目".to_string()));
+        messages.insert("destroy_confirm_prompt", ("Destroy this environment? This cannot be undone [y/N]: ".to_string(), "是否销毁该环境? 此操作无法撤销 [y/N]: ".to_string()));
+        messages.insert("destroy_confirm_required", ("confirmation needs an interactive terminal, use --yes to destroy without confirming".to_string(), "需要在交互式终端中确认，使用 --yes 跳过确认直接销毁".to_string()));
+        messages.insert("destroy_cancelled", ("Destroy cancelled".to_string(), "已取消销毁".to_string()));
+        messages.insert("destroying_environment", ("Destroying environment...".to_string(), "正在销毁环境...".to_string()));
+        messages.insert("destroy_container_failed", ("⚠ Failed to remove container {}: {}".to_string(), "⚠ 删除容器 {} 失败: {}".to_string()));
+        messages.insert("destroy_volume_failed", ("⚠ Failed to remove volume {}: {}".to_string(), "⚠ 删除卷 {} 失败: {}".to_string()));
+        messages.insert("destroy_network_failed", ("⚠ Failed to remove network {}: {}".to_string(), "⚠ 删除网络 {} 失败: {}".to_string()));
+        messages.insert("destroy_image_failed", ("⚠ Failed to remove image {}: {}".to_string(), "⚠ 删除镜像 {} 失败: {}".to_string()));
+        messages.insert("destroy_archived", ("✓ Environment files archived to {}".to_string(), "✓ 环境文件已打包到 {}".to_string()));
+        messages.insert("destroy_completed", ("✓ Environment {} destroyed".to_string(), "✓ 环境 {} 已销毁".to_string()));
+        messages.insert("template_not_cached", ("{} is not in the template cache, run without --offline to download it".to_string(), "模板缓存中没有 {}，去掉 --offline 以下载".to_string()));
+        messages.insert("template_cache_fallback", ("⚠ Could not reach {} ({}), using the cached copy".to_string(), "⚠ 无法访问 {} ({})，使用缓存的版本".to_string()));
+        messages.insert("invalid_template_path", ("Invalid template path: {}".to_string(), "无效的模板路径: {}".to_string()));
+        messages.insert("cache_dir_unknown", ("Cannot determine the cache directory, set MDDE_CACHE_DIR".to_string(), "无法确定缓存目录，请设置 MDDE_CACHE_DIR".to_string()));
+        messages.insert("cache_empty", ("The template cache is empty ({})".to_string(), "模板缓存为空 ({})".to_string()));
+        messages.insert("cache_cleared", ("✓ Removed {} cached files ({})".to_string(), "✓ 已删除 {} 个缓存文件 ({})".to_string()));
+        messages.insert("invalid_template_source", ("Invalid template source: {} (expected name=http(s)://... or name=directory)".to_string(), "无效的模板来源: {} (格式为 name=http(s)://... 或 name=目录)".to_string()));
+        messages.insert("unknown_template_source", ("Unknown template source: {}".to_string(), "未知的模板来源: {}".to_string()));
+        messages.insert("source_list_failed", ("⚠ Failed to retrieve the environment list from source {}: {}".to_string(), "⚠ 无法从来源 {} 获取环境列表: {}".to_string()));
+        messages.insert("template_source_label", ("Template source: {} ({})".to_string(), "模板来源: {} ({})".to_string()));
+        messages.insert("manifest_missing_compose", ("The template manifest has no required file written to {}".to_string(), "模板清单中没有写入 {} 的必需文件".to_string()));
+        messages.insert("manifest_duplicate_target", ("Duplicate target path in the template manifest: {}".to_string(), "模板清单中的写入路径重复: {}".to_string()));
+        messages.insert("manifest_reserved_target", ("The template manifest cannot write to {}, it is managed by mdde".to_string(), "模板清单不能写入 {}，该路径由 mdde 管理".to_string()));
+        messages.insert("invalid_trusted_key", ("Invalid trusted key: {} (expected a base64 encoded Ed25519 public key)".to_string(), "无效的信任公钥: {} (应为 base64 编码的 Ed25519 公钥)".to_string()));
+        messages.insert("invalid_digest", ("Invalid sha256 digest for {}: {}".to_string(), "{} 的 sha256 摘要无效: {}".to_string()));
+        messages.insert("digest_mismatch", ("{} does not match the manifest digest (expected {}, got {})".to_string(), "{} 与清单中的摘要不一致 (应为 {}，实际为 {})".to_string()));
+        messages.insert("invalid_signature", ("The manifest signature is not valid base64".to_string(), "清单签名不是有效的 base64".to_string()));
+        messages.insert("untrusted_signature", ("The manifest signature does not match any trusted key".to_string(), "清单签名与所有信任的公钥都不匹配".to_string()));
+        messages.insert("user_only_config_key", ("{} can only be set in the user config file or with --config".to_string(), "{} 只能在用户配置文件中或通过 --config 设置".to_string()));
+        messages.insert("template_not_signed", ("Template {} has no signed manifest.json, which is required when trusted_keys is set".to_string(), "模板 {} 没有签名的 manifest.json，设置了 trusted_keys 时必须签名".to_string()));
+        messages.insert("file_digest_missing", ("{} has no sha256 digest in the signed manifest".to_string(), "签名的清单中 {} 没有 sha256 摘要".to_string()));
+        messages.insert("template_signature_verified", ("✓ Template signature verified".to_string(), "✓ 模板签名验证通过".to_string()));
+        messages.insert("verify_no_lock", ("No recorded template digests: {} does not exist, recreate the environment to record them".to_string(), "没有记录的模板摘要: {} 不存在，重新创建环境以记录摘要".to_string()));
+        messages.insert("verify_template", ("Template: {} (source: {})".to_string(), "模板: {} (来源: {})".to_string()));
+        messages.insert("verify_signed", ("Signature verified when created".to_string(), "创建时已验证签名".to_string()));
+        messages.insert("verify_failed", ("{} template files were modified or are missing".to_string(), "{} 个模板文件被修改或缺失".to_string()));
+        messages.insert("invalid_variable_name", ("Invalid variable name: '{}'. Use letters, digits and underscores, not starting with a digit".to_string(), "无效的变量名: '{}'。只能包含字母、数字和下划线，且不能以数字开头".to_string()));
+        messages.insert("invalid_variable_pattern", ("Invalid pattern for variable {}: {}".to_string(), "变量 {} 的正则表达式无效: {}".to_string()));
+        messages.insert("duplicate_variable", ("Variable {} is declared more than once".to_string(), "变量 {} 重复声明".to_string()));
+        messages.insert("variable_not_in_choices", ("Invalid value for {}: '{}'. Allowed values: {}".to_string(), "{} 的值无效: '{}'。可选值: {}".to_string()));
+        messages.insert("variable_pattern_mismatch", ("Invalid value for {}: '{}' does not match {}".to_string(), "{} 的值无效: '{}' 不匹配 {}".to_string()));
+        messages.insert("variable_not_integer", ("Invalid value for {}: '{}' is not an integer".to_string(), "{} 的值无效: '{}' 不是整数".to_string()));
+        messages.insert("variable_not_boolean", ("Invalid value for {}: '{}' is not a boolean (true/false)".to_string(), "{} 的值无效: '{}' 不是布尔值 (true/false)".to_string()));
+        messages.insert("variable_control_character", ("Invalid value for {}: line breaks and control characters are only allowed when the template declares a pattern".to_string(), "{} 的值无效: 只有模板声明了 pattern 时才允许换行和控制字符".to_string()));
+        messages.insert("variable_required", ("Variable {0} has no default value, specify it with --set {0}=<value>".to_string(), "变量 {0} 没有默认值，请通过 --set {0}=<值> 指定".to_string()));
+        messages.insert("unknown_variable", ("The template does not declare variable {}".to_string(), "模板没有声明变量 {}".to_string()));
+        messages.insert("template_unexpected_else", ("Unexpected {{else}} in template".to_string(), "模板中有多余的 {{else}}".to_string()));
+        messages.insert("template_unclosed_if", ("Unclosed {{#if}} in template".to_string(), "模板中的 {{#if}} 没有对应的 {{/if}}".to_string()));
+        messages.insert("template_render_failed", ("Failed to render {}: {}".to_string(), "渲染 {} 失败: {}".to_string()));
+        messages.insert("variable_prompt", ("Value for {}".to_string(), "{} 的值".to_string()));
+        messages.insert("variable_choices", ("Allowed values: {}".to_string(), "可选值: {}".to_string()));
+        messages.insert("invalid_variable_format", ("Invalid variable: '{}'. Should be name=value format, example: node_version=20".to_string(), "无效的变量: '{}'。应为 name=value 格式，例如: node_version=20".to_string()));
+        messages.insert("reclaimed_space", ("Total reclaimed space: {}".to_string(), "共回收空间: {}".to_string()));
 
         // 状态相关
+        messages.insert("no_services", ("No service containers found, run 'mdde start' first".to_string(), "未找到服务容器，请先运行 'mdde start'".to_string()));
+        messages.insert("watch_exit_hint", ("Press Ctrl+C to exit".to_string(), "按 Ctrl+C 退出".to_string()));
         messages.insert("environment_status", ("Development environment status:".to_string(), "开发环境状态:".to_string()));
 
         // 初始化相关
@@ -135,10 +209,28 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
         messages.insert("docker_running", ("✓ Docker service is running normally".to_string(), "✓ Docker 服务运行正常".to_string()));
         messages.insert("docker_not_running", ("✗ Docker service is not running".to_string(), "✗ Docker 服务未运行".to_string()));
         messages.insert("start_docker", ("  Please start Docker service".to_string(), "  请启动 Docker 服务".to_string()));
+        messages.insert("check_engine", ("Checking {}...".to_string(), "检查 {}...".to_string()));
+        messages.insert("engine_installed", ("✓ {} is installed ({})".to_string(), "✓ {} 已安装（{}）".to_string()));
+        messages.insert("engine_not_installed", ("✗ {} is not installed or inaccessible".to_string(), "✗ {} 未安装或无法访问".to_string()));
+        messages.insert("engine_running", ("✓ {} service is running normally".to_string(), "✓ {} 服务运行正常".to_string()));
+        messages.insert("engine_not_running", ("✗ {} service is not running".to_string(), "✗ {} 服务未运行".to_string()));
+        messages.insert("engine_source_configured", ("set by engine= in cfg.env".to_string(), "由 cfg.env 中的 engine= 指定".to_string()));
+        messages.insert("engine_source_detected", ("auto-detected".to_string(), "自动检测".to_string()));
+        messages.insert("engine_api_socket", ("  API socket: {}".to_string(), "  API 套接字: {}".to_string()));
+        messages.insert("engine_api_unavailable", ("⚠ API socket is unavailable, falling back to the command line".to_string(), "⚠ API 套接字不可用，将回退到命令行".to_string()));
+        messages.insert("install_podman", ("  Please install Podman".to_string(), "  请安装 Podman".to_string()));
+        messages.insert("start_podman", ("  Please start Podman (podman machine start, or systemctl --user start podman.socket)".to_string(), "  请启动 Podman（podman machine start 或 systemctl --user start podman.socket）".to_string()));
+        messages.insert("invalid_engine", ("Invalid engine value: {} (expected docker or podman)".to_string(), "无效的 engine 配置: {}（可选值为 docker 或 podman）".to_string()));
         messages.insert("check_docker_compose", ("📦 Checking Docker Compose...".to_string(), "📦 检查 Docker Compose...".to_string()));
         messages.insert("docker_compose_installed", ("✓ Docker Compose is installed".to_string(), "✓ Docker Compose 已安装".to_string()));
         messages.insert("docker_compose_not_installed", ("✗ Docker Compose is not installed".to_string(), "✗ Docker Compose 未安装".to_string()));
         messages.insert("install_docker_compose", ("  Please install Docker Compose".to_string(), "  请安装 Docker Compose".to_string()));
+        messages.insert("compose_flavor", ("  Using: {} ({})".to_string(), "  使用: {}（{}）".to_string()));
+        messages.insert("compose_source_configured", ("set by compose= in cfg.env".to_string(), "由 cfg.env 中的 compose= 指定".to_string()));
+        messages.insert("compose_source_detected", ("auto-detected".to_string(), "自动检测".to_string()));
+        messages.insert("compose_flavor_unavailable", ("✗ Configured compose command is unavailable: {}".to_string(), "✗ 配置的 compose 命令不可用: {}".to_string()));
+        messages.insert("compose_not_found", ("Neither `{}` nor `{}` is available".to_string(), "未找到 `{}` 或 `{}`".to_string()));
+        messages.insert("invalid_compose_flavor", ("Invalid compose value: {} (expected plugin or standalone)".to_string(), "无效的 compose 配置: {}（可选值为 plugin 或 standalone）".to_string()));
         messages.insert("check_network", ("🌐 Checking network connection...".to_string(), "🌐 检查网络连接...".to_string()));
         messages.insert("network_ok", ("✓ Network connection is normal".to_string(), "✓ 网络连接正常".to_string()));
         messages.insert("network_server", ("  Server: {}".to_string(), "  服务器: {}".to_string()));
@@ -149,7 +241,43 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
         messages.insert("check_config_files", ("📁 Checking configuration files...".to_string(), "📁 检查配置文件...".to_string()));
         messages.insert("docker_compose_exists", ("✓ docker-compose.yml exists".to_string(), "✓ docker-compose.yml 存在".to_string()));
         messages.insert("docker_compose_not_exists", ("⚠ docker-compose.yml does not exist".to_string(), "⚠ docker-compose.yml 不存在".to_string()));
-        messages.insert("current_dir", ("  Current directory: {}".to_string(), "  当前目录: {}".to_string()));
+        messages.insert("env_syntax_error", ("line {}: {}".to_string(), "第 {} 行: {}".to_string()));
+        messages.insert("env_file_warning", ("⚠ ignoring invalid line in cfg.env: {}".to_string(), "⚠ 忽略 cfg.env 中有错误的行: {}".to_string()));
+        messages.insert("env_expected_assignment", ("expected KEY=VALUE, found '{}'".to_string(), "应为 KEY=VALUE 格式，实际为 '{}'".to_string()));
+        messages.insert("env_invalid_key", ("invalid variable name '{}'".to_string(), "无效的变量名 '{}'".to_string()));
+        messages.insert("env_unterminated_quote", ("unterminated quoted value".to_string(), "引号未闭合".to_string()));
+        messages.insert("env_unexpected_characters", ("unexpected characters after quoted value: '{}'".to_string(), "引号值后有多余的字符: '{}'".to_string()));
+        messages.insert("env_bad_substitution", ("invalid variable substitution '{}'".to_string(), "无效的变量插值 '{}'".to_string()));
+        messages.insert("env_required_variable", ("variable {} is required: {}".to_string(), "必须设置变量 {}: {}".to_string()));
+        messages.insert("check_invalid_reference", ("line {}: invalid variable reference '{}'".to_string(), "第 {} 行: 无效的变量引用 '{}'".to_string()));
+        messages.insert("check_missing_variable", ("{} is referenced on line {} but not defined, compose will substitute an empty string".to_string(), "第 {1} 行引用的变量 {0} 未定义，compose 会替换为空字符串".to_string()));
+        messages.insert("check_required_variable", ("{} is required on line {}: {}".to_string(), "第 {1} 行要求设置变量 {0}: {2}".to_string()));
+        messages.insert("check_empty_variable", ("{} is referenced on line {} but its value is empty".to_string(), "第 {1} 行引用的变量 {0} 的值为空".to_string()));
+        messages.insert("check_unused_variable", ("{} is defined in cfg.env but not referenced by docker-compose.yml".to_string(), "{} 在 cfg.env 中定义，但 docker-compose.yml 中没有引用".to_string()));
+        messages.insert("check_malformed_value", ("{} has an invalid value '{}': {}".to_string(), "{} 的值 '{}' 无效: {}".to_string()));
+        messages.insert("check_invalid_container_name", ("only letters, digits, '_', '.' and '-' are allowed and it must start with a letter or digit".to_string(), "只能包含字母、数字、'_'、'.' 和 '-'，并以字母或数字开头".to_string()));
+        messages.insert("check_workspace_missing", ("workspace directory does not exist: {}".to_string(), "工作目录不存在: {}".to_string()));
+        messages.insert("check_suggest_set", ("run `mdde env --set {}=<value>`".to_string(), "执行 `mdde env --set {}=<值>`".to_string()));
+        messages.insert("check_suggest_rename", ("did you mean to rename {} to {} in cfg.env?".to_string(), "是否需要将 cfg.env 中的 {} 改名为 {}？".to_string()));
+        messages.insert("check_suggest_remove", ("remove it with `mdde env --del {}`".to_string(), "可以使用 `mdde env --del {}` 删除".to_string()));
+        messages.insert("check_variables_header", ("Variables referenced by docker-compose.yml:".to_string(), "docker-compose.yml 引用的变量:".to_string()));
+        messages.insert("check_no_variables", ("docker-compose.yml does not reference any variables".to_string(), "docker-compose.yml 没有引用任何变量".to_string()));
+        messages.insert("check_issues_header", ("Problems:".to_string(), "问题:".to_string()));
+        messages.insert("check_passed", ("✓ cfg.env matches docker-compose.yml".to_string(), "✓ cfg.env 与 docker-compose.yml 一致".to_string()));
+        messages.insert("check_failed", ("configuration check found {} error(s)".to_string(), "配置检查发现 {} 个错误".to_string()));
+        messages.insert("check_compose_variables", ("🧩 Check compose variables:".to_string(), "🧩 检查 compose 变量:".to_string()));
+        messages.insert("project_root", ("  Project root: {}".to_string(), "  项目根目录: {}".to_string()));
+        messages.insert("project_dir_not_found", ("Project directory does not exist: {}".to_string(), "项目目录不存在: {}".to_string()));
+        messages.insert("registry_update_failed", ("⚠ Failed to register the environment: {}".to_string(), "⚠ 登记环境失败: {}".to_string()));
+        messages.insert("registry_pruned", ("Removed {} ({}): the directory no longer exists".to_string(), "已移除 {} ({})：目录已不存在".to_string()));
+        messages.insert("registry_env_missing", ("⚠ Skipping {}: the directory no longer exists, run mdde ls --prune to remove it".to_string(), "⚠ 跳过 {}：目录已不存在，可使用 mdde ls --prune 移除".to_string()));
+        messages.insert("stopping_registered_env", ("Stopping {}...".to_string(), "正在停止 {}...".to_string()));
+        messages.insert("no_registered_environments", ("No registered environments, environments are registered by mdde create".to_string(), "没有登记的环境，mdde create 创建环境时会自动登记".to_string()));
+        messages.insert("invalid_env_name", ("Invalid environment name: {} (only lowercase letters, digits, '-' and '_' are allowed)".to_string(), "无效的环境名称: {}（只能包含小写字母、数字、'-' 和 '_'）".to_string()));
+        messages.insert("env_not_found", ("Environment {} does not exist, create it with mdde --env <name> create".to_string(), "环境 {} 不存在，可使用 mdde --env <name> create 创建".to_string()));
+        messages.insert("default_env_set", ("✓ Default environment set to {}".to_string(), "✓ 默认环境已切换为 {}".to_string()));
+        messages.insert("no_environments", ("No environments in this project, create one with mdde create".to_string(), "项目中还没有环境，可使用 mdde create 创建".to_string()));
+        messages.insert("current_environment", ("  Environment: {} ({})".to_string(), "  当前环境: {} ({})".to_string()));
         messages.insert("mdde_env_exists", ("✓ .mdde/cfg.env exists".to_string(), "✓ .mdde/cfg.env 存在".to_string()));
         messages.insert("mdde_env_not_exists", ("⚠ .mdde/cfg.env does not exist".to_string(), "⚠ .mdde/cfg.env 不存在".to_string()));
 
@@ -190,19 +318,19 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
         messages.insert("env_type_empty", ("Development environment type cannot be empty".to_string(), "开发环境类型不能为空".to_string()));
         messages.insert("invalid_env_type", ("Invalid development environment type: '{}'. Please select a valid environment type".to_string(), "无效的开发环境类型: '{}'. 请选择有效的环境类型".to_string()));
         
-        messages.insert("downloaded_compose", ("✓ Downloaded docker-compose.yml".to_string(), "✓ 已下载 docker-compose.yml".to_string()));
-        messages.insert("downloaded_dockerfile", ("✓ Downloaded Dockerfile".to_string(), "✓ 已下载 Dockerfile".to_string()));
-        messages.insert("dockerfile_not_exists", ("ℹ Dockerfile does not exist, using default image".to_string(), "ℹ Dockerfile 不存在，使用默认镜像".to_string()));
-        messages.insert("dockerfile_download_failed", ("⚠ Failed to download Dockerfile: {}".to_string(), "⚠ 下载 Dockerfile 失败: {}".to_string()));
+        messages.insert("downloaded_template_file", ("✓ Downloaded {}".to_string(), "✓ 已下载 {}".to_string()));
+        messages.insert("optional_file_not_exists", ("ℹ {} is not in the template, skipped".to_string(), "ℹ 模板中没有 {}，已跳过".to_string()));
+        messages.insert("optional_file_download_failed", ("⚠ Failed to download {}: {}".to_string(), "⚠ 下载 {} 失败: {}".to_string()));
         
         messages.insert("env_created_success", ("✓ Development environment created successfully".to_string(), "✓ 开发环境创建成功".to_string()));
         messages.insert("env_name_label", ("Environment name: {}".to_string(), "环境名称: {}".to_string()));
+        messages.insert("environment_label", ("Environment: {}".to_string(), "所属环境: {}".to_string()));
         messages.insert("env_type_label", ("Environment type: {}".to_string(), "环境类型: {}".to_string()));
         messages.insert("workspace_label", ("Workspace: {}".to_string(), "工作目录: {}".to_string()));
         messages.insert("app_port_label", ("Application port: {} (host port:{} -> container port:{})".to_string(), "应用端口: {} (主机端口:{} -> 容器端口:{})".to_string()));
-        messages.insert("config_file_label", ("Configuration file: .mdde/docker-compose.yml".to_string(), "配置文件: .mdde/docker-compose.yml".to_string()));
-        messages.insert("env_file_label", ("Environment file: .mdde/cfg.env".to_string(), "环境变量文件: .mdde/cfg.env".to_string()));
-        messages.insert("custom_image_label", ("Custom image: .mdde/Dockerfile".to_string(), "自定义镜像: .mdde/Dockerfile".to_string()));
+        messages.insert("config_file_label", ("Configuration file: {}".to_string(), "配置文件: {}".to_string()));
+        messages.insert("env_file_label", ("Environment file: {}".to_string(), "环境变量文件: {}".to_string()));
+        messages.insert("custom_image_label", ("Custom image: {}".to_string(), "自定义镜像: {}".to_string()));
         
         messages.insert("next_steps", ("Next steps:".to_string(), "下一步操作:".to_string()));
         messages.insert("start_env_step", ("1. Start environment: mdde start".to_string(), "1. 启动环境: mdde start".to_string()));
@@ -215,7 +343,7 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
         messages.insert("env_name_invalid_chars", ("Environment name can only contain letters, numbers, hyphens and underscores".to_string(), "环境名称只能包含字母、数字、连字符和下划线".to_string()));
         
         // 端口验证相关
-        messages.insert("port_format_error", ("Application port format error: '{}'. Should be host_port:container_port format, example: 8080:80".to_string(), "应用端口格式错误: '{}'. 应为 host_port:container_port 格式，例如: 8080:80".to_string()));
+        messages.insert("port_format_error", ("Port mapping format error: '{}'. Should be [ip:][host_port:]container_port[/protocol], example: 8080:80, 127.0.0.1:5000:5000, 5353:53/udp".to_string(), "端口映射格式错误: '{}'. 应为 [ip:][host_port:]container_port[/protocol] 格式，例如: 8080:80、127.0.0.1:5000:5000、5353:53/udp".to_string()));
         messages.insert("invalid_host_port", ("Invalid host port: '{}'. Must be a number between 1-65535".to_string(), "无效的主机端口: '{}'. 必须是 1-65535 之间的数字".to_string()));
         messages.insert("invalid_container_port", ("Invalid container port: '{}'. Must be a number between 1-65535".to_string(), "无效的容器端口: '{}'. 必须是 1-65535 之间的数字".to_string()));
         messages.insert("port_cannot_be_zero", ("Port number cannot be 0".to_string(), "端口号不能为 0".to_string()));
@@ -238,8 +366,8 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
         messages.insert("only_one_operation", ("Only one operation option can be used at a time".to_string(), "只能同时使用一个操作选项".to_string()));
         messages.insert("display_env_vars", ("Display environment variable configuration".to_string(), "显示环境变量配置".to_string()));
         messages.insert("env_file_empty", ("Environment variable file is empty or does not exist".to_string(), "环境变量文件为空或不存在".to_string()));
-        messages.insert("file_location", ("File location: .mdde/cfg.env".to_string(), "文件位置: .mdde/cfg.env".to_string()));
-        messages.insert("env_config_header", ("Environment variable configuration (.mdde/cfg.env):".to_string(), "环境变量配置 (.mdde/cfg.env):".to_string()));
+        messages.insert("file_location", ("File location: {}".to_string(), "文件位置: {}".to_string()));
+        messages.insert("env_config_header", ("Environment variable configuration ({}):".to_string(), "环境变量配置 ({}):".to_string()));
         messages.insert("total_env_vars", ("Total {} environment variables".to_string(), "总共 {} 个环境变量".to_string()));
         messages.insert("set_env_var", ("Set environment variable".to_string(), "设置环境变量".to_string()));
         messages.insert("env_var_updated", ("✓ Environment variable updated".to_string(), "✓ 环境变量已更新".to_string()));
@@ -286,6 +414,42 @@ fn get_messages() -> &'static HashMap<MessageKey, (String, String)> {
 
         // 启动环境相关 (start 命令额外的)
         messages.insert("start_env_name", ("Start development environment: {}".to_string(), "启动开发环境: {}".to_string()));
+        messages.insert("waiting_for_services", ("Waiting for services to become ready (timeout {}s)...".to_string(), "等待服务就绪（超时 {} 秒）...".to_string()));
+        messages.insert("service_ready", ("  ✓ {}: ready".to_string(), "  ✓ {}: 已就绪".to_string()));
+        messages.insert("service_containers_missing", ("no service containers".to_string(), "没有服务容器".to_string()));
+        messages.insert("service_waiting", ("  ⏳ {}: {}".to_string(), "  ⏳ {}: {}".to_string()));
+        messages.insert("service_failed", ("  ✗ {}: {}".to_string(), "  ✗ {}: {}".to_string()));
+        messages.insert("all_services_ready", ("✓ All services are ready".to_string(), "✓ 所有服务已就绪".to_string()));
+        // 端口冲突检查 (create/start 命令)
+        messages.insert("checking_ports", ("Checking host ports...".to_string(), "检查宿主机端口...".to_string()));
+        messages.insert("port_in_use", ("✗ Port {} is in use by {}".to_string(), "✗ 端口 {} 已被 {} 占用".to_string()));
+        messages.insert("port_holder_environment", ("mdde environment container {} ({})".to_string(), "mdde 环境容器 {}（{}）".to_string()));
+        messages.insert("port_holder_container", ("container {}".to_string(), "容器 {}".to_string()));
+        messages.insert("port_holder_process", ("process {} (PID {})".to_string(), "进程 {}（PID {}）".to_string()));
+        messages.insert("port_holder_unknown", ("another process".to_string(), "其他进程".to_string()));
+        messages.insert("port_use_next_prompt", ("Use the next free port {} instead? [Y/n]: ".to_string(), "是否改用下一个空闲端口 {}? [Y/n]: ".to_string()));
+        messages.insert("port_auto_selected", ("Using free port {} instead".to_string(), "改用空闲端口 {}".to_string()));
+        messages.insert("port_auto_hint", ("Use --auto-port to pick the next free port automatically".to_string(), "使用 --auto-port 自动选择下一个空闲端口".to_string()));
+        messages.insert("port_remapped", ("Port mapping changed to {}".to_string(), "端口映射已改为 {}".to_string()));
+        // 分层配置 (config 命令)
+        messages.insert("config_origin_default", ("built-in default".to_string(), "内置默认值".to_string()));
+        messages.insert("config_origin_user", ("user config {}".to_string(), "用户配置 {}".to_string()));
+        messages.insert("config_origin_project", ("project config {}".to_string(), "项目配置 {}".to_string()));
+        messages.insert("config_origin_env", ("environment variable {}".to_string(), "环境变量 {}".to_string()));
+        messages.insert("config_origin_cli", ("command line --config".to_string(), "命令行 --config".to_string()));
+        messages.insert("unknown_config_key", ("Unknown config key: {}".to_string(), "未知配置项: {}".to_string()));
+        messages.insert("config_override_format", ("Invalid config override '{}'. Should be key=value".to_string(), "无效的配置覆盖 '{}'，应为 key=value 格式".to_string()));
+        messages.insert("effective_config", ("Effective configuration:".to_string(), "生效的配置:".to_string()));
+
+        // 端口映射管理 (port 命令)
+        messages.insert("port_added", ("✓ Added port mapping {}".to_string(), "✓ 已添加端口映射 {}".to_string()));
+        messages.insert("port_removed", ("✓ Removed port mapping {}".to_string(), "✓ 已删除端口映射 {}".to_string()));
+        messages.insert("port_mapping_exists", ("Port mapping {} overlaps existing mapping {}".to_string(), "端口映射 {} 与已有映射 {} 冲突".to_string()));
+        messages.insert("port_mapping_not_found", ("Port mapping not found: {}".to_string(), "未找到端口映射: {}".to_string()));
+        messages.insert("port_restart_hint", ("Run 'mdde restart' to apply the change".to_string(), "运行 'mdde restart' 使更改生效".to_string()));
+        messages.insert("no_port_mappings", ("No port mappings configured".to_string(), "未配置端口映射".to_string()));
+        messages.insert("port_range_mismatch", ("Host and container port ranges differ in size: '{}'".to_string(), "宿主机端口范围与容器端口范围长度不一致: '{}'".to_string()));
+        messages.insert("invalid_protocol", ("Invalid protocol: '{}'. Must be tcp, udp or sctp".to_string(), "无效的协议: '{}'. 必须是 tcp、udp 或 sctp".to_string()));
         messages.insert("start_failed", ("Start failed: {}".to_string(), "启动失败: {}".to_string()));
 
         // 停止环境相关 (stop 命令)
@@ -316,13 +480,14 @@ pub fn tf(key: MessageKey, args: &[&dyn std::fmt::Display]) -> String {
     let template = t(key);
     let mut result = template.to_string();
 
-    // 简单的字符串替换，支持 {} 占位符
+    // 简单的字符串替换，`{}` 占位符按顺序依次替换，也支持 {0}, {1} 等
     for (i, arg) in args.iter().enumerate() {
-        if i == 0 {
-            result = result.replace("{}", &arg.to_string());
+        let arg = arg.to_string();
+        let indexed = format!("{{{}}}", i);
+        if result.contains(&indexed) {
+            result = result.replace(&indexed, &arg);
         } else {
-            // 对于多个参数，可以扩展支持 {0}, {1} 等
-            result = result.replace(&format!("{{{}}}", i), &arg.to_string());
+            result = result.replacen("{}", &arg, 1);
         }
     }
 
@@ -392,6 +557,11 @@ mod tests {
         let result_zh = tf("server_address", &[&"http://localhost:3000"]);
         assert_eq!(result_zh, "服务器地址: http://localhost:3000");
 
+        // 多个参数按顺序替换
+        set_language(Language::English);
+        let result = tf("engine_installed", &[&"Podman", &"configured"]);
+        assert_eq!(result, "✓ Podman is installed (configured)");
+
         // 重置为英文，避免影响其他测试
         set_language(Language::English);
     }
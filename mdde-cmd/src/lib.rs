@@ -1,10 +1,26 @@
+pub mod backend;
 pub mod cli;
 pub mod commands;
+pub mod compose;
+pub mod compose_model;
 pub mod config;
+pub mod config_check;
+pub mod container_engine;
 pub mod docker;
+pub mod env_file;
 pub mod error;
 pub mod http;
 pub mod i18n;
+pub mod port_mapping;
+pub mod ports;
+pub mod project;
+pub mod registry;
+pub mod table;
+pub mod template_cache;
+pub mod template_integrity;
+pub mod template_manifest;
+pub mod template_source;
+pub mod template_vars;
 pub mod utils;
 
 pub use config::Config;
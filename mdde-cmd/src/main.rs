@@ -16,8 +16,15 @@ async fn main() -> Result<(), MddeError> {
     // 解析命令行参数
     let cli = Cli::parse();
 
-    // 加载配置
-    let config = Config::load().await?;
+    // 确定项目根目录，然后加载配置，命令行中的 --config 优先级最高
+    cli.apply_project_options()?;
+    let overrides = cli.config_overrides()?;
+    let config = if cli.inspects_env_file() {
+        // 查看和编辑 cfg.env 的命令在文件有错误时仍然可以运行
+        Config::load_lenient(&overrides).await?
+    } else {
+        Config::load_with(&overrides).await?
+    };
     // 打印 config 数据
     println!(
         "{}",
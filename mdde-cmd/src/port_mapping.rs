@@ -0,0 +1,355 @@
+use crate::error::MddeError;
+use crate::i18n;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 端口协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+impl Protocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Sctp => "sctp",
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = MddeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "tcp" => Ok(Protocol::Tcp),
+            "udp" => Ok(Protocol::Udp),
+            "sctp" => Ok(Protocol::Sctp),
+            _ => Err(MddeError::InvalidPortFormat(i18n::tf(
+                "invalid_protocol",
+                &[&s],
+            ))),
+        }
+    }
+}
+
+/// 端口或端口范围，单个端口时 `start == end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    pub fn single(port: u16) -> Self {
+        Self {
+            start: port,
+            end: port,
+        }
+    }
+
+    fn len(&self) -> usize {
+        usize::from(self.end - self.start) + 1
+    }
+
+    pub fn is_single(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn ports(&self) -> impl Iterator<Item = u16> {
+        self.start..=self.end
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        (self.start..=self.end).contains(&port)
+    }
+
+    /// 解析 `8080` 或 `8080-8090`，`invalid_key` 为端口无效时的提示
+    fn parse(s: &str, invalid_key: i18n::MessageKey) -> Result<Self, MddeError> {
+        let port = |p: &str| {
+            let port = p
+                .trim()
+                .parse::<u16>()
+                .map_err(|_| MddeError::InvalidPortFormat(i18n::tf(invalid_key, &[&p])))?;
+            if port == 0 {
+                return Err(MddeError::InvalidPortFormat(
+                    i18n::t("port_cannot_be_zero").to_string(),
+                ));
+            }
+            Ok(port)
+        };
+
+        let range = match s.split_once('-') {
+            Some((start, end)) => Self {
+                start: port(start)?,
+                end: port(end)?,
+            },
+            None => Self::single(port(s)?),
+        };
+        if range.start > range.end {
+            return Err(MddeError::InvalidPortFormat(i18n::tf(invalid_key, &[&s])));
+        }
+        Ok(range)
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_single() {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+/// 端口映射，格式为 `[ip:][host_port:]container_port[/protocol]`
+///
+/// 端口可以是范围（`7000-7002:7000-7002`），IPv6 地址需要用方括号（`[::1]:8080:80`）。
+/// 省略宿主机端口时与容器端口相同。
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PortMapping {
+    pub host_ip: Option<String>,
+    /// 宿主机端口，None 表示与容器端口相同
+    pub host: Option<PortRange>,
+    pub container: PortRange,
+    pub protocol: Protocol,
+}
+
+impl PortMapping {
+    /// 映射到宿主机的端口范围
+    pub fn host_range(&self) -> PortRange {
+        self.host.unwrap_or(self.container)
+    }
+
+    /// 宿主机上的第一个端口
+    pub fn host_port(&self) -> u16 {
+        self.host_range().start
+    }
+
+    /// 将宿主机端口整体平移到从 `port` 开始，保留绑定地址、容器端口和协议
+    pub fn with_host_port(&self, port: u16) -> Self {
+        let len = self.host_range().end - self.host_range().start;
+        Self {
+            host: Some(PortRange {
+                start: port,
+                end: port.saturating_add(len),
+            }),
+            ..self.clone()
+        }
+    }
+
+    /// 完整的 compose 短格式，总是包含宿主机端口和协议
+    pub fn to_compose(&self) -> String {
+        let mut mapping = String::new();
+        if let Some(ip) = &self.host_ip {
+            mapping.push_str(ip);
+            mapping.push(':');
+        }
+        format!(
+            "{}{}:{}/{}",
+            mapping,
+            self.host_range(),
+            self.container,
+            self.protocol
+        )
+    }
+
+    /// 解析逗号分隔的多个端口映射
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, MddeError> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|mapping| !mapping.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// 格式化为逗号分隔的列表
+    pub fn format_list(mappings: &[Self]) -> String {
+        mappings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for PortMapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ip) = &self.host_ip {
+            write!(f, "{}:", ip)?;
+        }
+        if let Some(host) = &self.host {
+            write!(f, "{}:", host)?;
+        }
+        write!(f, "{}", self.container)?;
+        if self.protocol != Protocol::Tcp {
+            write!(f, "/{}", self.protocol)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PortMapping {
+    type Err = MddeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format_error = || MddeError::InvalidPortFormat(i18n::tf("port_format_error", &[&s]));
+        let s = s.trim();
+
+        let (mapping, protocol) = match s.rsplit_once('/') {
+            Some((mapping, protocol)) => (mapping, protocol.parse()?),
+            None => (s, Protocol::Tcp),
+        };
+
+        let (host_ip, host, container) = match mapping.rsplit_once(':') {
+            Some((host, container)) => match host.rsplit_once(':') {
+                Some((ip, host)) => (Some(ip), Some(host), container),
+                None => (None, Some(host), container),
+            },
+            None => (None, None, mapping),
+        };
+
+        if host_ip.is_some_and(|ip| ip.is_empty() || (ip.contains(':') && !ip.starts_with('['))) {
+            return Err(format_error());
+        }
+        let host = host
+            .map(|host| PortRange::parse(host, "invalid_host_port"))
+            .transpose()?;
+        let container = PortRange::parse(container, "invalid_container_port")?;
+        if host.is_some_and(|host| host.len() != container.len()) {
+            return Err(MddeError::InvalidPortFormat(i18n::tf(
+                "port_range_mismatch",
+                &[&s],
+            )));
+        }
+
+        Ok(Self {
+            host_ip: host_ip.map(str::to_string),
+            host,
+            container,
+            protocol,
+        })
+    }
+}
+
+impl From<u16> for PortMapping {
+    /// 宿主机端口与容器端口相同的 TCP 映射
+    fn from(port: u16) -> Self {
+        Self {
+            host_ip: None,
+            host: None,
+            container: PortRange::single(port),
+            protocol: Protocol::Tcp,
+        }
+    }
+}
+
+impl TryFrom<String> for PortMapping {
+    type Error = MddeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<PortMapping> for String {
+    fn from(mapping: PortMapping) -> Self {
+        mapping.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for mapping in [
+            "8080",
+            "8080:80",
+            "127.0.0.1:5000:5000",
+            "[::1]:8080:80",
+            "5353:53/udp",
+            "7000-7002:8000-8002",
+            "0.0.0.0:9000-9001:9000-9001/sctp",
+        ] {
+            let parsed: PortMapping = mapping.parse().unwrap();
+            assert_eq!(parsed.to_string(), mapping);
+        }
+
+        let parsed: PortMapping = "127.0.0.1:5000:6000/udp".parse().unwrap();
+        assert_eq!(parsed.host_ip.as_deref(), Some("127.0.0.1"));
+        assert_eq!(parsed.host, Some(PortRange::single(5000)));
+        assert_eq!(parsed.container, PortRange::single(6000));
+        assert_eq!(parsed.protocol, Protocol::Udp);
+
+        // 省略宿主机端口时与容器端口相同
+        let parsed: PortMapping = "3000".parse().unwrap();
+        assert_eq!(parsed.host_port(), 3000);
+        assert_eq!(parsed.to_compose(), "3000:3000/tcp");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for mapping in [
+            "",
+            "abc:80",
+            "8080:xyz",
+            "0:80",
+            "8080:80/icmp",
+            "7000-7002:8000",
+            "7002-7000:7002-7000",
+            ":8080:80",
+            "::1:8080:80",
+        ] {
+            assert!(
+                mapping.parse::<PortMapping>().is_err(),
+                "{} should be rejected",
+                mapping
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_host_port() {
+        let mapping: PortMapping = "127.0.0.1:8080:80".parse().unwrap();
+        assert_eq!(
+            mapping.with_host_port(8081).to_string(),
+            "127.0.0.1:8081:80"
+        );
+
+        let mapping: PortMapping = "7000-7002:7000-7002/udp".parse().unwrap();
+        assert_eq!(
+            mapping.with_host_port(9000).to_string(),
+            "9000-9002:7000-7002/udp"
+        );
+
+        let mapping: PortMapping = "3000".parse().unwrap();
+        assert_eq!(mapping.with_host_port(3001).to_string(), "3001:3000");
+    }
+
+    #[test]
+    fn test_list_round_trip() {
+        let mappings =
+            PortMapping::parse_list("8080:80, 5353:53/udp,,127.0.0.1:5000:5000").unwrap();
+        assert_eq!(mappings.len(), 3);
+        assert_eq!(
+            PortMapping::format_list(&mappings),
+            "8080:80,5353:53/udp,127.0.0.1:5000:5000"
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use crate::backend::service::COMPOSE_WORKING_DIR_LABEL;
+use crate::backend::{ContainerBackend, ContainerDetails};
+use crate::compose::ComposeProject;
+use crate::error::MddeError;
+use crate::i18n;
+use crate::port_mapping::PortMapping;
+use crate::project;
+use colored::*;
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+
+/// compose 记录的配置文件路径标签，多个文件以逗号分隔
+const CONFIG_FILES_LABEL: &str = "com.docker.compose.project.config_files";
+
+/// compose 文件中发布到宿主机的端口
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HostPort {
+    pub port: u16,
+    pub protocol: String,
+}
+
+impl fmt::Display for HostPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.port, self.protocol)
+    }
+}
+
+/// 占用端口的一方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortHolder {
+    /// 其他 mdde 环境的容器，可能已停止但启动时会争用端口
+    Environment {
+        container: String,
+        project_dir: String,
+    },
+    /// 其他容器
+    Container(String),
+    /// 宿主机上的进程
+    Process { pid: u32, name: String },
+    /// 端口被占用，但无法确定占用方
+    Unknown,
+}
+
+impl fmt::Display for PortHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortHolder::Environment {
+                container,
+                project_dir,
+            } => write!(
+                f,
+                "{}",
+                i18n::tf("port_holder_environment", &[container, project_dir])
+            ),
+            PortHolder::Container(name) => {
+                write!(f, "{}", i18n::tf("port_holder_container", &[name]))
+            }
+            PortHolder::Process { pid, name } => {
+                write!(f, "{}", i18n::tf("port_holder_process", &[name, pid]))
+            }
+            PortHolder::Unknown => write!(f, "{}", i18n::t("port_holder_unknown")),
+        }
+    }
+}
+
+/// 端口占用检查，汇总容器声明的端口和宿主机上的监听情况
+#[derive(Debug, Default)]
+pub struct PortCheck {
+    /// 其他容器声明的端口
+    claims: HashMap<(u16, String), PortHolder>,
+    /// 当前项目自身容器声明的端口，不算冲突
+    own: HashSet<(u16, String)>,
+}
+
+impl PortCheck {
+    /// 收集本机所有容器（包括已停止的）声明的端口
+    ///
+    /// 查询容器失败时（如引擎未运行）只检查宿主机端口。
+    pub fn collect(backend: &dyn ContainerBackend, project: &ComposeProject) -> Self {
+        let mut check = Self::default();
+        let own_file = project.compose_file.to_string_lossy().to_string();
+
+        for id in backend.list_containers().unwrap_or_default() {
+            let Ok(details) = backend.inspect(&id) else {
+                continue;
+            };
+            let is_own = details
+                .labels
+                .get(CONFIG_FILES_LABEL)
+                .is_some_and(|files| files.split(',').any(|file| file == own_file));
+            let holder = container_holder(&details);
+
+            for port in &details.ports {
+                let Some(host_port) = port.host_port else {
+                    continue;
+                };
+                let key = (host_port, port.protocol.clone());
+                if is_own {
+                    check.own.insert(key);
+                } else {
+                    check.claims.entry(key).or_insert_with(|| holder.clone());
+                }
+            }
+        }
+
+        check
+    }
+
+    /// 检查端口是否被占用，返回占用方
+    pub fn holder(&self, port: u16, protocol: &str) -> Option<PortHolder> {
+        let key = (port, protocol.to_string());
+        if self.own.contains(&key) {
+            return None;
+        }
+        if let Some(holder) = self.claims.get(&key) {
+            return Some(holder.clone());
+        }
+        if host_port_available(port, protocol) {
+            None
+        } else {
+            Some(
+                find_process(port, protocol)
+                    .map(|(pid, name)| PortHolder::Process { pid, name })
+                    .unwrap_or(PortHolder::Unknown),
+            )
+        }
+    }
+
+    /// 标记端口已被本次操作选用，避免多个冲突选到同一个空闲端口
+    pub fn reserve(&mut self, port: u16, protocol: &str) {
+        self.claims
+            .insert((port, protocol.to_string()), PortHolder::Unknown);
+    }
+
+    /// 从 `port` 之后查找第一个空闲端口
+    pub fn next_free(&self, port: u16, protocol: &str) -> Option<u16> {
+        (port.saturating_add(1)..=u16::MAX).find(|&p| self.holder(p, protocol).is_none())
+    }
+
+    /// 处理端口冲突：`--auto-port` 时直接换用下一个空闲端口，交互式终端中询问用户
+    pub fn resolve(
+        &self,
+        port: u16,
+        protocol: &str,
+        holder: &PortHolder,
+        auto_port: bool,
+    ) -> Result<u16, MddeError> {
+        println!(
+            "{}",
+            i18n::tf("port_in_use", &[&format!("{}/{}", port, protocol), holder]).red()
+        );
+
+        let conflict =
+            || MddeError::PortConflict(vec![format!("{}/{} ({})", port, protocol, holder)]);
+        let next = self.next_free(port, protocol).ok_or_else(conflict)?;
+
+        if auto_port {
+            println!("{}", i18n::tf("port_auto_selected", &[&next]).yellow());
+            return Ok(next);
+        }
+
+        if !io::stdin().is_terminal() {
+            println!("{}", i18n::t("port_auto_hint"));
+            return Err(conflict());
+        }
+
+        print!("{}", i18n::tf("port_use_next_prompt", &[&next]));
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        match input.trim().to_lowercase().as_str() {
+            "" | "y" | "yes" => Ok(next),
+            _ => Err(conflict()),
+        }
+    }
+
+    /// 检查端口映射的所有宿主机端口，单个端口冲突时按 [`PortCheck::resolve`] 换用空闲端口，端口范围冲突时报错
+    pub fn resolve_mappings(
+        &mut self,
+        mappings: &mut [PortMapping],
+        auto_port: bool,
+    ) -> Result<(), MddeError> {
+        let mut conflicts = Vec::new();
+        for mapping in mappings.iter_mut() {
+            let protocol = mapping.protocol.as_str();
+            for port in mapping.host_range().ports() {
+                let Some(holder) = self.holder(port, protocol) else {
+                    continue;
+                };
+                if mapping.host_range().is_single() {
+                    let free = self.resolve(port, protocol, &holder, auto_port)?;
+                    *mapping = mapping.with_host_port(free);
+                    break;
+                }
+                let port = format!("{}/{}", port, protocol);
+                println!("{}", i18n::tf("port_in_use", &[&port, &holder]).red());
+                conflicts.push(format!("{} ({})", port, holder));
+            }
+            // 已选用的端口不能再分配给后面的映射
+            for port in mapping.host_range().ports() {
+                self.reserve(port, protocol);
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(MddeError::PortConflict(conflicts))
+        }
+    }
+}
+
+fn container_holder(details: &ContainerDetails) -> PortHolder {
+    if details
+        .labels
+        .get(CONFIG_FILES_LABEL)
+        .is_some_and(|files| files.contains(".mdde"))
+    {
+        PortHolder::Environment {
+            container: details.name.clone(),
+            project_dir: details
+                .labels
+                .get(COMPOSE_WORKING_DIR_LABEL)
+                .map(|dir| {
+                    // 命名环境的目录为 .mdde/envs/<name>，项目目录是 .mdde 的上一级
+                    dir.rsplit_once(project::MDDE_DIR)
+                        .map_or(dir.as_str(), |(root, _)| root)
+                        .trim_end_matches(['/', '\\'])
+                })
+                .unwrap_or_default()
+                .to_string(),
+        }
+    } else {
+        PortHolder::Container(details.name.clone())
+    }
+}
+
+/// 解析 `compose config` 输出中所有发布到宿主机的端口
+///
+/// 同时支持短格式（`127.0.0.1:8080:80/udp`）和长格式（`published`/`target` 字段），端口范围会展开，
+/// 结果按端口排序且不重复。
+pub fn published_ports(compose_config: &str) -> Result<Vec<HostPort>, MddeError> {
+    let config: Value = serde_yaml::from_str(compose_config)?;
+    let mut ports = Vec::new();
+
+    let services = config.get("services").and_then(Value::as_mapping);
+    for service in services.into_iter().flat_map(|s| s.values()) {
+        let entries = service.get("ports").and_then(Value::as_sequence);
+        for entry in entries.into_iter().flatten() {
+            let (range, protocol) = match entry {
+                Value::String(mapping) => match mapping.parse::<PortMapping>() {
+                    Ok(PortMapping {
+                        host: Some(host),
+                        protocol,
+                        ..
+                    }) => (host, protocol.to_string()),
+                    // 只有容器端口时由引擎随机分配宿主机端口
+                    _ => continue,
+                },
+                Value::Mapping(_) => {
+                    let published = match entry.get("published") {
+                        Some(Value::String(port)) => port.clone(),
+                        Some(Value::Number(port)) => port.to_string(),
+                        _ => continue,
+                    };
+                    let Ok(published) = published.parse::<PortMapping>() else {
+                        continue;
+                    };
+                    let protocol = entry
+                        .get("protocol")
+                        .and_then(Value::as_str)
+                        .unwrap_or("tcp");
+                    (published.host_range(), protocol.to_string())
+                }
+                _ => continue,
+            };
+
+            for port in range.ports() {
+                ports.push(HostPort {
+                    port,
+                    protocol: protocol.clone(),
+                });
+            }
+        }
+    }
+
+    // 多个服务可能发布同一个端口，按端口排序后去重
+    ports.sort();
+    ports.dedup();
+    Ok(ports)
+}
+
+/// 尝试在所有地址上绑定端口，判断宿主机端口是否空闲
+fn host_port_available(port: u16, protocol: &str) -> bool {
+    let addr = (Ipv4Addr::UNSPECIFIED, port);
+    match protocol {
+        "udp" => UdpSocket::bind(addr).is_ok(),
+        _ => TcpListener::bind(addr).is_ok(),
+    }
+}
+
+/// 查找监听指定端口的进程
+#[cfg(target_os = "linux")]
+fn find_process(port: u16, protocol: &str) -> Option<(u32, String)> {
+    // /proc/net/{tcp,udp}[6] 中 st 列 0A 表示 LISTEN，UDP 的已绑定套接字为 07
+    let (tables, listen_state) = match protocol {
+        "udp" => (["/proc/net/udp", "/proc/net/udp6"], "07"),
+        _ => (["/proc/net/tcp", "/proc/net/tcp6"], "0A"),
+    };
+
+    let mut inodes = HashSet::new();
+    for table in tables {
+        let Ok(content) = std::fs::read_to_string(table) else {
+            continue;
+        };
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != listen_state {
+                continue;
+            }
+            let local_port = fields[1]
+                .rsplit_once(':')
+                .and_then(|(_, hex)| u16::from_str_radix(hex, 16).ok());
+            if local_port == Some(port) {
+                inodes.insert(format!("socket:[{}]", fields[9]));
+            }
+        }
+    }
+    if inodes.is_empty() {
+        return None;
+    }
+
+    // 没有权限读取的进程会被跳过
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let owns_socket = fds.flatten().any(|fd| {
+            std::fs::read_link(fd.path())
+                .is_ok_and(|target| inodes.contains(target.to_string_lossy().as_ref()))
+        });
+        if owns_socket {
+            let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            return Some((pid, name.trim().to_string()));
+        }
+    }
+
+    None
+}
+
+/// 查找监听指定端口的进程，依赖 `lsof`
+#[cfg(not(target_os = "linux"))]
+fn find_process(port: u16, protocol: &str) -> Option<(u32, String)> {
+    let mut cmd = std::process::Command::new("lsof");
+    cmd.arg("-nP").arg("-Fpc");
+    match protocol {
+        "udp" => cmd.arg(format!("-iUDP:{}", port)),
+        _ => cmd.arg(format!("-iTCP:{}", port)).arg("-sTCP:LISTEN"),
+    };
+    let output = cmd.output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // -F 输出每个字段一行，p 开头为 PID，c 开头为命令名
+    let pid = stdout
+        .lines()
+        .find_map(|l| l.strip_prefix('p'))?
+        .parse()
+        .ok()?;
+    let name = stdout
+        .lines()
+        .find_map(|l| l.strip_prefix('c'))
+        .unwrap_or_default();
+    Some((pid, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeContainer;
+    use crate::backend::{FakeBackend, PublishedPort};
+    use std::path::Path;
+
+    #[test]
+    fn test_published_ports() {
+        let config = r#"
+services:
+  app:
+    ports:
+      - "8080:80"
+      - "127.0.0.1:5353:53/udp"
+      - "9000"
+      - "7000-7002:7000-7002"
+  db:
+    ports:
+      - target: 5432
+        published: "15432"
+        protocol: tcp
+      - target: 6379
+  proxy:
+    ports:
+      - "8080:8080"
+"#;
+        let ports = published_ports(config).unwrap();
+        let ports: Vec<String> = ports.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            ports,
+            vec![
+                "5353/udp",
+                "7000/tcp",
+                "7001/tcp",
+                "7002/tcp",
+                "8080/tcp",
+                "15432/tcp"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_holder_from_containers() {
+        let project = ComposeProject::new(Path::new("/work/current"));
+        let own_file = project.compose_file.to_string_lossy().to_string();
+        let published = |port| PublishedPort {
+            container_port: 80,
+            protocol: "tcp".to_string(),
+            host_ip: None,
+            host_port: Some(port),
+        };
+
+        let mut own = FakeContainer::new("current-app-1", "node:22")
+            .label(CONFIG_FILES_LABEL, &own_file)
+            .running();
+        own.ports.push(published(18080));
+        let mut other = FakeContainer::new("other-app-1", "node:22")
+            .label(CONFIG_FILES_LABEL, "/work/other/.mdde/docker-compose.yml")
+            .label(COMPOSE_WORKING_DIR_LABEL, "/work/other/.mdde");
+        let mut named = FakeContainer::new("named-app-1", "node:22")
+            .label(
+                CONFIG_FILES_LABEL,
+                "/work/named/.mdde/envs/test/docker-compose.yml",
+            )
+            .label(COMPOSE_WORKING_DIR_LABEL, "/work/named/.mdde/envs/test");
+        named.ports.push(published(18083));
+        other.ports.push(published(18081));
+        let mut plain = FakeContainer::new("nginx", "nginx").running();
+        plain.ports.push(published(18082));
+
+        let backend = FakeBackend::new()
+            .with_container(own)
+            .with_container(other)
+            .with_container(named)
+            .with_container(plain);
+        let check = PortCheck::collect(&backend, &project);
+
+        assert_eq!(check.holder(18080, "tcp"), None);
+        assert_eq!(
+            check.holder(18081, "tcp"),
+            Some(PortHolder::Environment {
+                container: "other-app-1".to_string(),
+                project_dir: "/work/other".to_string()
+            })
+        );
+        assert_eq!(
+            check.holder(18083, "tcp"),
+            Some(PortHolder::Environment {
+                container: "named-app-1".to_string(),
+                project_dir: "/work/named".to_string()
+            })
+        );
+        assert_eq!(
+            check.holder(18082, "tcp"),
+            Some(PortHolder::Container("nginx".to_string()))
+        );
+        assert_eq!(check.next_free(18080, "tcp").map(|p| p > 18082), Some(true));
+    }
+
+    #[test]
+    fn test_holder_from_host_listener() {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let check = PortCheck::default();
+
+        match check.holder(port, "tcp") {
+            #[cfg(target_os = "linux")]
+            Some(PortHolder::Process { pid, .. }) => assert_eq!(pid, std::process::id()),
+            Some(_) => {}
+            None => panic!("port {} should be in use", port),
+        }
+
+        drop(listener);
+        assert_eq!(check.holder(port, "tcp"), None);
+    }
+}
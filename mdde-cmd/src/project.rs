@@ -0,0 +1,257 @@
+use crate::error::MddeError;
+use crate::i18n;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// mdde 的项目目录名
+pub const MDDE_DIR: &str = ".mdde";
+
+/// 命名环境所在的目录，位于 `.mdde/` 下
+pub const ENVS_DIR: &str = "envs";
+
+/// 记录默认环境名称的文件，位于 `.mdde/` 下
+pub const DEFAULT_ENV_FILE: &str = "default-env";
+
+/// 直接位于 `.mdde/` 下的环境的名称
+pub const DEFAULT_ENV: &str = "default";
+
+/// 通过 `--project-dir` 指定的项目根目录
+static PROJECT_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// 通过 `--env` 指定的环境
+static ENVIRONMENT: RwLock<Option<String>> = RwLock::new(None);
+
+/// 指定项目根目录，`None` 表示从当前目录向上查找
+pub fn set_project_dir(dir: Option<PathBuf>) -> Result<(), MddeError> {
+    let dir = match dir {
+        Some(dir) => {
+            let dir = std::env::current_dir()?.join(dir);
+            if !dir.is_dir() {
+                return Err(MddeError::InvalidArgument(i18n::tf(
+                    "project_dir_not_found",
+                    &[&dir.display()],
+                )));
+            }
+            Some(dir)
+        }
+        None => None,
+    };
+    if let Ok(mut project_dir) = PROJECT_DIR.write() {
+        *project_dir = dir;
+    }
+    Ok(())
+}
+
+/// 指定本次使用的环境，`None` 表示使用默认环境
+pub fn set_environment(name: Option<String>) -> Result<(), MddeError> {
+    if let Some(name) = &name {
+        validate_env_name(name)?;
+    }
+    if let Ok(mut environment) = ENVIRONMENT.write() {
+        *environment = name;
+    }
+    Ok(())
+}
+
+/// 环境名称只能包含小写字母、数字、`-` 和 `_`
+///
+/// 名称会用在 compose 项目名称中，compose 只接受小写的项目名称，不能通过转换大小写区分 `Dev` 和 `dev`。
+pub fn validate_env_name(name: &str) -> Result<(), MddeError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(MddeError::InvalidArgument(i18n::tf(
+            "invalid_env_name",
+            &[&name],
+        )))
+    }
+}
+
+/// 从 `start` 开始向上查找包含 `.mdde/` 的目录
+///
+/// 到达 git 仓库根目录或跨越文件系统时停止查找，找不到时返回 `None`。
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    let start_device = device(start);
+    for dir in start.ancestors() {
+        if start_device.is_some() && device(dir) != start_device {
+            break;
+        }
+        if dir.join(MDDE_DIR).is_dir() {
+            return Some(dir.to_path_buf());
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
+
+/// 当前项目的根目录：优先使用 `--project-dir`，否则从当前目录向上查找，都没有时为当前目录
+pub fn root() -> Result<PathBuf, MddeError> {
+    if let Some(dir) = PROJECT_DIR.read().ok().and_then(|dir| dir.clone()) {
+        return Ok(dir);
+    }
+    let current_dir = std::env::current_dir()?;
+    Ok(discover(&current_dir).unwrap_or(current_dir))
+}
+
+/// 当前使用的环境：优先使用 `--env`，其次是项目记录的默认环境，都没有时为 `default`
+pub fn environment() -> Result<String, MddeError> {
+    if let Some(name) = ENVIRONMENT.read().ok().and_then(|name| name.clone()) {
+        return Ok(name);
+    }
+    Ok(default_environment(&root()?)?.unwrap_or_else(|| DEFAULT_ENV.to_string()))
+}
+
+/// 项目记录的默认环境，没有记录时为 `None`
+///
+/// 记录文件可能被手动修改，读取时同样检查名称，避免 `../..` 这样的值被拼接到环境目录中。
+pub fn default_environment(root: &Path) -> Result<Option<String>, MddeError> {
+    let Ok(content) = std::fs::read_to_string(root.join(MDDE_DIR).join(DEFAULT_ENV_FILE)) else {
+        return Ok(None);
+    };
+    let name = content.trim();
+    if name.is_empty() {
+        return Ok(None);
+    }
+    validate_env_name(name)?;
+    Ok(Some(name.to_string()))
+}
+
+/// 记录项目的默认环境，设置为 `default` 时删除记录
+pub fn set_default_environment(root: &Path, name: &str) -> Result<(), MddeError> {
+    validate_env_name(name)?;
+    let path = root.join(MDDE_DIR).join(DEFAULT_ENV_FILE);
+    if name == DEFAULT_ENV {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    } else {
+        std::fs::create_dir_all(root.join(MDDE_DIR))?;
+        std::fs::write(path, format!("{}\n", name))?;
+    }
+    Ok(())
+}
+
+/// 环境的目录：`default` 为 `.mdde/`，其他环境为 `.mdde/envs/<name>/`
+pub fn env_dir_in(root: &Path, name: &str) -> PathBuf {
+    let mdde_dir = root.join(MDDE_DIR);
+    if name == DEFAULT_ENV {
+        mdde_dir
+    } else {
+        mdde_dir.join(ENVS_DIR).join(name)
+    }
+}
+
+/// 当前环境的目录
+pub fn env_dir() -> Result<PathBuf, MddeError> {
+    Ok(env_dir_in(&root()?, &environment()?))
+}
+
+/// 项目中已创建的环境（包含 compose 文件的环境目录），按名称排序，`default` 在最前
+pub fn environments(root: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(root.join(MDDE_DIR).join(ENVS_DIR)) {
+        names.extend(
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| entry.path().join("docker-compose.yml").is_file())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name != DEFAULT_ENV && validate_env_name(name).is_ok()),
+        );
+    }
+    names.sort();
+    if env_dir_in(root, DEFAULT_ENV)
+        .join("docker-compose.yml")
+        .is_file()
+    {
+        names.insert(0, DEFAULT_ENV.to_string());
+    }
+    names
+}
+
+/// 目录所在的设备号，用于判断是否跨越了文件系统
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_walks_up_to_mdde_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("src").join("api");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(discover(&nested), None);
+
+        std::fs::create_dir(dir.path().join(MDDE_DIR)).unwrap();
+        assert_eq!(discover(&nested).as_deref(), Some(dir.path()));
+        assert_eq!(discover(dir.path()).as_deref(), Some(dir.path()));
+    }
+
+    #[test]
+    fn test_discover_stops_at_git_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(MDDE_DIR)).unwrap();
+        let repo = dir.path().join("repo");
+        let nested = repo.join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(repo.join(".git")).unwrap();
+
+        assert_eq!(discover(&nested), None);
+
+        // 仓库根目录本身的 .mdde 仍然可以找到
+        std::fs::create_dir(repo.join(MDDE_DIR)).unwrap();
+        assert_eq!(discover(&nested), Some(repo));
+    }
+
+    #[test]
+    fn test_environments_and_default_pointer() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(environments(root).is_empty());
+        assert_eq!(default_environment(root).unwrap(), None);
+
+        for name in [DEFAULT_ENV, "test", "dev"] {
+            let env_dir = env_dir_in(root, name);
+            std::fs::create_dir_all(&env_dir).unwrap();
+            std::fs::write(env_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+        }
+        // 没有 compose 文件的目录不算环境
+        std::fs::create_dir_all(env_dir_in(root, "empty")).unwrap();
+
+        assert_eq!(env_dir_in(root, DEFAULT_ENV), root.join(MDDE_DIR));
+        assert_eq!(
+            env_dir_in(root, "dev"),
+            root.join(MDDE_DIR).join(ENVS_DIR).join("dev")
+        );
+        assert_eq!(environments(root), vec!["default", "dev", "test"]);
+
+        set_default_environment(root, "dev").unwrap();
+        assert_eq!(default_environment(root).unwrap().as_deref(), Some("dev"));
+        set_default_environment(root, DEFAULT_ENV).unwrap();
+        assert_eq!(default_environment(root).unwrap(), None);
+
+        assert!(set_default_environment(root, "../escape").is_err());
+        // 手动写入的无效名称在读取时报错
+        std::fs::write(root.join(MDDE_DIR).join(DEFAULT_ENV_FILE), "../..\n").unwrap();
+        assert!(default_environment(root).is_err());
+        assert!(validate_env_name("").is_err());
+        assert!(validate_env_name("test_2-b").is_ok());
+        // 大小写不同的名称会对应同一个 compose 项目
+        assert!(validate_env_name("Dev").is_err());
+    }
+}
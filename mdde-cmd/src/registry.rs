@@ -0,0 +1,195 @@
+use crate::compose::ComposeProject;
+use crate::error::MddeError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 本机所有 mdde 环境的登记表，`create` 时登记，用于跨项目查看和停止环境
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub environments: Vec<RegistryEntry>,
+}
+
+/// 登记表中的一个环境，以项目目录和环境名称区分
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub project_dir: PathBuf,
+    pub environment: String,
+    pub container_name: String,
+    /// 创建时使用的模板，如 `node22`
+    pub template: String,
+    #[serde(default)]
+    pub ports: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RegistryEntry {
+    /// 环境的 compose 项目
+    pub fn compose_project(&self) -> ComposeProject {
+        ComposeProject::in_environment(&self.project_dir, &self.environment)
+    }
+
+    /// 环境目录和 compose 文件是否仍然存在
+    pub fn exists(&self) -> bool {
+        self.compose_project().compose_file.is_file()
+    }
+
+    fn same_environment(&self, other: &RegistryEntry) -> bool {
+        self.project_dir == other.project_dir && self.environment == other.environment
+    }
+}
+
+impl Registry {
+    /// 登记表文件路径，可以通过 `MDDE_REGISTRY_FILE` 环境变量指定
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("MDDE_REGISTRY_FILE") {
+            return Some(PathBuf::from(path));
+        }
+        dirs::data_dir().map(|dir| dir.join("mdde").join("registry.json"))
+    }
+
+    /// 读取登记表，文件不存在时为空
+    pub fn load() -> Result<Self, MddeError> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, MddeError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| MddeError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// 保存登记表，先写入临时文件再替换，避免并发写入时损坏
+    pub fn save(&self) -> Result<(), MddeError> {
+        match Self::path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), MddeError> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.persist(path).map_err(|e| MddeError::Io(e.error))?;
+        Ok(())
+    }
+
+    /// 登记环境，同一项目中同名的环境会被替换
+    pub fn register(&mut self, mut entry: RegistryEntry) {
+        entry.project_dir = canonical(&entry.project_dir);
+        match self
+            .environments
+            .iter_mut()
+            .find(|existing| existing.same_environment(&entry))
+        {
+            Some(existing) => *existing = entry,
+            None => self.environments.push(entry),
+        }
+    }
+
+    /// 移除项目中的环境，返回是否存在该环境
+    pub fn unregister(&mut self, project_dir: &Path, environment: &str) -> bool {
+        let project_dir = canonical(project_dir);
+        let before = self.environments.len();
+        self.environments
+            .retain(|entry| entry.project_dir != project_dir || entry.environment != environment);
+        self.environments.len() != before
+    }
+
+    /// 移除目录已不存在的环境，返回被移除的条目
+    pub fn prune(&mut self) -> Vec<RegistryEntry> {
+        let (kept, removed) = std::mem::take(&mut self.environments)
+            .into_iter()
+            .partition(RegistryEntry::exists);
+        self.environments = kept;
+        removed
+    }
+
+    /// 项目中登记的环境
+    pub fn in_project(&self, project_dir: &Path) -> Vec<&RegistryEntry> {
+        let project_dir = canonical(project_dir);
+        self.environments
+            .iter()
+            .filter(|entry| entry.project_dir == project_dir)
+            .collect()
+    }
+}
+
+/// 规范化的绝对路径，路径不存在时保持原样
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project;
+
+    fn entry(project_dir: &Path, environment: &str, container_name: &str) -> RegistryEntry {
+        RegistryEntry {
+            project_dir: project_dir.to_path_buf(),
+            environment: environment.to_string(),
+            container_name: container_name.to_string(),
+            template: "node22".to_string(),
+            ports: vec!["8080:80".to_string()],
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_register_replaces_same_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = Registry::default();
+        registry.register(entry(dir.path(), "default", "first"));
+        registry.register(entry(dir.path(), "test", "other"));
+        registry.register(entry(dir.path(), "default", "second"));
+
+        assert_eq!(registry.environments.len(), 2);
+        assert_eq!(registry.in_project(dir.path())[0].container_name, "second");
+
+        assert!(registry.unregister(dir.path(), "test"));
+        assert!(!registry.unregister(dir.path(), "test"));
+        assert_eq!(registry.environments.len(), 1);
+    }
+
+    #[test]
+    fn test_prune_and_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = dir.path().join("live");
+        let env_dir = project::env_dir_in(&live, "dev");
+        std::fs::create_dir_all(&env_dir).unwrap();
+        std::fs::write(env_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+        let mut registry = Registry::default();
+        registry.register(entry(&live, "dev", "live"));
+        registry.register(entry(&dir.path().join("gone"), "default", "gone"));
+
+        let path = dir.path().join("data").join("registry.json");
+        registry.save_to(&path).unwrap();
+        let mut loaded = Registry::load_from(&path).unwrap();
+        assert_eq!(loaded, registry);
+
+        let removed = loaded.prune();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].container_name, "gone");
+        assert_eq!(loaded.environments.len(), 1);
+        assert_eq!(loaded.environments[0].container_name, "live");
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(Registry::load_from(&path).is_err());
+        assert_eq!(
+            Registry::load_from(&dir.path().join("missing.json")).unwrap(),
+            Registry::default()
+        );
+    }
+}
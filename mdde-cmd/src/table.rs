@@ -0,0 +1,66 @@
+use colored::*;
+
+/// 命令输出中的对齐表格，列宽取表头和所有单元格中最长的字符数
+#[derive(Debug, Clone)]
+pub struct Table {
+    headers: Vec<String>,
+    widths: Vec<usize>,
+}
+
+impl Table {
+    pub fn new<R: AsRef<[String]>>(headers: &[&str], rows: &[R]) -> Self {
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+        for row in rows {
+            for (width, cell) in widths.iter_mut().zip(row.as_ref()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            widths,
+        }
+    }
+
+    /// 加粗的表头
+    pub fn header(&self) -> String {
+        self.row(&self.headers).bold().to_string()
+    }
+
+    /// 左对齐的一行，列之间以两个空格分隔，去掉行尾的空白
+    pub fn row(&self, cells: &[String]) -> String {
+        cells
+            .iter()
+            .zip(&self.widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// 渲染表头和所有行，行不需要单独着色时使用
+pub fn render<R: AsRef<[String]>>(headers: &[&str], rows: &[R]) -> Vec<String> {
+    let table = Table::new(headers, rows);
+    let mut lines = vec![table.header()];
+    lines.extend(rows.iter().map(|row| table.row(row.as_ref())));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_are_aligned() {
+        let rows = vec![
+            ["node22".to_string(), "运行中".to_string(), String::new()],
+            ["a".to_string(), "-".to_string(), "8080".to_string()],
+        ];
+        let table = Table::new(&["NAME", "STATE", "PORTS"], &rows);
+        assert_eq!(table.row(&rows[0]), "node22  运行中");
+        assert_eq!(table.row(&rows[1]), "a       -      8080");
+        assert!(table.header().contains("NAME    STATE  PORTS"));
+        assert_eq!(render(&["NAME", "STATE", "PORTS"], &rows).len(), 3);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::MddeError;
+use crate::i18n;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// 下载过的模板文件的本地缓存，按模板服务器地址和文件路径存放
+///
+/// 每个文件保存在 `<服务器>/files/<路径>`，对应的 [`CacheEntry`] 保存在 `<服务器>/meta/<路径>.json`。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateCache {
+    dir: PathBuf,
+}
+
+/// 缓存中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// 模板服务器地址，即 `Config::host`
+    pub base_url: String,
+    /// 相对于服务器地址的路径，如 `node22/docker-compose.yml`
+    pub path: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// 服务器返回 404，离线时同样视为不存在
+    #[serde(default)]
+    pub missing: bool,
+    pub size: u64,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// 从缓存中读取的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cached {
+    pub entry: CacheEntry,
+    /// 文件内容，`entry.missing` 时为 `None`
+    pub content: Option<String>,
+}
+
+impl Cached {
+    /// 文件内容，服务器上不存在的文件与在线时一样返回 404
+    pub fn into_content(self) -> Result<String, MddeError> {
+        self.content.ok_or(MddeError::HttpStatus(404))
+    }
+}
+
+/// 服务器返回的缓存验证信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl TemplateCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 缓存目录，可以通过 `MDDE_CACHE_DIR` 环境变量指定
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("MDDE_CACHE_DIR") {
+            return Some(PathBuf::from(path));
+        }
+        dirs::cache_dir().map(|dir| dir.join("mdde").join("templates"))
+    }
+
+    /// 默认位置的缓存，无法确定缓存目录时为 `None`
+    pub fn open() -> Option<Self> {
+        Self::path().map(Self::new)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 读取缓存的文件，不存在、已损坏或属于其他服务器时为 `None`
+    pub fn lookup(&self, base_url: &str, path: &str) -> Option<Cached> {
+        let (file, meta) = self.locate(base_url, path).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&std::fs::read_to_string(meta).ok()?).ok()?;
+        // 不同的服务器地址可能对应同一个目录名
+        if entry.base_url != base_url || entry.path != path {
+            return None;
+        }
+        let content = if entry.missing {
+            None
+        } else {
+            Some(std::fs::read_to_string(file).ok()?)
+        };
+        Some(Cached { entry, content })
+    }
+
+    /// 保存下载的文件，`content` 为 `None` 表示服务器上不存在该文件
+    pub fn store(
+        &self,
+        base_url: &str,
+        path: &str,
+        content: Option<&str>,
+        validators: Validators,
+    ) -> Result<CacheEntry, MddeError> {
+        let (file, meta) = self.locate(base_url, path)?;
+        let entry = CacheEntry {
+            base_url: base_url.to_string(),
+            path: path.to_string(),
+            etag: validators.etag,
+            last_modified: validators.last_modified,
+            missing: content.is_none(),
+            size: content.map_or(0, |content| content.len() as u64),
+            fetched_at: Utc::now(),
+        };
+
+        for dir in [file.parent(), meta.parent()].into_iter().flatten() {
+            std::fs::create_dir_all(dir)?;
+        }
+        match content {
+            Some(content) => std::fs::write(&file, content)?,
+            None if file.exists() => std::fs::remove_file(&file)?,
+            None => {}
+        }
+        std::fs::write(&meta, serde_json::to_string_pretty(&entry)?)?;
+        Ok(entry)
+    }
+
+    /// 缓存中的所有文件，按服务器和路径排序
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, MddeError> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for item in walkdir::WalkDir::new(&self.dir) {
+            let item = item.map_err(|e| MddeError::FileOperation(e.to_string()))?;
+            let is_meta = item.file_type().is_file()
+                && item.path().extension().is_some_and(|ext| ext == "json")
+                && item.path().components().any(|c| c.as_os_str() == "meta");
+            if !is_meta {
+                continue;
+            }
+            if let Ok(entry) = serde_json::from_str(&std::fs::read_to_string(item.path())?) {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by(|a: &CacheEntry, b| (&a.base_url, &a.path).cmp(&(&b.base_url, &b.path)));
+        Ok(entries)
+    }
+
+    /// 清空缓存，返回删除的文件数和大小
+    pub fn clear(&self) -> Result<(usize, u64), MddeError> {
+        let entries = self.entries()?;
+        if self.dir.exists() {
+            std::fs::remove_dir_all(&self.dir)?;
+        }
+        Ok((entries.len(), entries.iter().map(|entry| entry.size).sum()))
+    }
+
+    /// 文件和元数据的路径，路径中不能包含 `..` 或绝对路径
+    fn locate(&self, base_url: &str, path: &str) -> Result<(PathBuf, PathBuf), MddeError> {
+        let relative = Path::new(path);
+        if path.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(MddeError::InvalidArgument(i18n::tf(
+                "invalid_template_path",
+                &[&path],
+            )));
+        }
+
+        let server = self.dir.join(server_dir(base_url));
+        let mut meta = server.join("meta").join(relative).into_os_string();
+        meta.push(".json");
+        Ok((server.join("files").join(relative), PathBuf::from(meta)))
+    }
+}
+
+/// 服务器地址对应的目录名，如 `https://example.com/templates` 为 `example.com_templates`
+fn server_dir(base_url: &str) -> String {
+    let address = base_url
+        .split_once("://")
+        .map_or(base_url, |(_, rest)| rest)
+        .trim_end_matches('/');
+    address
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "https://example.com/templates";
+
+    #[test]
+    fn test_store_and_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = TemplateCache::new(dir.path());
+        assert!(cache.lookup(HOST, "node22/docker-compose.yml").is_none());
+
+        let validators = Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        cache
+            .store(
+                HOST,
+                "node22/docker-compose.yml",
+                Some("services: {}\n"),
+                validators,
+            )
+            .unwrap();
+        cache
+            .store(HOST, "node22/Dockerfile", None, Validators::default())
+            .unwrap();
+
+        let cached = cache.lookup(HOST, "node22/docker-compose.yml").unwrap();
+        assert_eq!(cached.entry.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(cached.entry.size, 13);
+        assert_eq!(cached.into_content().unwrap(), "services: {}\n");
+        assert!(matches!(
+            cache
+                .lookup(HOST, "node22/Dockerfile")
+                .unwrap()
+                .into_content(),
+            Err(MddeError::HttpStatus(404))
+        ));
+        // 目录名相同的其他服务器
+        assert!(cache
+            .lookup("http://example.com/templates", "node22/docker-compose.yml")
+            .is_none());
+
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "node22/Dockerfile");
+        assert_eq!(cache.clear().unwrap(), (2, 13));
+        assert!(cache.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_cache() {
+        let cache = TemplateCache::new("/tmp/mdde-cache");
+        assert!(cache.locate(HOST, "../secret").is_err());
+        assert!(cache.locate(HOST, "/etc/passwd").is_err());
+        assert!(cache.locate(HOST, "").is_err());
+        assert_eq!(server_dir("http://127.0.0.1:8080/"), "127.0.0.1_8080");
+    }
+}
@@ -0,0 +1,281 @@
+use crate::error::MddeError;
+use crate::i18n;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// 环境目录中记录模板文件摘要的文件
+pub const LOCK_FILE: &str = "template-lock.json";
+
+/// 模板清单的签名文件后缀，如 `manifest.json.sig`
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+
+/// 内容的 SHA-256 摘要，小写十六进制
+pub fn sha256_hex(content: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, content)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 是否为 SHA-256 摘要的十六进制形式
+pub fn is_sha256_hex(digest: &str) -> bool {
+    digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// 信任的 Ed25519 公钥，配置中为 base64 编码的 32 字节公钥
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TrustedKey(String);
+
+impl TrustedKey {
+    /// 解析逗号分隔的列表
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, MddeError> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// 格式化为逗号分隔的列表
+    pub fn format_list(keys: &[Self]) -> String {
+        keys.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        // 解析时已检查过格式
+        BASE64.decode(&self.0).unwrap_or_default()
+    }
+}
+
+impl FromStr for TrustedKey {
+    type Err = MddeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match BASE64.decode(s) {
+            Ok(bytes) if bytes.len() == 32 => Ok(Self(s.to_string())),
+            _ => Err(MddeError::Config(i18n::tf("invalid_trusted_key", &[&s]))),
+        }
+    }
+}
+
+impl fmt::Display for TrustedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// 模板签名覆盖的内容：模板名称、换行和 `manifest.json` 的原始内容
+///
+/// 名称也在签名范围内，一个模板的签名清单不能冒充另一个模板。
+pub fn signed_payload(template: &str, manifest: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(template.len() + 1 + manifest.len());
+    payload.extend_from_slice(template.as_bytes());
+    payload.push(b'\n');
+    payload.extend_from_slice(manifest);
+    payload
+}
+
+/// 检查 `message` 的分离签名，签名为 base64 编码，由任意一个信任的公钥验证通过即可
+pub fn verify_signature(
+    keys: &[TrustedKey],
+    message: &[u8],
+    signature: &str,
+) -> Result<(), MddeError> {
+    let signature = BASE64
+        .decode(signature.trim())
+        .map_err(|_| MddeError::IntegrityCheck(i18n::t("invalid_signature").to_string()))?;
+    let verified = keys.iter().any(|key| {
+        UnparsedPublicKey::new(&ED25519, key.bytes())
+            .verify(message, &signature)
+            .is_ok()
+    });
+    if verified {
+        Ok(())
+    } else {
+        Err(MddeError::IntegrityCheck(
+            i18n::t("untrusted_signature").to_string(),
+        ))
+    }
+}
+
+/// 创建环境时写入的模板文件及其摘要，`mdde verify` 据此检查文件是否被修改
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateLock {
+    /// 创建时使用的模板，非默认来源时带有来源名称
+    pub template: String,
+    /// 模板所在的来源
+    pub source: String,
+    /// 模板清单的签名是否经过信任的公钥验证
+    pub signed: bool,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<LockedFile>,
+    /// 创建时使用的模板变量的值
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// 写入环境目录的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedFile {
+    /// 相对于环境目录的路径
+    pub target: String,
+    pub sha256: String,
+}
+
+/// 文件与记录的摘要比较的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Ok,
+    Modified,
+    Missing,
+}
+
+impl FileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Ok => "ok",
+            FileStatus::Modified => "modified",
+            FileStatus::Missing => "missing",
+        }
+    }
+}
+
+/// 一个文件的检查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileCheck {
+    pub target: String,
+    pub status: FileStatus,
+    pub expected: String,
+    pub actual: Option<String>,
+}
+
+impl TemplateLock {
+    /// 读取环境目录中的记录，没有记录时为 `None`
+    pub fn load(env_dir: &Path) -> Result<Option<Self>, MddeError> {
+        let path = env_dir.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    pub fn save(&self, env_dir: &Path) -> Result<(), MddeError> {
+        std::fs::write(env_dir.join(LOCK_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 按记录的顺序检查环境目录中的文件
+    pub fn check(&self, env_dir: &Path) -> Result<Vec<FileCheck>, MddeError> {
+        self.files
+            .iter()
+            .map(|file| {
+                let path = env_dir.join(&file.target);
+                let actual = if path.is_file() {
+                    Some(sha256_hex(&std::fs::read(&path)?))
+                } else {
+                    None
+                };
+                let status = match &actual {
+                    None => FileStatus::Missing,
+                    Some(actual) if *actual == file.sha256 => FileStatus::Ok,
+                    Some(_) => FileStatus::Modified,
+                };
+                Ok(FileCheck {
+                    target: file.target.clone(),
+                    status,
+                    expected: file.sha256.clone(),
+                    actual,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(is_sha256_hex(&sha256_hex(b"")));
+        assert!(!is_sha256_hex("ba7816bf"));
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let keys: Vec<TrustedKey> = vec![BASE64.encode(pair.public_key()).parse().unwrap()];
+        let signature = BASE64.encode(pair.sign(b"manifest"));
+
+        assert!(verify_signature(&keys, b"manifest", &signature).is_ok());
+        assert!(verify_signature(&keys, b"tampered", &signature).is_err());
+        assert!(verify_signature(&keys, b"manifest", "not base64!").is_err());
+        assert!(verify_signature(&[], b"manifest", &signature).is_err());
+
+        // 签名绑定模板名称
+        let payload = signed_payload("node22", b"manifest");
+        let signature = BASE64.encode(pair.sign(&payload));
+        assert!(verify_signature(&keys, &payload, &signature).is_ok());
+        let other = signed_payload("java21", b"manifest");
+        assert!(verify_signature(&keys, &other, &signature).is_err());
+
+        assert!("c2hvcnQ=".parse::<TrustedKey>().is_err());
+        assert_eq!(TrustedKey::parse_list(" , ").unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_check_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("docker-compose.yml"), "services: {}\n").unwrap();
+        std::fs::write(dir.path().join("entrypoint.sh"), "#!/bin/sh\n").unwrap();
+        let lock = TemplateLock {
+            template: "node22".to_string(),
+            source: "default".to_string(),
+            signed: false,
+            created_at: Utc::now(),
+            files: ["docker-compose.yml", "entrypoint.sh", "Dockerfile"]
+                .iter()
+                .map(|target| LockedFile {
+                    target: target.to_string(),
+                    sha256: sha256_hex(b"#!/bin/sh\n"),
+                })
+                .collect(),
+            variables: BTreeMap::new(),
+        };
+        lock.save(dir.path()).unwrap();
+        assert_eq!(TemplateLock::load(dir.path()).unwrap(), Some(lock.clone()));
+
+        let statuses: Vec<_> = lock
+            .check(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|check| check.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [FileStatus::Modified, FileStatus::Ok, FileStatus::Missing]
+        );
+    }
+}
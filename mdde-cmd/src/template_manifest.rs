@@ -0,0 +1,234 @@
+use crate::error::MddeError;
+use crate::i18n;
+use crate::project;
+use crate::template_integrity::{self, LOCK_FILE};
+use crate::template_vars::{self, TemplateVariable};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+/// 每个模板必须包含的 compose 文件，写入环境目录后由 compose 读取
+pub const COMPOSE_FILE: &str = "docker-compose.yml";
+
+/// 模板目录中的清单文件名
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// 模板中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateFile {
+    /// 相对于模板目录的路径
+    pub path: String,
+    /// 相对于环境目录的写入路径，未设置时与 `path` 相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// 写入后设置可执行权限，仅在 Unix 上生效
+    #[serde(default)]
+    pub executable: bool,
+    /// 模板中没有该文件时跳过
+    #[serde(default)]
+    pub optional: bool,
+    /// 文件内容的 SHA-256 摘要，十六进制；设置时下载的内容必须与之一致
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl TemplateFile {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            target: None,
+            executable: false,
+            optional: false,
+            sha256: None,
+        }
+    }
+
+    /// 相对于环境目录的写入路径
+    pub fn target(&self) -> &str {
+        self.target.as_deref().unwrap_or(&self.path)
+    }
+
+    /// 检查下载的内容与清单中的摘要是否一致，清单中没有摘要时不检查
+    pub fn check_digest(&self, content: &str) -> Result<(), MddeError> {
+        let Some(expected) = &self.sha256 else {
+            return Ok(());
+        };
+        let actual = template_integrity::sha256_hex(content.as_bytes());
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(MddeError::IntegrityCheck(i18n::tf(
+                "digest_mismatch",
+                &[&self.path, expected, &actual],
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 模板的文件清单，来自模板目录中的 `manifest.json` 或 `index.json` 中的 `files`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateManifest {
+    pub files: Vec<TemplateFile>,
+    /// 模板声明的变量，有变量时所有文件在写入前都会渲染
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<TemplateVariable>,
+}
+
+impl Default for TemplateManifest {
+    /// 没有清单的模板：`docker-compose.yml` 和可选的 `Dockerfile`
+    fn default() -> Self {
+        Self {
+            files: vec![
+                TemplateFile::new(COMPOSE_FILE),
+                TemplateFile {
+                    optional: true,
+                    ..TemplateFile::new("Dockerfile")
+                },
+            ],
+            variables: Vec::new(),
+        }
+    }
+}
+
+impl TemplateManifest {
+    /// 解析 `manifest.json` 并检查其中的路径
+    pub fn parse(content: &str) -> Result<Self, MddeError> {
+        let manifest: Self = serde_json::from_str(content)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// 检查文件路径：不能离开模板或环境目录、写入路径不能重复、不能覆盖 mdde 自己的文件，
+    /// 并且必须有一个必需的文件写入 `docker-compose.yml`；同时检查变量的声明
+    pub fn validate(&self) -> Result<(), MddeError> {
+        template_vars::validate_all(&self.variables)?;
+        let mut targets = HashSet::new();
+        for file in &self.files {
+            relative_path(&file.path)?;
+            if let Some(digest) = &file.sha256 {
+                if !template_integrity::is_sha256_hex(digest) {
+                    return Err(MddeError::Config(i18n::tf(
+                        "invalid_digest",
+                        &[&file.path, digest],
+                    )));
+                }
+            }
+            let target = relative_path(file.target())?;
+            let first = target.components().next().map(|c| c.as_os_str());
+            let reserved = [
+                project::ENVS_DIR,
+                project::DEFAULT_ENV_FILE,
+                "cfg.env",
+                LOCK_FILE,
+            ];
+            if reserved.iter().any(|name| first == Some(name.as_ref())) {
+                return Err(MddeError::Config(i18n::tf(
+                    "manifest_reserved_target",
+                    &[&file.target()],
+                )));
+            }
+            if !targets.insert(target) {
+                return Err(MddeError::Config(i18n::tf(
+                    "manifest_duplicate_target",
+                    &[&file.target()],
+                )));
+            }
+        }
+
+        if !self
+            .files
+            .iter()
+            .any(|file| !file.optional && file.target() == COMPOSE_FILE)
+        {
+            return Err(MddeError::Config(i18n::tf(
+                "manifest_missing_compose",
+                &[&COMPOSE_FILE],
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 只由普通路径组成的相对路径，不能包含 `..` 或绝对路径
+fn relative_path(path: &str) -> Result<PathBuf, MddeError> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(MddeError::Config(i18n::tf(
+            "invalid_template_path",
+            &[&path],
+        )));
+    }
+    Ok(relative.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = TemplateManifest::parse(
+            r#"{"files": [
+                {"path": "docker-compose.yml"},
+                {"path": "scripts/entrypoint.sh", "target": "entrypoint.sh", "executable": true},
+                {"path": "initdb.sql", "target": "initdb/01.sql", "optional": true}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert_eq!(manifest.files[0], TemplateFile::new(COMPOSE_FILE));
+        assert_eq!(manifest.files[1].target(), "entrypoint.sh");
+        assert!(manifest.files[1].executable);
+        assert!(manifest.files[2].optional);
+        assert!(TemplateManifest::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_check_digest() {
+        let file = TemplateFile {
+            sha256: Some(
+                "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD".to_string(),
+            ),
+            ..TemplateFile::new("init.sql")
+        };
+        assert!(file.check_digest("abc").is_ok());
+        assert!(matches!(
+            file.check_digest("abd"),
+            Err(MddeError::IntegrityCheck(_))
+        ));
+        assert!(TemplateFile::new("init.sql").check_digest("abd").is_ok());
+    }
+
+    #[test]
+    fn test_validate_manifest() {
+        let invalid = |files: &str| {
+            TemplateManifest::parse(&format!(
+                r#"{{"files": [{{"path": "docker-compose.yml"}}, {}]}}"#,
+                files
+            ))
+            .is_err()
+        };
+        assert!(invalid(r#"{"path": "../secret"}"#));
+        assert!(invalid(r#"{"path": "init.sh", "target": "/etc/profile"}"#));
+        assert!(invalid(r#"{"path": "cfg.env"}"#));
+        assert!(invalid(
+            r#"{"path": "other.yml", "target": "envs/test/docker-compose.yml"}"#
+        ));
+        assert!(invalid(
+            r#"{"path": "other.yml", "target": "docker-compose.yml"}"#
+        ));
+        assert!(invalid(r#"{"path": "template-lock.json"}"#));
+        assert!(invalid(r#"{"path": "init.sh", "sha256": "abc"}"#));
+        assert!(!invalid(r#"{"path": "init.sh", "executable": true}"#));
+
+        // 没有必需的 compose 文件
+        assert!(TemplateManifest::parse(r#"{"files": [{"path": "Dockerfile"}]}"#).is_err());
+        assert!(TemplateManifest::parse(
+            r#"{"files": [{"path": "docker-compose.yml", "optional": true}]}"#
+        )
+        .is_err());
+    }
+}
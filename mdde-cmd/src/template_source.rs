@@ -0,0 +1,521 @@
+use crate::commands::create::DevEnvironment;
+use crate::error::MddeError;
+use crate::http::MddeClient;
+use crate::i18n;
+use crate::template_cache::TemplateCache;
+use crate::template_integrity::{self, TrustedKey, SIGNATURE_SUFFIX};
+use crate::template_manifest::{TemplateManifest, COMPOSE_FILE, MANIFEST_FILE};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// `host` 对应的模板来源名称
+pub const DEFAULT_SOURCE: &str = "default";
+
+/// 模板来源的位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceLocation {
+    /// HTTP(S) 模板服务器的基础地址
+    Http(String),
+    /// 本地目录，如仓库的检出目录
+    Local(PathBuf),
+}
+
+impl FromStr for SourceLocation {
+    type Err = MddeError;
+
+    /// `http://`/`https://` 地址、`file://` 地址或目录路径，目录可以以 `~/` 开头
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Ok(SourceLocation::Http(s.trim_end_matches('/').to_string()));
+        }
+        let path = match s.strip_prefix("file://") {
+            Some(path) => path,
+            None if s.contains("://") => {
+                return Err(MddeError::Config(i18n::tf(
+                    "invalid_template_source",
+                    &[&s],
+                )))
+            }
+            None => s,
+        };
+        if path.is_empty() {
+            return Err(MddeError::Config(i18n::tf(
+                "invalid_template_source",
+                &[&s],
+            )));
+        }
+        let path = match (path.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(path),
+        };
+        Ok(SourceLocation::Local(path))
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceLocation::Http(url) => write!(f, "{}", url),
+            SourceLocation::Local(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// 一个命名的模板来源，配置中写作 `name=location`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateSource {
+    pub name: String,
+    pub location: SourceLocation,
+}
+
+impl TemplateSource {
+    /// `host` 对应的来源，名称为 [`DEFAULT_SOURCE`]
+    pub fn from_host(host: &str) -> Result<Self, MddeError> {
+        Ok(Self {
+            name: DEFAULT_SOURCE.to_string(),
+            location: host.parse()?,
+        })
+    }
+
+    /// 解析逗号分隔的列表
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, MddeError> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|source| !source.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    /// 格式化为逗号分隔的列表
+    pub fn format_list(sources: &[Self]) -> String {
+        sources
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl FromStr for TemplateSource {
+    type Err = MddeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MddeError::Config(i18n::tf("invalid_template_source", &[&s]));
+        let (name, location) = s.split_once('=').ok_or_else(invalid)?;
+        let name = name.trim();
+        if name.is_empty()
+            || name == DEFAULT_SOURCE
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            location: location.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.location)
+    }
+}
+
+/// `create` 的模板参数，`source:name` 指定来源，只写名称时按顺序在所有来源中查找
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateRef {
+    pub source: Option<String>,
+    pub name: String,
+}
+
+impl TemplateRef {
+    pub fn parse(s: &str) -> Self {
+        match s.split_once(':') {
+            Some((source, name)) => Self {
+                source: Some(source.to_string()),
+                name: name.to_string(),
+            },
+            None => Self {
+                source: None,
+                name: s.to_string(),
+            },
+        }
+    }
+}
+
+/// 带来源的开发环境，来自各来源 `index.json` 合并后的列表
+#[derive(Debug, Clone)]
+pub struct SourcedEnvironment {
+    pub source: String,
+    pub environment: DevEnvironment,
+}
+
+/// 读取一个来源的方式
+enum Fetcher {
+    Http(MddeClient),
+    Local(PathBuf),
+}
+
+impl Fetcher {
+    async fn download(&self, template: &str, filename: &str) -> Result<String, MddeError> {
+        match self {
+            Fetcher::Http(client) => client.download_script(template, filename).await,
+            Fetcher::Local(dir) => read_local(dir, &format!("{}/{}", template, filename)).await,
+        }
+    }
+
+    async fn environments(&self) -> Result<Vec<DevEnvironment>, MddeError> {
+        match self {
+            Fetcher::Http(client) => client.get_environments().await,
+            Fetcher::Local(dir) => local_environments(dir).await,
+        }
+    }
+
+    /// 模板的文件清单，来源中没有该模板时为 `None`；清单来自 `manifest.json` 时同时返回其原始内容
+    ///
+    /// 依次使用模板目录中的 `manifest.json`、`index.json` 中该模板的 `files`，都没有时使用默认清单，
+    /// 此时以 `docker-compose.yml` 是否存在判断来源中是否有该模板。
+    async fn manifest(
+        &self,
+        template: &str,
+    ) -> Result<Option<(TemplateManifest, Option<String>)>, MddeError> {
+        match self.download(template, MANIFEST_FILE).await {
+            Ok(content) => {
+                let manifest = TemplateManifest::parse(&content)?;
+                return Ok(Some((manifest, Some(content))));
+            }
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e),
+        }
+
+        // 获取不到 index.json 不影响使用模板
+        let listed = self.environments().await.ok().and_then(|environments| {
+            environments.into_iter().find(|env| {
+                env.name == template && !(env.files.is_empty() && env.variables.is_empty())
+            })
+        });
+        if let Some(env) = listed {
+            let mut manifest = TemplateManifest {
+                files: env.files,
+                variables: env.variables,
+            };
+            if manifest.files.is_empty() {
+                manifest.files = TemplateManifest::default().files;
+            }
+            manifest.validate()?;
+            return Ok(Some((manifest, None)));
+        }
+
+        match self.download(template, COMPOSE_FILE).await {
+            Ok(_) => Ok(Some((TemplateManifest::default(), None))),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 找到的模板
+pub struct ResolvedTemplate<'a> {
+    pub source: &'a TemplateSource,
+    pub manifest: TemplateManifest,
+    /// 清单的签名已经过信任的公钥验证
+    pub signed: bool,
+}
+
+/// 按顺序排列的所有模板来源
+pub struct TemplateSources {
+    sources: Vec<(TemplateSource, Fetcher)>,
+    trusted_keys: Vec<TrustedKey>,
+}
+
+impl TemplateSources {
+    /// HTTP 来源使用本地缓存，`offline` 时只从缓存读取
+    pub fn new(sources: Vec<TemplateSource>, cache: Option<TemplateCache>, offline: bool) -> Self {
+        let sources = sources
+            .into_iter()
+            .map(|source| {
+                let fetcher = match &source.location {
+                    SourceLocation::Http(url) => {
+                        Fetcher::Http(MddeClient::new(url).with_cache(cache.clone(), offline))
+                    }
+                    SourceLocation::Local(dir) => Fetcher::Local(dir.clone()),
+                };
+                (source, fetcher)
+            })
+            .collect();
+        Self {
+            sources,
+            trusted_keys: Vec::new(),
+        }
+    }
+
+    /// 只使用经其中一个公钥签名的模板，为空时不检查签名
+    pub fn with_trusted_keys(mut self, trusted_keys: Vec<TrustedKey>) -> Self {
+        self.trusted_keys = trusted_keys;
+        self
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &TemplateSource> {
+        self.sources.iter().map(|(source, _)| source)
+    }
+
+    /// 是否配置了多个来源
+    pub fn is_multiple(&self) -> bool {
+        self.sources.len() > 1
+    }
+
+    /// 读取一个来源中模板的文件，文件不存在时 [`MddeError::is_not_found`] 为 true
+    pub async fn download(
+        &self,
+        source: &TemplateSource,
+        template: &str,
+        filename: &str,
+    ) -> Result<String, MddeError> {
+        let (_, fetcher) = self
+            .sources
+            .iter()
+            .find(|(s, _)| s.name == source.name)
+            .ok_or_else(|| {
+                MddeError::InvalidArgument(i18n::tf("unknown_template_source", &[&source.name]))
+            })?;
+        fetcher.download(template, filename).await
+    }
+
+    /// 找到模板所在的来源及其文件清单
+    ///
+    /// 指定来源时只在该来源中查找；否则按顺序查找，跳过没有该模板的来源，其他错误直接返回。
+    /// 配置了信任的公钥时，模板必须有 `manifest.json`，其签名 `manifest.json.sig` 必须由其中一个公钥验证通过，
+    /// 签名的内容见 [`template_integrity::signed_payload`]，
+    /// 并且清单中的每个文件都要有摘要。
+    pub async fn resolve(
+        &self,
+        reference: &TemplateRef,
+    ) -> Result<ResolvedTemplate<'_>, MddeError> {
+        let candidates: Vec<&(TemplateSource, Fetcher)> = match &reference.source {
+            Some(name) => {
+                let source = self
+                    .sources
+                    .iter()
+                    .find(|(source, _)| &source.name == name)
+                    .ok_or_else(|| {
+                        MddeError::InvalidArgument(i18n::tf("unknown_template_source", &[name]))
+                    })?;
+                vec![source]
+            }
+            None => self.sources.iter().collect(),
+        };
+
+        for (source, fetcher) in candidates {
+            let Some((manifest, content)) = fetcher.manifest(&reference.name).await? else {
+                continue;
+            };
+            if self.trusted_keys.is_empty() {
+                return Ok(ResolvedTemplate {
+                    source,
+                    manifest,
+                    signed: false,
+                });
+            }
+
+            let unsigned =
+                || MddeError::IntegrityCheck(i18n::tf("template_not_signed", &[&reference.name]));
+            let content = content.ok_or_else(unsigned)?;
+            let signature_file = format!("{}{}", MANIFEST_FILE, SIGNATURE_SUFFIX);
+            let signature = match fetcher.download(&reference.name, &signature_file).await {
+                Ok(signature) => signature,
+                Err(e) if e.is_not_found() => return Err(unsigned()),
+                Err(e) => return Err(e),
+            };
+            let payload = template_integrity::signed_payload(&reference.name, content.as_bytes());
+            template_integrity::verify_signature(&self.trusted_keys, &payload, &signature)?;
+            if let Some(file) = manifest.files.iter().find(|file| file.sha256.is_none()) {
+                return Err(MddeError::IntegrityCheck(i18n::tf(
+                    "file_digest_missing",
+                    &[&file.path],
+                )));
+            }
+            return Ok(ResolvedTemplate {
+                source,
+                manifest,
+                signed: true,
+            });
+        }
+        Err(MddeError::TemplateNotFound(reference.name.clone()))
+    }
+
+    /// 合并所有来源的环境列表，保持来源的顺序
+    ///
+    /// 获取失败的来源给出警告后跳过，所有来源都失败时返回第一个错误。
+    pub async fn environments(&self) -> Result<Vec<SourcedEnvironment>, MddeError> {
+        let mut environments = Vec::new();
+        let mut first_error = None;
+        let mut succeeded = false;
+
+        for (source, fetcher) in &self.sources {
+            match fetcher.environments().await {
+                Ok(listed) => {
+                    succeeded = true;
+                    environments.extend(listed.into_iter().map(|environment| SourcedEnvironment {
+                        source: source.name.clone(),
+                        environment,
+                    }));
+                }
+                Err(e) => {
+                    if self.is_multiple() {
+                        println!(
+                            "{}",
+                            i18n::tf("source_list_failed", &[&source.name, &e]).yellow()
+                        );
+                    }
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) if !succeeded => Err(e),
+            _ => Ok(environments),
+        }
+    }
+}
+
+/// 读取本地来源中的文件，路径中不能包含 `..` 或绝对路径
+async fn read_local(dir: &Path, path: &str) -> Result<String, MddeError> {
+    let relative = Path::new(path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(MddeError::InvalidArgument(i18n::tf(
+            "invalid_template_path",
+            &[&path],
+        )));
+    }
+
+    let file = dir.join(relative);
+    match tokio::fs::read_to_string(&file).await {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(MddeError::TemplateNotFound(file.display().to_string()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 本地来源的环境列表：有 `index.json` 时读取它，否则列出包含 `docker-compose.yml` 的子目录
+async fn local_environments(dir: &Path) -> Result<Vec<DevEnvironment>, MddeError> {
+    match read_local(dir, "index.json").await {
+        Ok(content) => return Ok(serde_json::from_str(&content)?),
+        Err(e) if e.is_not_found() => {}
+        Err(e) => return Err(e),
+    }
+
+    let mut environments: Vec<DevEnvironment> = std::fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join("docker-compose.yml").is_file())
+        .map(|entry| DevEnvironment::new(&entry.file_name().to_string_lossy(), ""))
+        .collect();
+    environments.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(environments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        let sources =
+            TemplateSource::parse_list("team=file:///srv/templates, intranet=https://tpl.local/,")
+                .unwrap();
+        assert_eq!(
+            sources,
+            vec![
+                TemplateSource {
+                    name: "team".to_string(),
+                    location: SourceLocation::Local(PathBuf::from("/srv/templates")),
+                },
+                TemplateSource {
+                    name: "intranet".to_string(),
+                    location: SourceLocation::Http("https://tpl.local".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            TemplateSource::format_list(&sources),
+            "team=/srv/templates,intranet=https://tpl.local"
+        );
+        assert_eq!(
+            "local=./templates"
+                .parse::<TemplateSource>()
+                .unwrap()
+                .location,
+            SourceLocation::Local(PathBuf::from("./templates"))
+        );
+
+        assert!("/srv/templates".parse::<TemplateSource>().is_err());
+        assert!("default=/srv/templates".parse::<TemplateSource>().is_err());
+        assert!("a b=/srv/templates".parse::<TemplateSource>().is_err());
+        assert!("team=ftp://tpl.local".parse::<TemplateSource>().is_err());
+        assert!("team=".parse::<TemplateSource>().is_err());
+    }
+
+    #[test]
+    fn test_parse_template_ref() {
+        assert_eq!(
+            TemplateRef::parse("team:node22"),
+            TemplateRef {
+                source: Some("team".to_string()),
+                name: "node22".to_string(),
+            }
+        );
+        assert_eq!(TemplateRef::parse("java/openjdk17").source, None);
+    }
+
+    #[tokio::test]
+    async fn test_local_source() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("node22")).unwrap();
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        std::fs::write(
+            dir.path().join("node22/docker-compose.yml"),
+            "services: {}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_local(dir.path(), "node22/docker-compose.yml")
+                .await
+                .unwrap(),
+            "services: {}\n"
+        );
+        assert!(read_local(dir.path(), "node22/Dockerfile")
+            .await
+            .unwrap_err()
+            .is_not_found());
+        assert!(matches!(
+            read_local(dir.path(), "../node22/docker-compose.yml").await,
+            Err(MddeError::InvalidArgument(_))
+        ));
+
+        let environments = local_environments(dir.path()).await.unwrap();
+        let names: Vec<_> = environments.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["node22"]);
+
+        std::fs::write(
+            dir.path().join("index.json"),
+            r#"[{"name": "node22", "description": "Node.js 22"}]"#,
+        )
+        .unwrap();
+        let environments = local_environments(dir.path()).await.unwrap();
+        assert_eq!(environments[0].description, "Node.js 22");
+    }
+}
@@ -0,0 +1,511 @@
+use crate::error::MddeError;
+use crate::i18n;
+use regex_automata::meta::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// 模板变量的类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    #[default]
+    String,
+    Integer,
+    Boolean,
+}
+
+/// 模板声明的变量，创建环境时交互式输入或通过 `--set name=value` 指定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    /// 变量名，模板文件中以 `{{ name }}` 引用
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub kind: VariableType,
+    /// 默认值，没有默认值的变量必须指定
+    #[serde(
+        default,
+        deserialize_with = "scalar",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// 可选值，为空时不限制
+    #[serde(
+        default,
+        deserialize_with = "scalars",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub choices: Vec<String>,
+    /// 值必须完整匹配的正则表达式
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+impl TemplateVariable {
+    /// 检查变量的声明：变量名、正则表达式、默认值和可选值
+    pub fn validate(&self) -> Result<(), MddeError> {
+        let mut chars = self.name.chars();
+        let valid_name = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(MddeError::Config(i18n::tf(
+                "invalid_variable_name",
+                &[&self.name],
+            )));
+        }
+        self.regex()?;
+        for choice in &self.choices {
+            self.parse_value(choice)
+                .map_err(|e| MddeError::Config(e.to_string()))?;
+        }
+        if let Some(default) = &self.default {
+            self.check(default)
+                .map_err(|e| MddeError::Config(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// 检查输入的值，返回规范化后的值：整数去掉多余的符号和空白，布尔值为 `true`/`false`
+    ///
+    /// 值原样替换到模板文件中，没有 `pattern` 的字符串不能包含换行和控制字符，以免改变 YAML 的结构。
+    pub fn check(&self, value: &str) -> Result<String, MddeError> {
+        let value = self.parse_value(value)?;
+
+        if self.pattern.is_none() && value.chars().any(char::is_control) {
+            return Err(MddeError::InvalidArgument(i18n::tf(
+                "variable_control_character",
+                &[&self.name],
+            )));
+        }
+
+        if !self.choices.is_empty() {
+            let allowed = self
+                .choices
+                .iter()
+                .any(|choice| self.parse_value(choice).ok().as_ref() == Some(&value));
+            if !allowed {
+                return Err(MddeError::InvalidArgument(i18n::tf(
+                    "variable_not_in_choices",
+                    &[&self.name, &value, &self.choices.join(", ")],
+                )));
+            }
+        }
+
+        if let Some(regex) = self.regex()? {
+            if !regex.is_match(value.as_str()) {
+                return Err(MddeError::InvalidArgument(i18n::tf(
+                    "variable_pattern_mismatch",
+                    &[
+                        &self.name,
+                        &value,
+                        &self.pattern.as_deref().unwrap_or_default(),
+                    ],
+                )));
+            }
+        }
+        Ok(value)
+    }
+
+    /// 按类型解析值
+    fn parse_value(&self, value: &str) -> Result<String, MddeError> {
+        let trimmed = value.trim();
+        match self.kind {
+            VariableType::String => Ok(value.to_string()),
+            VariableType::Integer => trimmed.parse::<i64>().map(|n| n.to_string()).map_err(|_| {
+                MddeError::InvalidArgument(i18n::tf("variable_not_integer", &[&self.name, &value]))
+            }),
+            VariableType::Boolean => match trimmed.to_lowercase().as_str() {
+                "true" | "yes" | "y" | "on" | "1" => Ok("true".to_string()),
+                "false" | "no" | "n" | "off" | "0" => Ok("false".to_string()),
+                _ => Err(MddeError::InvalidArgument(i18n::tf(
+                    "variable_not_boolean",
+                    &[&self.name, &value],
+                ))),
+            },
+        }
+    }
+
+    /// 完整匹配 `pattern` 的正则表达式
+    fn regex(&self) -> Result<Option<Regex>, MddeError> {
+        let Some(pattern) = &self.pattern else {
+            return Ok(None);
+        };
+        Regex::new(&format!("^(?:{})$", pattern))
+            .map(Some)
+            .map_err(|e| MddeError::Config(i18n::tf("invalid_variable_pattern", &[&self.name, &e])))
+    }
+}
+
+/// 检查一组变量的声明，变量名不能重复
+pub fn validate_all(variables: &[TemplateVariable]) -> Result<(), MddeError> {
+    let mut names = HashSet::new();
+    for variable in variables {
+        variable.validate()?;
+        if !names.insert(variable.name.as_str()) {
+            return Err(MddeError::Config(i18n::tf(
+                "duplicate_variable",
+                &[&variable.name],
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 确定所有变量的值：`provided` 中的值优先，其次为 `prompt` 的输入，最后为默认值
+///
+/// `prompt` 返回 `None` 时使用默认值；`provided` 中有未声明的变量或必需的变量没有值时返回错误。
+pub fn resolve_values(
+    variables: &[TemplateVariable],
+    mut provided: BTreeMap<String, String>,
+    mut prompt: impl FnMut(&TemplateVariable) -> Result<Option<String>, MddeError>,
+) -> Result<BTreeMap<String, String>, MddeError> {
+    if let Some(name) = provided
+        .keys()
+        .find(|name| !variables.iter().any(|variable| &variable.name == *name))
+    {
+        return Err(MddeError::InvalidArgument(i18n::tf(
+            "unknown_variable",
+            &[name],
+        )));
+    }
+
+    let mut values = BTreeMap::new();
+    for variable in variables {
+        let value = match provided.remove(&variable.name) {
+            Some(value) => Some(value),
+            None => prompt(variable)?.or_else(|| variable.default.clone()),
+        };
+        let value = value.ok_or_else(|| {
+            MddeError::InvalidArgument(i18n::tf("variable_required", &[&variable.name]))
+        })?;
+        values.insert(variable.name.clone(), variable.check(&value)?);
+    }
+    Ok(values)
+}
+
+/// 模板中的一段
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Text(&'a str),
+    Var(&'a str),
+    If(&'a str),
+    Else,
+    EndIf,
+}
+
+/// 将变量渲染到模板文件中
+///
+/// 支持 `{{ name }}` 和 `{{#if name}}...{{else}}...{{/if}}`，`false` 和空值为假。
+/// 只处理 `values` 中有的变量，其他 `{{ ... }}` 原样保留；单独占一行的 `if`/`else`/`/if` 连同整行删除。
+pub fn render(content: &str, values: &BTreeMap<String, String>) -> Result<String, MddeError> {
+    let tokens = tokenize(content, values);
+
+    let mut output = String::with_capacity(content.len());
+    // 每层 if 的条件，以及是否已进入 else
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    let active = |stack: &[(bool, bool)]| {
+        stack
+            .iter()
+            .all(|(condition, in_else)| condition != in_else)
+    };
+
+    for token in tokens {
+        match token {
+            Token::Text(text) if active(&stack) => output.push_str(text),
+            Token::Var(name) if active(&stack) => output.push_str(&values[name]),
+            Token::Text(_) | Token::Var(_) => {}
+            Token::If(name) => {
+                let value = &values[name];
+                stack.push((!value.is_empty() && value != "false", false));
+            }
+            Token::Else => match stack.last_mut() {
+                Some((_, in_else @ false)) => *in_else = true,
+                _ => {
+                    return Err(MddeError::Config(
+                        i18n::t("template_unexpected_else").to_string(),
+                    ))
+                }
+            },
+            Token::EndIf => {
+                stack.pop();
+            }
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(MddeError::Config(
+            i18n::t("template_unclosed_if").to_string(),
+        ));
+    }
+    Ok(output)
+}
+
+/// 切分模板，`{{else}}` 和 `{{/if}}` 只在 `{{#if}}` 内部识别，
+/// 未声明变量的 `{{#if}}` 与它的 `{{else}}`、`{{/if}}` 一起原样保留
+fn tokenize<'a>(content: &'a str, values: &BTreeMap<String, String>) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    // 每层打开的 if 的变量是否已声明
+    let mut open: Vec<bool> = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        let end = start + length + 2;
+        let inner = rest[start + 2..end - 2].trim();
+        let declared = open.last() == Some(&true);
+        let token = if let Some(name) = inner.strip_prefix("#if ") {
+            let name = name.trim();
+            let known = values.contains_key(name);
+            open.push(known);
+            known.then_some(Token::If(name))
+        } else if inner == "else" {
+            declared.then_some(Token::Else)
+        } else if inner == "/if" {
+            open.pop();
+            declared.then_some(Token::EndIf)
+        } else {
+            values.contains_key(inner).then_some(Token::Var(inner))
+        };
+
+        let Some(token) = token else {
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        };
+
+        let (mut before, mut after) = (&rest[..start], &rest[end..]);
+        if !matches!(token, Token::Var(_)) {
+            // 单独占一行的块标签不输出所在的行
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let line_end = after.find('\n').map_or(after.len(), |i| i + 1);
+            if before[line_start..].trim().is_empty() && after[..line_end].trim().is_empty() {
+                before = &before[..line_start];
+                after = &after[line_end..];
+            }
+        }
+        if !before.is_empty() {
+            tokens.push(Token::Text(before));
+        }
+        tokens.push(token);
+        rest = after;
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// 默认值可以写成字符串、数字或布尔值
+fn scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    scalar_to_string(value).map_err(serde::de::Error::custom)
+}
+
+/// 可选值可以写成字符串、数字或布尔值
+fn scalars<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Vec::<serde_json::Value>::deserialize(deserializer)?
+        .into_iter()
+        .filter_map(|value| scalar_to_string(value).transpose())
+        .collect::<Result<_, _>>()
+        .map_err(serde::de::Error::custom)
+}
+
+fn scalar_to_string(value: serde_json::Value) -> Result<Option<String>, String> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(s) => Ok(Some(s)),
+        serde_json::Value::Number(n) => Ok(Some(n.to_string())),
+        serde_json::Value::Bool(b) => Ok(Some(b.to_string())),
+        other => Err(format!(
+            "expected a string, number or boolean, found {}",
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Vec<TemplateVariable> {
+        serde_json::from_str(
+            r#"[
+                {"name": "node_version", "type": "integer", "default": 22, "choices": [18, 20, 22]},
+                {"name": "with_db", "type": "boolean", "default": false},
+                {"name": "db_name", "pattern": "[a-z][a-z0-9_]*", "description": "Database name"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_values() {
+        let variables = variables();
+        assert!(validate_all(&variables).is_ok());
+        let [version, with_db, db_name] = &variables[..] else {
+            unreachable!()
+        };
+        assert_eq!(version.default.as_deref(), Some("22"));
+        assert_eq!(version.check(" +20 ").unwrap(), "20");
+        assert!(version.check("21").is_err());
+        assert!(version.check("lts").is_err());
+        assert_eq!(with_db.check("Yes").unwrap(), "true");
+        assert!(with_db.check("maybe").is_err());
+        assert_eq!(db_name.check("app_db").unwrap(), "app_db");
+        assert!(db_name.check("App-DB").is_err());
+        assert!(db_name.check("app\n  privileged: true").is_err());
+
+        // 没有 pattern 的字符串不能包含换行和控制字符
+        let variable = |json: &str| serde_json::from_str::<TemplateVariable>(json).unwrap();
+        let image = variable(r#"{"name": "image"}"#);
+        assert_eq!(image.check("node:22").unwrap(), "node:22");
+        assert!(image.check("node:22\n    privileged: true").is_err());
+        assert!(image.check("node:22\r").is_err());
+        assert!(image.check("node\t22").is_err());
+        let motd = variable(r#"{"name": "motd", "pattern": "(?s).*"}"#);
+        assert_eq!(motd.check("line 1\nline 2").unwrap(), "line 1\nline 2");
+
+        let invalid = |json: &str| {
+            let variable: TemplateVariable = serde_json::from_str(json).unwrap();
+            variable.validate().is_err()
+        };
+        assert!(invalid(r#"{"name": "1st"}"#));
+        assert!(invalid(
+            r#"{"name": "port", "type": "integer", "default": "http"}"#
+        ));
+        assert!(invalid(r#"{"name": "tag", "pattern": "("}"#));
+        assert!(invalid(
+            r#"{"name": "v", "default": "c", "choices": ["a", "b"]}"#
+        ));
+        assert!(validate_all(&[variables[0].clone(), variables[0].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_values() {
+        let variables = variables();
+        let resolved = resolve_values(
+            &variables,
+            values(&[("db_name", "app"), ("with_db", "yes")]),
+            |_| Ok(None),
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            values(&[
+                ("db_name", "app"),
+                ("node_version", "22"),
+                ("with_db", "true")
+            ])
+        );
+
+        // 交互式输入的值优先于默认值，`--set` 的值不再询问
+        let mut asked = Vec::new();
+        let resolved = resolve_values(&variables, values(&[("db_name", "app")]), |variable| {
+            asked.push(variable.name.clone());
+            Ok((variable.name == "node_version").then(|| "18".to_string()))
+        })
+        .unwrap();
+        assert_eq!(resolved["node_version"], "18");
+        assert_eq!(asked, ["node_version", "with_db"]);
+
+        assert!(resolve_values(&variables, BTreeMap::new(), |_| Ok(None)).is_err());
+        assert!(resolve_values(
+            &variables,
+            values(&[("db_name", "app"), ("db_user", "root")]),
+            |_| Ok(None)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let template = "\
+services:
+  app:
+    image: node:{{ node_version }}
+    labels:
+      - \"traefik.http.routers.app.rule={{ .Name }}\"
+{{#if with_db}}
+  db:
+    image: postgres:16
+    environment:
+      POSTGRES_DB: {{db_name}}
+{{else}}
+  # no database
+{{/if}}
+";
+        let rendered = render(
+            template,
+            &values(&[
+                ("node_version", "20"),
+                ("with_db", "true"),
+                ("db_name", "app"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "\
+services:
+  app:
+    image: node:20
+    labels:
+      - \"traefik.http.routers.app.rule={{ .Name }}\"
+  db:
+    image: postgres:16
+    environment:
+      POSTGRES_DB: app
+"
+        );
+
+        let rendered = render(
+            template,
+            &values(&[
+                ("node_version", "20"),
+                ("with_db", "false"),
+                ("db_name", "app"),
+            ]),
+        )
+        .unwrap();
+        assert!(
+            rendered.ends_with("rule={{ .Name }}\"\n  # no database\n"),
+            "{}",
+            rendered
+        );
+
+        // 未声明的变量原样保留
+        assert_eq!(
+            render("{{#if x}}a{{/if}}", &BTreeMap::new()).unwrap(),
+            "{{#if x}}a{{/if}}"
+        );
+        let flag = values(&[("x", "true")]);
+        assert_eq!(
+            render("[{{#if x}}a{{else}}b{{/if}}]", &flag).unwrap(),
+            "[a]"
+        );
+        assert!(render("{{#if x}}a", &flag).is_err());
+
+        // 未声明的 if 连同它的 else 和 /if 原样保留，不会关闭外层已声明的 if
+        assert_eq!(
+            render("{{#if x}}[{{#if y}}a{{else}}b{{/if}}]{{/if}}", &flag).unwrap(),
+            "[{{#if y}}a{{else}}b{{/if}}]"
+        );
+        assert_eq!(
+            render("{{#if y}}a{{/if}}{{#if x}}b{{/if}}", &flag).unwrap(),
+            "{{#if y}}a{{/if}}b"
+        );
+        assert!(render("{{#if x}}a{{else}}b{{else}}c{{/if}}", &flag).is_err());
+    }
+}
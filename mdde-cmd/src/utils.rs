@@ -43,6 +43,17 @@ pub fn format_file_size(bytes: u64) -> String {
     }
 }
 
+/// 在终端中询问用户，只有输入 y 或 yes 时返回 true
+pub fn confirm(prompt: &str) -> std::io::Result<bool> {
+    use std::io::Write;
+
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 /// 获取当前时间戳
 pub fn get_timestamp() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
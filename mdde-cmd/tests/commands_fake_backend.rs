@@ -0,0 +1,1821 @@
+use mdde::backend::fake::FakeContainer;
+use mdde::backend::prune::{NetworkUsage, VolumeUsage};
+use mdde::backend::service::{COMPOSE_PROJECT_LABEL, COMPOSE_WORKING_DIR_LABEL};
+use mdde::backend::{
+    self, ContainerBackend, ExecOutput, FakeBackend, OutputStream, PruneScope, PruneTarget,
+};
+use mdde::cli::OutputFormat;
+use mdde::cli::{ConfigAction, PortAction};
+use mdde::commands::{
+    self, clean, create, destroy, env, env_list, exec, logs, ls, port, run, start, status, stop,
+    use_env, verify,
+};
+use mdde::compose::{self, ComposeProject};
+use mdde::compose_model;
+use mdde::container_engine::ContainerEngine;
+use mdde::project;
+use mdde::registry::{Registry, RegistryEntry};
+use mdde::template_cache::TemplateCache;
+use mdde::template_integrity::{self, TemplateLock};
+use mdde::template_source::TemplateSources;
+use mdde::{Config, MddeError};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::{Mutex, MutexGuard};
+
+/// 命令依赖当前目录，测试之间需要串行切换目录
+static CWD_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// 在临时目录中运行测试，结束后恢复原目录
+struct ProjectDir {
+    dir: TempDir,
+    /// 存放本机环境登记表和模板缓存，避免测试写入用户目录
+    data: TempDir,
+    original: PathBuf,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl ProjectDir {
+    async fn new() -> Self {
+        let guard = CWD_LOCK.lock().await;
+        let dir = tempfile::tempdir().unwrap();
+        let original = std::env::current_dir().unwrap();
+        std::env::set_current_dir(dir.path()).unwrap();
+        let data = tempfile::tempdir().unwrap();
+        std::env::set_var("MDDE_REGISTRY_FILE", data.path().join("registry.json"));
+        std::env::set_var("MDDE_CACHE_DIR", data.path().join("cache"));
+        Self {
+            dir,
+            data,
+            original,
+            _guard: guard,
+        }
+    }
+
+    /// 写入一个最小的 compose 项目
+    fn with_compose_project(self) -> Self {
+        let mdde_dir = self.path().join(".mdde");
+        std::fs::create_dir_all(&mdde_dir).unwrap();
+        std::fs::write(mdde_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+        std::fs::write(mdde_dir.join("cfg.env"), "container_name=demo\n").unwrap();
+        self
+    }
+
+    fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    fn registry_file(&self) -> PathBuf {
+        self.data.path().join("registry.json")
+    }
+
+    fn cache(&self) -> TemplateCache {
+        TemplateCache::new(self.data.path().join("cache"))
+    }
+}
+
+impl Drop for ProjectDir {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.original);
+        std::env::remove_var("MDDE_REGISTRY_FILE");
+        std::env::remove_var("MDDE_CACHE_DIR");
+        let _ = project::set_project_dir(None);
+        let _ = project::set_environment(None);
+    }
+}
+
+/// 启动一个只提供静态文件的 HTTP 服务器，返回基础地址
+///
+/// 以文件长度作为 ETag，请求带有相同的 `If-None-Match` 时返回 304。
+fn serve_templates(files: &'static [(&'static str, &'static str)]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut if_none_match = None;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() <= 2 {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("if-none-match") {
+                        if_none_match = Some(value.trim().to_string());
+                    }
+                }
+            }
+
+            let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+            let response = match files.iter().find(|(p, _)| *p == path) {
+                Some((_, body)) if if_none_match == Some(format!("\"{}\"", body.len())) => {
+                    "HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                }
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\nETag: \"{}\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            let _ = (&stream).write_all(response.as_bytes());
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+fn demo_config() -> Config {
+    Config {
+        container_name: Some("demo".to_string()),
+        ..Default::default()
+    }
+}
+
+/// 创建指定模板和容器名称的参数，其他参数使用默认值
+fn create_options(template: &str, name: &str) -> create::CreateOptions {
+    create::CreateOptions {
+        dev_env: Some(template.to_string()),
+        name: Some(name.to_string()),
+        ..Default::default()
+    }
+}
+
+/// 在临时目录中写入模板来源的文件，路径相对于来源目录
+fn template_source(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, content) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+#[tokio::test]
+async fn test_create_then_start_then_status() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[(
+        "/node22/docker-compose.yml",
+        "services:\n  app:\n    image: node:22\n",
+    )]);
+
+    let config = Config {
+        host,
+        ..Default::default()
+    };
+    let backend = Arc::new(
+        FakeBackend::new().with_service(FakeContainer::new("demo", "node:22").service("app")),
+    );
+    let options = create::CreateOptions {
+        workspace: Some(project.path().to_string_lossy().to_string()),
+        ..create_options("node22", "demo")
+    };
+    create::execute(options, config, backend.as_ref())
+        .await
+        .unwrap();
+    assert!(project.path().join(".mdde/docker-compose.yml").exists());
+    assert!(!project.path().join(".mdde/Dockerfile").exists());
+
+    let config = Config::load().await.unwrap();
+    assert_eq!(config.container_name.as_deref(), Some("demo"));
+
+    start::execute(true, None, false, config.clone(), backend.as_ref())
+        .await
+        .unwrap();
+    assert!(backend.container("demo").unwrap().running);
+
+    status::execute(OutputFormat::Json, false, 2, config, backend.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        backend.calls(),
+        vec![
+            "compose_project_exists mdde",
+            "compose_config",
+            "compose_up detach=true",
+            "compose_project_exists mdde",
+            "compose_container_ids",
+            "inspect demo"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_named_environments_side_by_side() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[
+        (
+            "/node22/docker-compose.yml",
+            "services:\n  app:\n    image: node:22\n    container_name: ${container_name}\n",
+        ),
+        (
+            "/java21/docker-compose.yml",
+            "services:\n  app:\n    image: java:21\n    container_name: ${container_name}\n",
+        ),
+    ]);
+    let backend = FakeBackend::new()
+        .with_service(FakeContainer::new("demo-dev", "node:22").service("app"))
+        .with_service(FakeContainer::new("demo-test", "java:21").service("app"));
+
+    for (environment, template) in [("dev", "node22"), ("test", "java21")] {
+        project::set_environment(Some(environment.to_string())).unwrap();
+        let config = Config {
+            host: host.clone(),
+            ..Default::default()
+        };
+        let options = create_options(template, &format!("demo-{}", environment));
+        create::execute(options, config, &backend).await.unwrap();
+    }
+    let envs_dir = project.path().join(".mdde/envs");
+    assert!(envs_dir.join("dev/docker-compose.yml").exists());
+    assert!(envs_dir.join("test/docker-compose.yml").exists());
+    assert!(!project.path().join(".mdde/docker-compose.yml").exists());
+
+    // 第一个创建的环境成为默认环境
+    project::set_environment(None).unwrap();
+    assert_eq!(project::environment().unwrap(), "dev");
+    let config = Config::load().await.unwrap();
+    assert_eq!(config.container_name.as_deref(), Some("demo-dev"));
+
+    let environments = env_list::collect(project.path(), &project::environment().unwrap());
+    let names: Vec<_> = environments.iter().map(|env| env.name.as_str()).collect();
+    assert_eq!(names, ["dev", "test"]);
+    assert!(environments[0].default && environments[0].active);
+    assert_eq!(environments[1].container_name.as_deref(), Some("demo-test"));
+    assert_eq!(
+        environments[1].path,
+        Path::new(".mdde/envs/test").display().to_string()
+    );
+
+    // --env 只影响本次命令
+    project::set_environment(Some("test".to_string())).unwrap();
+    let config = Config::load().await.unwrap();
+    start::execute(true, None, false, config, &backend)
+        .await
+        .unwrap();
+    assert!(backend.container("demo-test").unwrap().running);
+
+    project::set_environment(None).unwrap();
+    use_env::execute("test".to_string()).await.unwrap();
+    assert_eq!(project::environment().unwrap(), "test");
+    assert!(use_env::execute("staging".to_string()).await.is_err());
+
+    project::set_environment(Some("staging".to_string())).unwrap();
+    let result = start::execute(true, None, false, demo_config(), &backend).await;
+    assert!(matches!(result, Err(MddeError::InvalidArgument(_))));
+    assert!(project::set_environment(Some("../dev".to_string())).is_err());
+}
+
+#[tokio::test]
+async fn test_create_uses_template_cache_offline() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[(
+        "/node22/docker-compose.yml",
+        "services:\n  app:\n    image: node:22\n",
+    )]);
+    let backend = FakeBackend::new();
+    let create = |template: &str, offline: bool| {
+        let options = create::CreateOptions {
+            offline,
+            ..create_options(template, "demo")
+        };
+        let config = Config {
+            host: host.clone(),
+            ..Default::default()
+        };
+        create::execute(options, config, &backend)
+    };
+
+    // 第二次下载时服务器返回 304，使用缓存的内容
+    create("node22", false).await.unwrap();
+    create("node22", false).await.unwrap();
+    let entries = project.cache().entries().unwrap();
+    let paths: Vec<_> = entries.iter().map(|entry| entry.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "index.json",
+            "node22/Dockerfile",
+            "node22/docker-compose.yml",
+            "node22/manifest.json"
+        ]
+    );
+    assert!(entries[1].missing && entries[3].missing);
+    assert_eq!(entries[2].etag.as_deref(), Some("\"36\""));
+
+    std::fs::remove_dir_all(project.path().join(".mdde")).unwrap();
+    create("node22", true).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(project.path().join(".mdde/docker-compose.yml")).unwrap(),
+        "services:\n  app:\n    image: node:22\n"
+    );
+    assert!(!project.path().join(".mdde/Dockerfile").exists());
+
+    let error = create("python312", true).await.unwrap_err();
+    assert!(matches!(error, MddeError::Network(_)), "{}", error);
+
+    commands::cache::execute(mdde::cli::CacheAction::Clear)
+        .await
+        .unwrap();
+    assert!(project.cache().entries().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_create_from_multiple_template_sources() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[
+        (
+            "/index.json",
+            r#"[{"name": "node22", "description": "Node.js 22"}, {"name": "java21", "description": "Java 21"}]"#,
+        ),
+        (
+            "/node22/docker-compose.yml",
+            "services:\n  app:\n    image: node:22\n",
+        ),
+        (
+            "/java21/docker-compose.yml",
+            "services:\n  app:\n    image: java:21\n",
+        ),
+    ]);
+    let team = template_source(&[
+        (
+            "node22/docker-compose.yml",
+            "services:\n  app:\n    image: registry.local/node:22\n",
+        ),
+        ("node22/Dockerfile", "FROM node:22\n"),
+    ]);
+
+    let config = Config {
+        host,
+        sources: vec![format!("team=file://{}", team.path().display())
+            .parse()
+            .unwrap()],
+        ..Default::default()
+    };
+    let sources = TemplateSources::new(config.template_sources().unwrap(), None, false);
+    let listed: Vec<_> = sources
+        .environments()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|env| format!("{}:{}", env.source, env.environment.name))
+        .collect();
+    assert_eq!(listed, ["team:node22", "default:node22", "default:java21"]);
+
+    let backend = FakeBackend::new();
+    let create = |template: &str| {
+        create::execute(create_options(template, "demo"), config.clone(), &backend)
+    };
+    let compose = || std::fs::read_to_string(project.path().join(".mdde/docker-compose.yml"));
+
+    // 未指定来源时使用第一个包含该模板的来源
+    create("node22").await.unwrap();
+    assert!(compose().unwrap().contains("registry.local/node:22"));
+    assert!(project.path().join(".mdde/Dockerfile").exists());
+    let env = std::fs::read_to_string(project.path().join(".mdde/cfg.env")).unwrap();
+    assert!(env.contains("template=team:node22"), "{}", env);
+
+    std::fs::remove_dir_all(project.path().join(".mdde")).unwrap();
+    create("default:node22").await.unwrap();
+    assert!(compose().unwrap().contains("image: node:22"));
+    assert!(!project.path().join(".mdde/Dockerfile").exists());
+
+    std::fs::remove_dir_all(project.path().join(".mdde")).unwrap();
+    create("java21").await.unwrap();
+    assert!(compose().unwrap().contains("java:21"));
+
+    let error = create("team:java21").await.unwrap_err();
+    assert!(error.is_not_found(), "{}", error);
+    let error = create("intranet:node22").await.unwrap_err();
+    assert!(matches!(error, MddeError::InvalidArgument(_)), "{}", error);
+}
+
+#[tokio::test]
+async fn test_create_writes_manifest_files() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[
+        (
+            "/index.json",
+            r#"[{"name": "pg16", "description": "PostgreSQL 16", "files": [
+                {"path": "docker-compose.yml"},
+                {"path": "init/schema.sql", "target": "initdb/01-schema.sql"}
+            ]}]"#,
+        ),
+        (
+            "/pg16/docker-compose.yml",
+            "services:\n  db:\n    image: postgres:16\n",
+        ),
+        ("/pg16/init/schema.sql", "CREATE TABLE demo (id int);\n"),
+    ]);
+    let team = template_source(&[
+        (
+            "node22/manifest.json",
+            r#"{"files": [
+                {"path": "docker-compose.yml"},
+                {"path": "scripts/entrypoint.sh", "target": "entrypoint.sh", "executable": true},
+                {"path": "dockerignore", "target": ".dockerignore"},
+                {"path": "Dockerfile", "optional": true}
+            ]}"#,
+        ),
+        ("node22/docker-compose.yml", "services: {}\n"),
+        ("node22/scripts/entrypoint.sh", "#!/bin/sh\n"),
+    ]);
+    let template = team.path().join("node22");
+
+    let config = Config {
+        host,
+        sources: vec![format!("team={}", team.path().display()).parse().unwrap()],
+        ..Default::default()
+    };
+    let backend = FakeBackend::new();
+    let create = |template: &str| {
+        create::execute(create_options(template, "demo"), config.clone(), &backend)
+    };
+    let mdde_dir = project.path().join(".mdde");
+
+    // 缺少必需的文件时不写入任何文件
+    let error = create("node22").await.unwrap_err();
+    assert!(error.is_not_found(), "{}", error);
+    assert!(!mdde_dir.join("docker-compose.yml").exists());
+
+    std::fs::write(template.join("dockerignore"), "node_modules\n").unwrap();
+    create("node22").await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(mdde_dir.join(".dockerignore")).unwrap(),
+        "node_modules\n"
+    );
+    assert!(!mdde_dir.join("Dockerfile").exists());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(mdde_dir.join("entrypoint.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+
+    std::fs::remove_dir_all(&mdde_dir).unwrap();
+    create("pg16").await.unwrap();
+    assert!(mdde_dir.join("docker-compose.yml").exists());
+    assert_eq!(
+        std::fs::read_to_string(mdde_dir.join("initdb/01-schema.sql")).unwrap(),
+        "CREATE TABLE demo (id int);\n"
+    );
+
+    // 清单中的路径不能离开环境目录
+    std::fs::write(
+        template.join("manifest.json"),
+        r#"{"files": [{"path": "docker-compose.yml"}, {"path": "dockerignore", "target": "../.dockerignore"}]}"#,
+    )
+    .unwrap();
+    let error = create("team:node22").await.unwrap_err();
+    assert!(matches!(error, MddeError::Config(_)), "{}", error);
+    assert!(!project.path().join(".dockerignore").exists());
+}
+
+#[tokio::test]
+async fn test_create_verifies_template_integrity() {
+    use base64::Engine;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    let project = ProjectDir::new().await;
+    let compose = "services:\n  app:\n    image: node:22\n";
+    let team = template_source(&[("node22/docker-compose.yml", compose)]);
+    let template = team.path().join("node22");
+    let write_manifest = |digest: &str| {
+        let manifest = format!(
+            r#"{{"files": [{{"path": "docker-compose.yml", "sha256": "{}"}}]}}"#,
+            digest
+        );
+        std::fs::write(template.join("manifest.json"), &manifest).unwrap();
+        manifest
+    };
+
+    let mut config = Config {
+        sources: vec![format!("team={}", team.path().display()).parse().unwrap()],
+        ..Default::default()
+    };
+    let backend = FakeBackend::new();
+    let create =
+        |config: Config| create::execute(create_options("team:node22", "demo"), config, &backend);
+    let mdde_dir = project.path().join(".mdde");
+
+    // 被篡改的文件不会写入
+    write_manifest(&template_integrity::sha256_hex(b"services: {}\n"));
+    let error = create(config.clone()).await.unwrap_err();
+    assert!(matches!(error, MddeError::IntegrityCheck(_)), "{}", error);
+    assert!(!mdde_dir.join("docker-compose.yml").exists());
+
+    let manifest = write_manifest(&template_integrity::sha256_hex(compose.as_bytes()));
+    create(config.clone()).await.unwrap();
+    verify::execute(OutputFormat::Json).await.unwrap();
+    let lock = TemplateLock::load(&mdde_dir).unwrap().unwrap();
+    assert_eq!(lock.template, "team:node22");
+    assert!(!lock.signed);
+    assert_eq!(lock.files[0].target, "docker-compose.yml");
+
+    std::fs::write(mdde_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+    let error = verify::execute(OutputFormat::Json).await.unwrap_err();
+    assert!(matches!(error, MddeError::IntegrityCheck(_)), "{}", error);
+
+    // 设置信任的公钥后必须有有效的签名
+    let rng = ring::rand::SystemRandom::new();
+    let pair = |rng| {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(rng).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    };
+    let (trusted, other) = (pair(&rng), pair(&rng));
+    let base64 = base64::engine::general_purpose::STANDARD;
+    config.trusted_keys = vec![base64.encode(trusted.public_key()).parse().unwrap()];
+    let signature_path = template.join("manifest.json.sig");
+
+    let error = create(config.clone()).await.unwrap_err();
+    assert!(matches!(error, MddeError::IntegrityCheck(_)), "{}", error);
+
+    // 其他公钥的签名、只签了清单内容或签给其他模板的签名都不被接受
+    let payload = template_integrity::signed_payload("node22", manifest.as_bytes());
+    for signature in [
+        other.sign(&payload),
+        trusted.sign(manifest.as_bytes()),
+        trusted.sign(&template_integrity::signed_payload(
+            "java21",
+            manifest.as_bytes(),
+        )),
+    ] {
+        std::fs::write(&signature_path, base64.encode(signature)).unwrap();
+        let error = create(config.clone()).await.unwrap_err();
+        assert!(matches!(error, MddeError::IntegrityCheck(_)), "{}", error);
+    }
+
+    std::fs::write(&signature_path, base64.encode(trusted.sign(&payload))).unwrap();
+    create(config.clone()).await.unwrap();
+    verify::execute(OutputFormat::Json).await.unwrap();
+    assert!(TemplateLock::load(&mdde_dir).unwrap().unwrap().signed);
+}
+
+#[tokio::test]
+async fn test_create_renders_template_variables() {
+    let project = ProjectDir::new().await;
+    let team = template_source(&[
+        (
+            "node/manifest.json",
+            r#"{
+                "files": [{"path": "docker-compose.yml"}, {"path": "Dockerfile"}],
+                "variables": [
+                    {"name": "node_version", "type": "integer", "default": 22, "choices": [20, 22]},
+                    {"name": "with_db", "type": "boolean", "default": false},
+                    {"name": "db_name", "pattern": "[a-z][a-z0-9_]*"}
+                ]
+            }"#,
+        ),
+        (
+            "node/docker-compose.yml",
+            "services:\n  app:\n    build: .\n{{#if with_db}}\n  db:\n    environment:\n      POSTGRES_DB: {{ db_name }}\n{{/if}}\n",
+        ),
+        ("node/Dockerfile", "FROM node:{{ node_version }}\n"),
+    ]);
+
+    let config = Config {
+        sources: vec![format!("team={}", team.path().display()).parse().unwrap()],
+        ..Default::default()
+    };
+    let backend = FakeBackend::new();
+    let create = |variables: &[&str]| {
+        let options = create::CreateOptions {
+            variables: variables.iter().map(ToString::to_string).collect(),
+            ..create_options("team:node", "demo")
+        };
+        create::execute(options, config.clone(), &backend)
+    };
+    let mdde_dir = project.path().join(".mdde");
+
+    // 无效的值和未声明的变量不会写入任何文件
+    for variables in [
+        &["node_version=20", "with_db=yes", "db_name=App-DB"][..],
+        &["node_version=18", "with_db=no", "db_name=app"],
+        &[
+            "node_version=20",
+            "with_db=no",
+            "db_name=app",
+            "db_user=root",
+        ],
+        &["node_version"],
+    ] {
+        let error = create(variables).await.unwrap_err();
+        assert!(matches!(error, MddeError::InvalidArgument(_)), "{}", error);
+        assert!(!mdde_dir.join("docker-compose.yml").exists());
+    }
+
+    create(&["node_version=20", "with_db=yes", "db_name=app"])
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(mdde_dir.join("docker-compose.yml")).unwrap(),
+        "services:\n  app:\n    build: .\n  db:\n    environment:\n      POSTGRES_DB: app\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(mdde_dir.join("Dockerfile")).unwrap(),
+        "FROM node:20\n"
+    );
+    let lock = TemplateLock::load(&mdde_dir).unwrap().unwrap();
+    assert_eq!(lock.variables["with_db"], "true");
+    verify::execute(OutputFormat::Json).await.unwrap();
+
+    create(&["node_version=22", "with_db=false", "db_name=app"])
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(mdde_dir.join("docker-compose.yml")).unwrap(),
+        "services:\n  app:\n    build: .\n"
+    );
+}
+
+#[tokio::test]
+async fn test_registry_lists_and_stops_all_environments() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[(
+        "/node22/docker-compose.yml",
+        "services:\n  app:\n    image: node:22\n",
+    )]);
+    let backend =
+        FakeBackend::new().with_service(FakeContainer::new("demo", "node:22").service("app"));
+    let config = Config {
+        host,
+        ..Default::default()
+    };
+    let options = create::CreateOptions {
+        ports: vec!["8080:80".to_string()],
+        ..create_options("node22", "demo")
+    };
+    create::execute(options, config, &backend).await.unwrap();
+
+    let mut registry = Registry::load_from(&project.registry_file()).unwrap();
+    assert_eq!(registry.environments.len(), 1);
+    let entry = registry.environments[0].clone();
+    assert_eq!(entry.project_dir, project.path().canonicalize().unwrap());
+    assert_eq!(entry.environment, "default");
+    assert_eq!(entry.template, "node22");
+    assert_eq!(entry.ports, ["8080:80"]);
+
+    // 另一个已被删除的检出
+    registry.register(RegistryEntry {
+        project_dir: project.path().join("deleted-checkout"),
+        ..entry.clone()
+    });
+    registry.save_to(&project.registry_file()).unwrap();
+
+    let config = Config::load().await.unwrap();
+    start::execute(true, None, false, config, &backend)
+        .await
+        .unwrap();
+    let states: Vec<_> = registry
+        .environments
+        .iter()
+        .map(|entry| ls::live_state(entry, &backend))
+        .collect();
+    assert_eq!(states[0].state, "running");
+    assert_eq!((states[0].running, states[0].total), (1, 1));
+    assert_eq!(states[1].state, "missing");
+    ls::execute(true, false, OutputFormat::Json, &backend)
+        .await
+        .unwrap();
+
+    stop::execute_all(false, &backend).await.unwrap();
+    assert!(backend.container("demo").is_none());
+    assert_eq!(
+        backend
+            .calls()
+            .iter()
+            .filter(|call| call.starts_with("compose_down"))
+            .count(),
+        1
+    );
+
+    ls::execute(true, true, OutputFormat::Table, &backend)
+        .await
+        .unwrap();
+    let registry = Registry::load_from(&project.registry_file()).unwrap();
+    assert_eq!(registry.environments, [entry]);
+}
+
+#[tokio::test]
+async fn test_start_without_compose_file() {
+    let _project = ProjectDir::new().await;
+    let backend = FakeBackend::new();
+
+    let result = start::execute(true, None, false, demo_config(), &backend).await;
+
+    assert!(matches!(result, Err(MddeError::FileOperation(_))));
+    assert!(backend.calls().is_empty());
+}
+
+#[tokio::test]
+async fn test_start_reports_backend_failure() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new().fail_on("compose_up", "port is already allocated");
+
+    let result = start::execute(true, None, false, demo_config(), &backend).await;
+
+    match result {
+        Err(MddeError::Docker(msg)) => assert!(msg.contains("port is already allocated")),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+/// 返回一个当前空闲的宿主机端口
+fn free_port() -> u16 {
+    TcpListener::bind("0.0.0.0:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+#[tokio::test]
+async fn test_create_saves_port_mappings() {
+    let project = ProjectDir::new().await;
+    let host = serve_templates(&[(
+        "/node22/docker-compose.yml",
+        "services:\n  app:\n    image: node:22\n",
+    )]);
+    let (app_port, extra_port) = (free_port(), free_port());
+    let app_mapping = format!("{}:80", app_port);
+    let extra_mapping = format!("127.0.0.1:{}:{}/udp", extra_port, extra_port);
+
+    let config = Config {
+        host,
+        ..Default::default()
+    };
+    let options = create::CreateOptions {
+        ports: vec![app_mapping.clone(), extra_mapping.clone()],
+        workspace: Some(project.path().to_string_lossy().to_string()),
+        ..create_options("node22", "demo")
+    };
+    create::execute(options, config, &FakeBackend::new())
+        .await
+        .unwrap();
+
+    let env_vars = Config::load_env_file().await.unwrap();
+    assert_eq!(env_vars["app_port"], app_mapping);
+    assert_eq!(env_vars["ports"], extra_mapping);
+
+    let config = Config::load().await.unwrap();
+    assert_eq!(config.app_port, Some(app_mapping.parse().unwrap()));
+    assert_eq!(config.ports, vec![extra_mapping.parse().unwrap()]);
+}
+
+#[tokio::test]
+async fn test_port_add_rm_ls() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new();
+    let (first, second) = (free_port(), free_port());
+
+    port::execute(
+        PortAction::Add {
+            mappings: vec![format!("{}:80,{}:53/udp", first, second)],
+            auto_port: false,
+        },
+        demo_config(),
+        &backend,
+    )
+    .await
+    .unwrap();
+    let config = Config::load().await.unwrap();
+    assert_eq!(config.app_port.unwrap().host_port(), first);
+    assert_eq!(config.ports[0].to_string(), format!("{}:53/udp", second));
+
+    // 与已有映射重叠
+    let result = port::execute(
+        PortAction::Add {
+            mappings: vec![format!("{}:8080", first)],
+            auto_port: false,
+        },
+        demo_config(),
+        &backend,
+    )
+    .await;
+    assert!(matches!(result, Err(MddeError::InvalidArgument(_))));
+
+    // 删除应用端口后，下一个映射成为应用端口
+    port::execute(
+        PortAction::Rm {
+            mappings: vec![first.to_string()],
+        },
+        demo_config(),
+        &backend,
+    )
+    .await
+    .unwrap();
+    let env_vars = Config::load_env_file().await.unwrap();
+    assert_eq!(env_vars["app_port"], format!("{}:53/udp", second));
+    assert!(!env_vars.contains_key("ports"));
+
+    let result = port::execute(
+        PortAction::Rm {
+            mappings: vec![first.to_string()],
+        },
+        demo_config(),
+        &backend,
+    )
+    .await;
+    assert!(matches!(result, Err(MddeError::InvalidArgument(_))));
+
+    port::execute(
+        PortAction::Ls {
+            format: OutputFormat::Json,
+        },
+        demo_config(),
+        &backend,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_env_edits_preserve_comments_and_order() {
+    let project = ProjectDir::new().await;
+    let env_path = project.path().join(".mdde").join("cfg.env");
+    std::fs::create_dir_all(env_path.parent().unwrap()).unwrap();
+    let content = "# 团队共享的配置\nhost=http://intranet\n\n# 数据库\nDB_URL=postgres://db\ncontainer_name=demo\n";
+    std::fs::write(&env_path, content).unwrap();
+
+    env::execute(
+        Some("DB_URL=postgres://other".to_string()),
+        false,
+        None,
+        demo_config(),
+    )
+    .await
+    .unwrap();
+    env::execute(Some("TOKEN=abc".to_string()), false, None, demo_config())
+        .await
+        .unwrap();
+    env::execute(
+        None,
+        false,
+        Some("container_name".to_string()),
+        demo_config(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&env_path).unwrap(),
+        "# 团队共享的配置\nhost=http://intranet\n\n# 数据库\nDB_URL=postgres://other\nTOKEN=abc\n"
+    );
+
+    // 未修改任何值时文件保持不变
+    let before = std::fs::read_to_string(&env_path).unwrap();
+    let mut config = demo_config();
+    config
+        .update([("host".to_string(), "http://intranet".to_string())].into())
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&env_path).unwrap(), before);
+}
+
+#[tokio::test]
+async fn test_env_del_fixes_malformed_env_file() {
+    let project = ProjectDir::new().await;
+    let env_path = project.path().join(".mdde").join("cfg.env");
+    std::fs::create_dir_all(env_path.parent().unwrap()).unwrap();
+    std::fs::write(&env_path, "host=http://intranet\nTOKEN=\"unterminated\n").unwrap();
+
+    // 严格加载失败，宽松加载跳过有错误的行
+    assert!(Config::load().await.is_err());
+    let config = Config::load_lenient(&[]).await.unwrap();
+    assert_eq!(config.host, "http://intranet");
+
+    env::execute(None, true, None, config.clone())
+        .await
+        .unwrap();
+    env::execute(None, false, Some("TOKEN".to_string()), config)
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&env_path).unwrap(),
+        "host=http://intranet\n"
+    );
+    assert!(Config::load().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_layered_config_origins() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let user_config = project.path().join("config.toml");
+    std::fs::write(
+        &user_config,
+        "host = \"http://user\"\nengine = \"podman\"\ncontainer_name = \"user\"\n",
+    )
+    .unwrap();
+    std::env::set_var("MDDE_CONFIG_FILE", &user_config);
+    std::env::set_var("MDDE_ENGINE", "docker");
+
+    let overrides = vec![("host".to_string(), "http://cli".to_string())];
+    let result = Config::load_layers(&overrides).await;
+    std::env::remove_var("MDDE_CONFIG_FILE");
+    std::env::remove_var("MDDE_ENGINE");
+    let (config, values) = result.unwrap();
+
+    assert_eq!(config.host, "http://cli");
+    assert_eq!(config.container_name.as_deref(), Some("demo"));
+    assert_eq!(config.engine, Some(ContainerEngine::Docker));
+
+    let layer = |key: &str| {
+        values
+            .iter()
+            .find(|value| value.key == key)
+            .map(|value| value.source.layer())
+    };
+    assert_eq!(layer("host"), Some("cli"));
+    assert_eq!(layer("container_name"), Some("project"));
+    assert_eq!(layer("engine"), Some("env"));
+}
+
+/// 写入发布了指定端口的 compose 文件和 cfg.env
+fn write_ports_project(project: &ProjectDir, ports: &[&str], app_port: &str) {
+    let mdde_dir = project.path().join(".mdde");
+    let ports: String = ports
+        .iter()
+        .map(|port| format!("      - \"{}\"\n", port))
+        .collect();
+    std::fs::write(
+        mdde_dir.join("docker-compose.yml"),
+        format!(
+            "services:\n  app:\n    image: node:22\n    ports:\n{}",
+            ports
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        mdde_dir.join("cfg.env"),
+        format!("container_name=demo\napp_port={}\n", app_port),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_start_remaps_busy_app_port() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+    let busy = listener.local_addr().unwrap().port();
+    let mapping = format!("{}:80", busy);
+    write_ports_project(&project, &[&mapping], &mapping);
+
+    let backend = FakeBackend::new();
+    start::execute(true, None, true, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    let env_vars = Config::load_env_file().await.unwrap();
+    let remapped = env_vars["app_port"].clone();
+    assert_ne!(remapped, mapping);
+    assert!(remapped.ends_with(":80"));
+    assert!(backend
+        .calls()
+        .contains(&"compose_up detach=true".to_string()));
+}
+
+#[tokio::test]
+async fn test_start_reports_port_held_by_other_environment() {
+    let project = ProjectDir::new().await.with_compose_project();
+    write_ports_project(&project, &["45432:5432"], "8080:80");
+
+    let backend = FakeBackend::new().with_container(
+        FakeContainer::new("other-db-1", "postgres:16")
+            .label(
+                "com.docker.compose.project.config_files",
+                "/work/other/.mdde/docker-compose.yml",
+            )
+            .label(
+                "com.docker.compose.project.working_dir",
+                "/work/other/.mdde",
+            )
+            .port(45432, 5432),
+    );
+
+    let result = start::execute(true, None, true, demo_config(), &backend).await;
+
+    match result {
+        Err(MddeError::PortConflict(ports)) => {
+            assert_eq!(ports.len(), 1);
+            assert!(ports[0].starts_with("45432/tcp"));
+            assert!(ports[0].contains("other-db-1"));
+            assert!(ports[0].contains("/work/other"));
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(!backend
+        .calls()
+        .iter()
+        .any(|call| call.starts_with("compose_up")));
+}
+
+#[tokio::test]
+async fn test_start_wait_for_healthy_services() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new()
+        .with_service(
+            FakeContainer::new("demo-web-1", "nginx:1.27")
+                .service("web")
+                .health("healthy"),
+        )
+        .with_service(FakeContainer::new("demo-worker-1", "node:22").service("worker"));
+
+    start::execute(
+        false,
+        Some(Duration::from_secs(5)),
+        false,
+        demo_config(),
+        &backend,
+    )
+    .await
+    .unwrap();
+
+    // --wait 总是以后台方式启动
+    assert_eq!(
+        backend.calls()[..3],
+        [
+            "compose_project_exists mdde",
+            "compose_config",
+            "compose_up detach=true"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_start_wait_for_app_port() {
+    let project = ProjectDir::new().await.with_compose_project();
+    // 应用端口只检查主服务
+    std::fs::write(
+        project.path().join(".mdde/docker-compose.yml"),
+        "services:\n  app:\n    image: node:22\n  worker:\n    image: node:22\n",
+    )
+    .unwrap();
+    let backend = FakeBackend::new()
+        .with_service(FakeContainer::new("demo-app-1", "node:22").service("app"))
+        .with_service(FakeContainer::new("demo-worker-1", "node:22").service("worker"));
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = Config {
+        app_port: Some(listener.local_addr().unwrap().port().into()),
+        ..demo_config()
+    };
+    start::execute(false, Some(Duration::from_secs(5)), false, config, &backend)
+        .await
+        .unwrap();
+
+    // 端口关闭后等待超时
+    let closed_port = listener.local_addr().unwrap().port();
+    drop(listener);
+    let config = Config {
+        app_port: Some(closed_port.into()),
+        ..demo_config()
+    };
+    let result = start::execute(false, Some(Duration::from_secs(1)), false, config, &backend).await;
+    match result {
+        Err(MddeError::ServicesNotReady(services)) => {
+            assert_eq!(services, vec![format!("app (port {})", closed_port)])
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_start_wait_reports_unready_services() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new()
+        .with_service(
+            FakeContainer::new("demo-web-1", "nginx:1.27")
+                .service("web")
+                .health("healthy"),
+        )
+        .with_service(
+            FakeContainer::new("demo-db-1", "postgres:16")
+                .service("db")
+                .health("starting"),
+        );
+
+    let result = start::execute(
+        false,
+        Some(Duration::from_secs(1)),
+        false,
+        demo_config(),
+        &backend,
+    )
+    .await;
+
+    match result {
+        Err(MddeError::ServicesNotReady(services)) => {
+            assert_eq!(services, vec!["db (starting)"])
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_start_checks_compose_variables() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let mdde_dir = project.path().join(".mdde");
+    std::fs::write(
+        mdde_dir.join("docker-compose.yml"),
+        "services:\n  app:\n    image: ${IMAGE}\n    container_name: ${container_name}\n",
+    )
+    .unwrap();
+    let backend =
+        FakeBackend::new().with_service(FakeContainer::new("demo", "node:22").service("app"));
+
+    let check = ConfigAction::Check {
+        format: OutputFormat::Json,
+    };
+    let result = commands::config::execute(check, &[], demo_config()).await;
+    assert!(matches!(result, Err(MddeError::Config(_))));
+
+    let result = start::execute(true, None, false, demo_config(), &backend).await;
+    assert!(matches!(result, Err(MddeError::Config(_))));
+    assert!(backend.calls().is_empty());
+
+    std::fs::write(
+        mdde_dir.join("cfg.env"),
+        "container_name=demo\nIMAGE=node:22\n",
+    )
+    .unwrap();
+    let check = ConfigAction::Check {
+        format: OutputFormat::Table,
+    };
+    commands::config::execute(check, &[], demo_config())
+        .await
+        .unwrap();
+    start::execute(true, None, false, demo_config(), &backend)
+        .await
+        .unwrap();
+    assert!(backend.container("demo").unwrap().running);
+}
+
+#[tokio::test]
+async fn test_config_render_masks_secrets() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let mdde_dir = project.path().join(".mdde");
+    std::fs::write(
+        mdde_dir.join("docker-compose.yml"),
+        "services:\n  app:\n    image: node:22\n    environment:\n      - API_TOKEN=${API_TOKEN}\n",
+    )
+    .unwrap();
+    std::fs::write(mdde_dir.join("cfg.env"), "API_TOKEN=abc123\n").unwrap();
+
+    let project = ComposeProject::current().unwrap();
+    let mut model = compose_model::resolve(&project, &demo_config()).unwrap();
+    assert_eq!(
+        model["services"]["app"]["environment"]["API_TOKEN"],
+        "abc123"
+    );
+    compose_model::mask_secrets(&mut model);
+    let rendered = commands::config::render_model(&model, OutputFormat::Json).unwrap();
+    assert!(rendered.contains(compose_model::MASK));
+    assert!(!rendered.contains("abc123"));
+
+    let render = ConfigAction::Render {
+        format: OutputFormat::Yaml,
+        show_secrets: false,
+    };
+    commands::config::execute(render, &[], demo_config())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_stop_removes_service_containers() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new().with_container(
+        FakeContainer::new("demo", "node:22")
+            .service("app")
+            .running(),
+    );
+
+    stop::execute(true, demo_config(), &backend).await.unwrap();
+
+    assert!(backend.container("demo").is_none());
+    assert_eq!(
+        backend.calls(),
+        vec!["compose_project_exists mdde", "compose_down volumes=true"]
+    );
+}
+
+#[tokio::test]
+async fn test_same_env_in_two_checkouts_uses_separate_compose_projects() {
+    let first = ProjectDir::new().await.with_compose_project();
+    let second = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(second.path().join(".mdde")).unwrap();
+    for file in ["docker-compose.yml", "cfg.env"] {
+        std::fs::copy(
+            first.path().join(".mdde").join(file),
+            second.path().join(".mdde").join(file),
+        )
+        .unwrap();
+    }
+    let first_project = ComposeProject::new(first.path());
+    let second_project = ComposeProject::new(second.path());
+    assert_ne!(first_project.name(), second_project.name());
+
+    let backend = FakeBackend::new()
+        .with_container(
+            FakeContainer::new("first-app-1", "node:22")
+                .service("app")
+                .label(COMPOSE_PROJECT_LABEL, &first_project.name())
+                .running(),
+        )
+        .with_container(
+            FakeContainer::new("second-app-1", "node:22")
+                .service("app")
+                .label(COMPOSE_PROJECT_LABEL, &second_project.name())
+                .running(),
+        );
+    assert_eq!(
+        backend.compose_container_ids(&second_project).unwrap(),
+        vec!["second-app-1"]
+    );
+
+    project::set_project_dir(Some(first.path().to_path_buf())).unwrap();
+    stop::execute(true, demo_config(), &backend).await.unwrap();
+
+    assert!(backend.container("first-app-1").is_none());
+    assert!(backend.container("second-app-1").is_some());
+}
+
+#[tokio::test]
+async fn test_default_env_keeps_legacy_compose_project() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let active = ComposeProject::active().unwrap();
+    let env_dir = active.compose_file.parent().unwrap().to_string_lossy();
+
+    // 升级前启动的容器使用 compose 默认的项目名称，并记录了项目目录
+    let backend = FakeBackend::new()
+        .with_container(
+            FakeContainer::new("legacy-app-1", "node:22")
+                .service("app")
+                .label(COMPOSE_PROJECT_LABEL, compose::LEGACY_PROJECT_NAME)
+                .label(COMPOSE_WORKING_DIR_LABEL, &env_dir)
+                .running(),
+        )
+        .with_container(
+            FakeContainer::new("other-app-1", "node:22")
+                .service("app")
+                .label(COMPOSE_PROJECT_LABEL, compose::LEGACY_PROJECT_NAME)
+                .label(COMPOSE_WORKING_DIR_LABEL, "/work/other/.mdde")
+                .running(),
+        );
+    let project = active.clone().with_legacy_fallback(&backend);
+    assert_eq!(project.name(), compose::LEGACY_PROJECT_NAME);
+    assert_eq!(
+        active.with_legacy_fallback(&FakeBackend::new()).name(),
+        ComposeProject::active().unwrap().name()
+    );
+
+    stop::execute(false, demo_config(), &backend).await.unwrap();
+    assert!(backend.container("legacy-app-1").is_none());
+}
+
+#[tokio::test]
+async fn test_commands_find_project_root_from_subdirectory() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let nested = project.path().join("src").join("api");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::env::set_current_dir(&nested).unwrap();
+
+    let env_vars = Config::load_env_file().await.unwrap();
+    assert_eq!(env_vars.get("container_name").unwrap(), "demo");
+
+    let backend = FakeBackend::new().with_container(
+        FakeContainer::new("demo", "node:22")
+            .service("app")
+            .running(),
+    );
+    stop::execute(false, demo_config(), &backend).await.unwrap();
+    assert_eq!(
+        backend.calls(),
+        vec!["compose_project_exists mdde", "compose_down volumes=false"]
+    );
+
+    // --project-dir 优先于向上查找
+    let other = tempfile::tempdir().unwrap();
+    project::set_project_dir(Some(other.path().to_path_buf())).unwrap();
+    let result = stop::execute(false, demo_config(), &backend).await;
+    project::set_project_dir(None).unwrap();
+    assert!(matches!(result, Err(MddeError::FileOperation(_))));
+
+    assert!(project::set_project_dir(Some(nested.join("missing"))).is_err());
+}
+
+#[tokio::test]
+async fn test_run_requires_running_container() {
+    let backend = FakeBackend::new().with_container(FakeContainer::new("demo", "node:22"));
+
+    let result = run::execute(vec!["ls".to_string()], demo_config(), &backend).await;
+
+    assert!(matches!(result, Err(MddeError::ContainerNotRunning(name)) if name == "demo"));
+    assert_eq!(backend.calls(), vec!["container_running demo"]);
+}
+
+#[tokio::test]
+async fn test_run_streams_command() {
+    let backend = FakeBackend::new()
+        .with_container(FakeContainer::new("demo", "node:22").running())
+        .with_exec_output(ExecOutput {
+            exit_code: 0,
+            stdout: "hello\n".to_string(),
+            stderr: String::new(),
+        });
+
+    run::execute(
+        vec!["echo".to_string(), "hello".to_string()],
+        demo_config(),
+        &backend,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        backend.calls(),
+        vec!["container_running demo", "exec_stream demo echo hello"]
+    );
+}
+
+#[tokio::test]
+async fn test_run_propagates_exit_code() {
+    let backend = FakeBackend::new()
+        .with_container(FakeContainer::new("demo", "node:22").running())
+        .with_exec_output(ExecOutput {
+            exit_code: 2,
+            ..Default::default()
+        });
+
+    let result = run::execute(vec!["false".to_string()], demo_config(), &backend).await;
+
+    assert!(matches!(result, Err(MddeError::Docker(msg)) if msg.contains('2')));
+}
+
+#[tokio::test]
+async fn test_exec_enters_running_container() {
+    let backend =
+        FakeBackend::new().with_container(FakeContainer::new("demo", "node:22").running());
+
+    exec::execute("/bin/sh".to_string(), demo_config(), &backend)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        backend.calls(),
+        vec!["container_running demo", "exec_interactive demo /bin/sh"]
+    );
+}
+
+#[tokio::test]
+async fn test_logs_tail_and_missing_container() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new().with_container(
+        FakeContainer::new("demo", "node:22")
+            .log(OutputStream::Stdout, "line 1")
+            .log(OutputStream::Stderr, "line 2"),
+    );
+
+    logs::execute(Some(10), None, false, false, demo_config(), &backend)
+        .await
+        .unwrap();
+    assert_eq!(backend.calls(), vec!["logs demo tail=Some(10)"]);
+
+    let backend = FakeBackend::new();
+    let result = logs::execute(None, None, true, false, demo_config(), &backend).await;
+    assert!(matches!(result, Err(MddeError::ContainerNotRunning(_))));
+}
+
+#[tokio::test]
+async fn test_clean_images_only_removes_unused() {
+    let backend = FakeBackend::new()
+        .with_container(FakeContainer::new("demo", "node:22").running())
+        .with_image("node:22")
+        .with_image("python:3.12");
+
+    let options = clean::CleanOptions {
+        images: true,
+        global: true,
+        yes: true,
+        ..Default::default()
+    };
+    clean::execute(options, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    assert_eq!(backend.images(), vec!["node:22"]);
+    assert_eq!(backend.calls(), vec!["prune Images Global"]);
+}
+
+#[tokio::test]
+async fn test_clean_defaults_to_mdde_resources() {
+    let backend = FakeBackend::new()
+        .with_container(
+            FakeContainer::new("demo-app-1", "demo-app").label(compose::MANAGED_LABEL, "true"),
+        )
+        .with_container(FakeContainer::new("colleague", "colleague-build"))
+        .with_labelled_image("demo-app", compose::MANAGED_LABEL, "true")
+        .with_labelled_image("demo-old", compose::MANAGED_LABEL, "true")
+        .with_image("colleague-build")
+        .with_image("python:3.12");
+
+    let options = clean::CleanOptions {
+        all: true,
+        yes: true,
+        ..Default::default()
+    };
+    clean::execute(options, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    // 只删除带有 mdde 标签的已停止容器和未使用的镜像
+    assert!(backend.container("demo-app-1").is_none());
+    assert!(backend.container("colleague").is_some());
+    assert_eq!(backend.images(), vec!["colleague-build", "python:3.12"]);
+    assert_eq!(backend.calls(), vec!["prune System Managed"]);
+}
+
+#[tokio::test]
+async fn test_clean_dry_run_removes_nothing() {
+    let backend = FakeBackend::new()
+        .with_container(
+            FakeContainer::new("demo-app-1", "demo-app").label(compose::MANAGED_LABEL, "true"),
+        )
+        .with_labelled_image("demo-app", compose::MANAGED_LABEL, "true")
+        .with_image("python:3.12");
+
+    let options = clean::CleanOptions {
+        all: true,
+        dry_run: true,
+        ..Default::default()
+    };
+    clean::execute(options, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    assert!(backend.container("demo-app-1").is_some());
+    assert_eq!(backend.images(), vec!["demo-app", "python:3.12"]);
+    assert_eq!(backend.calls(), vec!["disk_usage"]);
+}
+
+#[tokio::test]
+async fn test_clean_volumes_removes_listed_named_volumes() {
+    let volume = |name: &str, managed: bool| VolumeUsage {
+        name: name.to_string(),
+        labels: if managed {
+            [(compose::MANAGED_LABEL.to_string(), "true".to_string())].into()
+        } else {
+            Default::default()
+        },
+        size: None,
+    };
+    let backend = FakeBackend::new()
+        .with_volume(volume("demo_data", true))
+        .with_volume(volume("colleague_data", false));
+
+    // 命名卷也会被删除，与 dry-run 列出的一致
+    let usage = backend.disk_usage().unwrap();
+    let listed: Vec<String> = usage
+        .prune_candidates(PruneTarget::Volumes, PruneScope::Managed)
+        .into_iter()
+        .map(|candidate| candidate.name)
+        .collect();
+    assert_eq!(listed, vec!["demo_data"]);
+
+    let options = clean::CleanOptions {
+        volumes: true,
+        yes: true,
+        ..Default::default()
+    };
+    clean::execute(options, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    assert_eq!(backend.volumes(), vec!["colleague_data"]);
+    assert_eq!(
+        backend.calls(),
+        vec![
+            "disk_usage",
+            "prune Volumes Managed",
+            "disk_usage",
+            "remove_volume demo_data"
+        ]
+    );
+
+    // 全局清理只删除匿名卷，保留其他项目的命名卷
+    let anonymous = "0".repeat(64);
+    let backend = backend.with_volume(volume(&anonymous, false));
+    let options = clean::CleanOptions {
+        volumes: true,
+        global: true,
+        yes: true,
+        ..Default::default()
+    };
+    clean::execute(options, demo_config(), &backend)
+        .await
+        .unwrap();
+    assert_eq!(backend.volumes(), vec!["colleague_data"]);
+}
+
+/// 登记环境，并添加带有环境标签的容器和镜像
+fn labelled_environment(backend: FakeBackend, project: &ComposeProject, name: &str) -> FakeBackend {
+    let mut registry = Registry::load().unwrap();
+    registry.register(RegistryEntry {
+        project_dir: project.root.clone(),
+        environment: project.environment.clone(),
+        container_name: name.to_string(),
+        template: "node22".to_string(),
+        ports: Vec::new(),
+        created_at: chrono::Utc::now(),
+    });
+    registry.save().unwrap();
+
+    let root = project.root.to_string_lossy().to_string();
+    let image = format!("{}-app", name);
+    backend
+        .with_service(
+            FakeContainer::new(name, &image)
+                .service("app")
+                .label(compose::MANAGED_LABEL, "true")
+                .label(compose::PROJECT_LABEL, &root)
+                .label(compose::ENVIRONMENT_LABEL, &project.environment),
+        )
+        .with_labelled_image(&image, compose::PROJECT_LABEL, &root)
+        .with_labelled_image(&image, compose::ENVIRONMENT_LABEL, &project.environment)
+}
+
+#[tokio::test]
+async fn test_destroy_named_environment_keeps_others() {
+    let project = ProjectDir::new().await.with_compose_project();
+    std::fs::write(
+        project.path().join(".gitignore"),
+        "target/\n\n# MDDE 配置目录\n.mdde/\n",
+    )
+    .unwrap();
+    let default = ComposeProject::current().unwrap();
+    let dev = ComposeProject::in_environment(&default.root, "dev");
+    std::fs::create_dir_all(dev.compose_file.parent().unwrap()).unwrap();
+    std::fs::write(&dev.compose_file, "services: {}\n").unwrap();
+    std::fs::write(&dev.env_file, "container_name=dev\n").unwrap();
+    project::set_default_environment(&default.root, "dev").unwrap();
+
+    let backend = labelled_environment(FakeBackend::new(), &default, "demo");
+    let backend = labelled_environment(backend, &dev, "dev").with_image("python:3.12");
+    backend.compose_up(&dev, true).unwrap();
+
+    destroy::execute(false, true, true, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    let calls = backend.calls();
+    assert!(
+        calls.contains(&"compose_down volumes=true".to_string()),
+        "{:?}",
+        calls
+    );
+    assert!(
+        calls.contains(&"remove_image dev-app force=true".to_string()),
+        "{:?}",
+        calls
+    );
+    assert_eq!(backend.images(), vec!["demo-app", "python:3.12"]);
+
+    // 只删除 dev 环境的目录，并打包到项目根目录
+    assert!(!dev.compose_file.parent().unwrap().exists());
+    assert!(default.compose_file.exists());
+    assert_eq!(project::environments(&default.root), vec!["default"]);
+    assert_eq!(project::default_environment(&default.root).unwrap(), None);
+    let archives: Vec<_> = std::fs::read_dir(project.path())
+        .unwrap()
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("mdde-dev-") && name.ends_with(".tar"))
+        .collect();
+    assert_eq!(archives.len(), 1);
+
+    let registry = Registry::load_from(&project.registry_file()).unwrap();
+    assert_eq!(registry.environments.len(), 1);
+    assert_eq!(registry.environments[0].environment, "default");
+    assert!(std::fs::read_to_string(project.path().join(".gitignore"))
+        .unwrap()
+        .contains(".mdde/"));
+}
+
+#[tokio::test]
+async fn test_destroy_last_environment_removes_mdde_dir() {
+    let project = ProjectDir::new().await.with_compose_project();
+    std::fs::write(
+        project.path().join(".gitignore"),
+        "target/\n\n# MDDE 配置目录\n.mdde/\n",
+    )
+    .unwrap();
+    let default = ComposeProject::current().unwrap();
+    let backend = labelled_environment(FakeBackend::new(), &default, "demo");
+
+    // 保留配置时只删除容器和镜像
+    destroy::execute(true, false, true, demo_config(), &backend)
+        .await
+        .unwrap();
+    assert!(backend.images().is_empty());
+    assert!(default.compose_file.exists());
+    assert_eq!(
+        Registry::load_from(&project.registry_file())
+            .unwrap()
+            .environments
+            .len(),
+        1
+    );
+
+    destroy::execute(false, false, true, demo_config(), &backend)
+        .await
+        .unwrap();
+    assert!(!project.path().join(".mdde").exists());
+    assert_eq!(
+        std::fs::read_to_string(project.path().join(".gitignore")).unwrap(),
+        "target/\n"
+    );
+    assert!(Registry::load_from(&project.registry_file())
+        .unwrap()
+        .environments
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_destroy_refuses_foreign_resources_in_compose_project() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let default = ComposeProject::current().unwrap();
+    let backend = labelled_environment(FakeBackend::new(), &default, "demo").with_container(
+        FakeContainer::new("stranger", "redis")
+            .service("cache")
+            .label(COMPOSE_PROJECT_LABEL, &default.name()),
+    );
+
+    let result = destroy::execute(false, false, true, demo_config(), &backend).await;
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("stranger"), "{}", error);
+    assert!(!backend
+        .calls()
+        .iter()
+        .any(|call| call.starts_with("compose_down")));
+    assert!(default.compose_file.exists());
+}
+
+#[tokio::test]
+async fn test_destroy_removes_leftover_networks() {
+    let _project = ProjectDir::new().await.with_compose_project();
+    let default = ComposeProject::current().unwrap();
+    let network = |name: &str, compose_project: Option<&str>| {
+        let mut labels: std::collections::HashMap<String, String> = [
+            (compose::MANAGED_LABEL.to_string(), "true".to_string()),
+            (
+                compose::PROJECT_LABEL.to_string(),
+                default.root.to_string_lossy().to_string(),
+            ),
+            (
+                compose::ENVIRONMENT_LABEL.to_string(),
+                default.environment.clone(),
+            ),
+        ]
+        .into();
+        if let Some(compose_project) = compose_project {
+            labels.insert(
+                COMPOSE_PROJECT_LABEL.to_string(),
+                compose_project.to_string(),
+            );
+        }
+        NetworkUsage {
+            id: name.to_string(),
+            name: name.to_string(),
+            labels,
+        }
+    };
+    let backend = labelled_environment(FakeBackend::new(), &default, "demo")
+        .with_network(network("demo_default", Some(&default.name())))
+        .with_network(network("app_default", None))
+        .with_network(NetworkUsage {
+            id: "shared".to_string(),
+            name: "shared".to_string(),
+            labels: Default::default(),
+        });
+
+    destroy::execute(false, false, true, demo_config(), &backend)
+        .await
+        .unwrap();
+
+    // compose down 删除项目内的网络，旧版本留下的网络逐个删除，其他网络保持不变
+    assert_eq!(backend.networks(), vec!["shared"]);
+    let calls = backend.calls();
+    assert!(
+        calls.contains(&"remove_network app_default".to_string()),
+        "{:?}",
+        calls
+    );
+    assert!(
+        !calls.contains(&"remove_network demo_default".to_string()),
+        "{:?}",
+        calls
+    );
+}
+
+#[tokio::test]
+async fn test_wait_until_ready_checks_app_port_on_primary_service() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let compose_project = ComposeProject::new(project.path());
+    let timeout = Duration::from_millis(50);
+
+    // 还没有服务容器时不能视为就绪
+    let error = start::wait_until_ready(&FakeBackend::new(), &compose_project, None, timeout)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MddeError::ServicesNotReady(_)), "{}", error);
+
+    let backend = FakeBackend::new()
+        .with_container(
+            FakeContainer::new("demo-app-1", "node:22")
+                .service("app")
+                .running(),
+        )
+        .with_container(
+            FakeContainer::new("demo-db-1", "postgres:16")
+                .service("db")
+                .running(),
+        );
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    start::wait_until_ready(
+        &backend,
+        &compose_project,
+        Some(("app".to_string(), port)),
+        timeout,
+    )
+    .await
+    .unwrap();
+
+    drop(listener);
+    let error = start::wait_until_ready(
+        &backend,
+        &compose_project,
+        Some(("app".to_string(), port)),
+        timeout,
+    )
+    .await
+    .unwrap_err();
+    match error {
+        MddeError::ServicesNotReady(services) => {
+            assert_eq!(services, [format!("app (port {})", port)])
+        }
+        other => panic!("{}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_status_output_formats() {
+    let project = ProjectDir::new().await.with_compose_project();
+    let backend = FakeBackend::new()
+        .with_container(
+            FakeContainer::new("demo-web-1", "nginx:1.27")
+                .service("web")
+                .running()
+                .health("healthy")
+                .port(8080, 80)
+                .port(8443, 443),
+        )
+        .with_container(FakeContainer::new("demo-db-1", "postgres:16").service("db"))
+        .with_container(FakeContainer::new("unrelated", "busybox").running());
+    let services =
+        backend::compose_services(&backend, &ComposeProject::new(project.path())).unwrap();
+
+    assert_eq!(services.len(), 2);
+    assert_eq!(services[0].service, "db");
+    assert_eq!(services[0].state, "exited");
+    assert_eq!(services[1].ports.len(), 2);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&status::render(&services, OutputFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["status"], "success");
+    assert_eq!(json["data"]["total"], 2);
+    assert_eq!(json["data"]["containers"][1]["health"], "healthy");
+    assert_eq!(json["data"]["containers"][1]["ports"][1]["host_port"], 8443);
+
+    let yaml = status::render(&services, OutputFormat::Yaml).unwrap();
+    assert!(yaml.contains("total: 2"));
+    assert!(yaml.contains("image: postgres:16"));
+
+    let table = status::render(&services, OutputFormat::Table).unwrap();
+    assert!(table.contains("CONTAINER ID"));
+    assert!(table.contains("0.0.0.0:8080->80/tcp, 0.0.0.0:8443->443/tcp"));
+}
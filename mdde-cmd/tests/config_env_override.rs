@@ -1,4 +1,6 @@
+use mdde::compose::ComposeFlavor;
 use mdde::config::Config;
+use mdde::container_engine::ContainerEngine;
 use std::fs;
 use std::path::{Path, PathBuf};
 use tempfile::tempdir;
@@ -129,6 +131,7 @@ app_port=5000
 }
 
 #[tokio::test]
+#[allow(clippy::field_reassign_with_default)]
 async fn test_config_save_and_load() {
     let temp_dir = tempdir().unwrap();
     let current_dir = std::env::current_dir().unwrap();
@@ -149,8 +152,11 @@ async fn test_config_save_and_load() {
     let mut test_config = Config::default();
     test_config.host = "http://test-server:9000".to_string();
     test_config.container_name = Some("test-container".to_string());
-    test_config.app_port = Some(5000);
+    test_config.app_port = Some(5000.into());
+    test_config.ports = vec!["127.0.0.1:5353:53/udp".parse().unwrap()];
     test_config.workspace = Some(PathBuf::from("./test-workspace"));
+    test_config.compose = Some(ComposeFlavor::Standalone);
+    test_config.engine = Some(ContainerEngine::Podman);
 
     // 保存配置
     test_config.save().await.unwrap();
@@ -167,11 +173,17 @@ async fn test_config_save_and_load() {
         loaded_config.container_name,
         Some("test-container".to_string())
     );
-    assert_eq!(loaded_config.app_port, Some(5000));
+    assert_eq!(loaded_config.app_port, Some(5000.into()));
+    assert_eq!(
+        loaded_config.ports,
+        vec!["127.0.0.1:5353:53/udp".parse().unwrap()]
+    );
     assert_eq!(
         loaded_config.workspace,
         Some(PathBuf::from("./test-workspace"))
     );
+    assert_eq!(loaded_config.compose, Some(ComposeFlavor::Standalone));
+    assert_eq!(loaded_config.engine, Some(ContainerEngine::Podman));
 
     // 清理
     if Path::new(".mdde").exists() {
@@ -13,6 +13,7 @@ async fn test_config_default() {
 }
 
 #[tokio::test]
+#[allow(clippy::field_reassign_with_default)]
 async fn test_config_save_and_load() {
     let mut config = Config::default();
     config.host = "http://localhost:3000".to_string();